@@ -0,0 +1,22 @@
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Stages `paths`, given relative to the working directory.
+pub fn add<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    paths: &[String],
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let paths = paths.iter().map(String::as_str).collect::<Vec<&str>>();
+    repository.index_add(&paths)?;
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Lists branches, marking the current one with `*`. `verbose` adds the commit
+/// and the tracked ref with how far the branch is ahead of and behind it.
+pub fn branch_list<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    verbose: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let branches = repository.branch_list()?;
+    let width = branches.iter().map(|b| b.name.len()).max().unwrap_or(0);
+    for branch in branches {
+        let marker = match branch.current {
+            true => '*',
+            false => ' ',
+        };
+        if !verbose {
+            writeln!(out, "{} {}", marker, branch.name)?;
+            continue;
+        }
+        let commit = repository.commit_read(&branch.hash)?;
+        let upstream = match &branch.upstream {
+            Some(upstream) => {
+                let counts = match repository.ref_resolve(upstream)? {
+                    Some(hash) => match repository.ahead_behind(&branch.hash, &hash)? {
+                        (0, 0) => String::new(),
+                        (ahead, 0) => format!(": ahead {}", ahead),
+                        (0, behind) => format!(": behind {}", behind),
+                        (ahead, behind) => format!(": ahead {}, behind {}", ahead, behind),
+                    },
+                    None => ": gone".to_string(),
+                };
+                format!("[{}{}] ", upstream, counts)
+            }
+            None => String::new(),
+        };
+        writeln!(
+            out,
+            "{} {:width$} {} {}{}",
+            marker,
+            branch.name,
+            &branch.hash[..7],
+            upstream,
+            commit.message.lines().next().unwrap_or_default(),
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+pub fn branch_create<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    name: &str,
+    start: &str,
+    force: bool,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    repository.branch_create(name, start, force)?;
+    Ok(())
+}
+
+/// Renames `old`, or the current branch when it's `None`, to `new`.
+pub fn branch_rename<GCDA, GCDOA>(
+    repository: &mut GaalRepository<GCDA, GCDOA>,
+    old: Option<&str>,
+    new: &str,
+    force: bool,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let old = match old {
+        Some(old) => old.to_string(),
+        None => current_branch(repository)?,
+    };
+    repository.branch_rename(&old, new, force)?;
+    Ok(())
+}
+
+pub fn branch_delete<GCDA, GCDOA>(
+    repository: &mut GaalRepository<GCDA, GCDOA>,
+    name: &str,
+    force: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let hash = repository.branch_delete(name, force)?;
+    writeln!(out, "Deleted branch {} (was {}).", name, &hash[..7])?;
+    Ok(())
+}
+
+/// Sets or, when `upstream` is `None`, removes what `name` (or the current
+/// branch) tracks.
+pub fn branch_upstream<GCDA, GCDOA>(
+    repository: &mut GaalRepository<GCDA, GCDOA>,
+    name: Option<&str>,
+    upstream: Option<&str>,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => current_branch(repository)?,
+    };
+    match upstream {
+        Some(upstream) => {
+            repository.branch_set_upstream(&name, upstream)?;
+            writeln!(out, "branch '{}' set up to track '{}'.", name, upstream)?;
+        }
+        None => repository.branch_unset_upstream(&name)?,
+    }
+    Ok(())
+}
+
+fn current_branch<GCDA, GCDOA>(repository: &GaalRepository<GCDA, GCDOA>) -> Result<String, Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    repository
+        .head_branch()?
+        .ok_or_else(|| Error::InvalidArgument("HEAD is detached".to_string()))
+}
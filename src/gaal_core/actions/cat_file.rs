@@ -0,0 +1,26 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+pub fn cat_file<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    fmt: &str,
+    object: &str,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let hash = repository.object_find(object, Some(fmt))?;
+    let object = repository.object_read(hash)?;
+    out.write_all(&object.serialize()?)?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::{checkout::CheckoutTarget, default::GaalRepository},
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Switches the working directory, the index and `HEAD` to `revision`.
+pub fn checkout<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    revision: &str,
+    force: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    match repository.checkout(revision, force)? {
+        CheckoutTarget::Branch(name) => writeln!(out, "Switched to branch '{}'", name)?,
+        CheckoutTarget::Detached(hash) => {
+            let commit = repository.commit_read(&hash)?;
+            writeln!(
+                out,
+                "HEAD is now at {} {}",
+                &hash[..7],
+                commit.message.lines().next().unwrap_or_default()
+            )?
+        }
+        CheckoutTarget::Tree(hash) => writeln!(out, "Checked out tree {}", &hash[..7])?,
+    }
+    Ok(())
+}
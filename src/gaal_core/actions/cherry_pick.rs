@@ -0,0 +1,75 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::{
+        default::GaalRepository,
+        sequencer::{PickKind, PickOptions, PickOutcome},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::{commit::write_commit_summary, merge::write_conflicts, Error};
+
+/// What a `cherry-pick` or `revert` invocation asks for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PickAction {
+    /// Apply this commit.
+    Start(String, PickOptions),
+    /// Record the resolved conflicts of the operation in progress.
+    Continue,
+    /// Give up the operation in progress.
+    Abort,
+}
+
+/// Applies the changes of a commit on top of the current branch, or concludes
+/// the cherry-pick in progress.
+pub fn cherry_pick<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    action: &PickAction,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    pick(repository, PickKind::CherryPick, action, out)
+}
+
+pub(super) fn pick<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    kind: PickKind,
+    action: &PickAction,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let (revision, options) = match action {
+        PickAction::Start(revision, options) => (revision, options),
+        PickAction::Continue => {
+            let hash = repository.pick_continue(kind)?;
+            return write_commit_summary(repository, &hash, out);
+        }
+        PickAction::Abort => return Ok(repository.pick_abort(kind)?),
+    };
+    let outcome = match kind {
+        PickKind::CherryPick => repository.cherry_pick(revision, options)?,
+        PickKind::Revert => repository.revert(revision, options)?,
+    };
+    match outcome {
+        PickOutcome::Committed(hash) => write_commit_summary(repository, &hash, out),
+        PickOutcome::Conflicts(conflicts) => {
+            write_conflicts(&conflicts, out)?;
+            let hash = repository.object_peel_to(&repository.rev_parse(revision)?, "commit")?;
+            let commit = repository.commit_read(&hash)?;
+            let subject = commit.message.lines().next().unwrap_or_default();
+            Err(Error::PickConflicts(
+                kind.name(),
+                format!("{}... {}", &hash[..7], subject),
+            ))
+        }
+    }
+}
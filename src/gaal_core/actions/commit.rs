@@ -0,0 +1,65 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Records the staging area as a new commit on the current branch. Without a
+/// message, the one prepared by a merge in progress is used.
+pub fn commit<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    message: Option<&str>,
+    allow_empty: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let message = match message {
+        Some(message) => message.to_string(),
+        None => repository.merge_message()?.unwrap_or_default(),
+    };
+    if message.trim().is_empty() {
+        return Err(Error::InvalidArgument(
+            "Aborting commit due to empty commit message".to_string(),
+        ));
+    }
+
+    let hash = repository.commit_index(&message, allow_empty)?;
+    write_commit_summary(repository, &hash, out)
+}
+
+/// Prints `[<branch> <short id>] <subject>` for a commit that was just recorded.
+pub(super) fn write_commit_summary<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    hash: &str,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let commit = repository.commit_read(hash)?;
+    let branch = repository
+        .head_branch()?
+        .unwrap_or_else(|| "detached HEAD".to_string());
+    let root = match commit.parents.is_empty() {
+        true => " (root-commit)",
+        false => "",
+    };
+    writeln!(
+        out,
+        "[{}{} {}] {}",
+        branch,
+        root,
+        &hash[..7],
+        commit.message.lines().next().unwrap_or_default()
+    )?;
+    Ok(())
+}
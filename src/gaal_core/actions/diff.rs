@@ -0,0 +1,441 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::{
+        repository::{
+            default::GaalRepository,
+            diff::{DiffEntry, DiffOptions, DiffSide, DiffStatus},
+        },
+        text_diff::{diff_lines, edit_counts, hunks, is_binary, split_lines, DiffAlgorithm, Edit},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Widest `--stat` bar, longer ones are scaled down.
+const STAT_GRAPH_WIDTH: usize = 50;
+
+/// Computes the changes `git diff` would show for `revisions`: the index
+/// against the working directory without any, a tree against the working
+/// directory (or the index with `cached`) with one, and two trees with two or
+/// with a range `A..B`.
+pub fn diff_entries<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    revisions: &[&str],
+    cached: bool,
+    options: &DiffOptions,
+) -> Result<Vec<DiffEntry>, Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let tree = |revision: &str| repository.object_find(revision, Some("tree"));
+    let revisions = match revisions {
+        [range] if range.contains("..") && !range.contains("...") => {
+            let (a, b) = range.split_once("..").unwrap_or_default();
+            let or_head = |name: &'_ str| {
+                if name.is_empty() {
+                    "HEAD".to_string()
+                } else {
+                    name.to_string()
+                }
+            };
+            vec![or_head(a), or_head(b)]
+        }
+        _ => revisions
+            .iter()
+            .map(|revision| revision.to_string())
+            .collect(),
+    };
+
+    let entries = match (revisions.as_slice(), cached) {
+        ([], false) => repository.diff_index_to_work(options)?,
+        ([], true) => {
+            let head = match repository.ref_resolve("HEAD")? {
+                Some(_) => Some(tree("HEAD")?),
+                None => None,
+            };
+            repository.diff_tree_to_index(head.as_deref(), options)?
+        }
+        ([old], true) => repository.diff_tree_to_index(Some(&tree(old)?), options)?,
+        ([old], false) => repository.diff_tree_to_work(Some(&tree(old)?), options)?,
+        ([old, new], false) => {
+            repository.diff_trees(Some(&tree(old)?), Some(&tree(new)?), options)?
+        }
+        _ => {
+            return Err(Error::InvalidArgument(
+                "Expected at most two revisions, or one with --cached".to_string(),
+            ))
+        }
+    };
+    Ok(entries)
+}
+
+/// Prints the status and path of each change, both paths for renames and
+/// copies.
+pub fn diff_name_status(entries: &[DiffEntry], out: &mut impl Write) -> Result<(), Error> {
+    for entry in entries {
+        match (&entry.status, &entry.old, &entry.new) {
+            (DiffStatus::Renamed(_) | DiffStatus::Copied(_), Some(old), Some(new)) => {
+                writeln!(out, "{}\t{}\t{}", entry.status.code(), old.path, new.path)?
+            }
+            _ => writeln!(out, "{}\t{}", entry.status.code(), entry.path())?,
+        }
+    }
+    Ok(())
+}
+
+/// How changes are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// Unified diff of each file.
+    #[default]
+    Patch,
+    /// Changed lines of each file as a histogram, and their totals.
+    Stat,
+    /// Added and deleted lines of each file, tab separated.
+    NumStat,
+    /// Status and path of each file.
+    NameStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PatchOptions {
+    pub format: DiffFormat,
+    /// Unchanged lines shown around each change.
+    pub context: usize,
+    pub algorithm: DiffAlgorithm,
+}
+
+impl Default for PatchOptions {
+    fn default() -> Self {
+        Self {
+            format: DiffFormat::default(),
+            context: 3,
+            algorithm: DiffAlgorithm::default(),
+        }
+    }
+}
+
+/// Contents of both sides of a change, empty for a missing side.
+struct FileContents {
+    old: Vec<u8>,
+    new: Vec<u8>,
+    binary: bool,
+}
+
+impl FileContents {
+    fn read<GCDA, GCDOA>(
+        repository: &GaalRepository<GCDA, GCDOA>,
+        entry: &DiffEntry,
+    ) -> Result<Self, Error>
+    where
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    {
+        let read = |side: &Option<DiffSide>| match side {
+            Some(side) => repository.diff_content(side),
+            None => Ok(Vec::new()),
+        };
+        let (old, new) = (read(&entry.old)?, read(&entry.new)?);
+        let binary = is_binary(&old) || is_binary(&new);
+        Ok(Self { old, new, binary })
+    }
+
+    fn edits(&self, algorithm: DiffAlgorithm) -> Vec<Edit> {
+        diff_lines(&split_lines(&self.old), &split_lines(&self.new), algorithm)
+    }
+}
+
+/// Prints `entries` in `options.format`.
+pub fn diff_write<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    entries: &[DiffEntry],
+    options: &PatchOptions,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    match options.format {
+        DiffFormat::Patch => diff_patch(repository, entries, options, out),
+        DiffFormat::Stat | DiffFormat::NumStat => diff_stat(repository, entries, options, out),
+        DiffFormat::NameStatus => diff_name_status(entries, out),
+    }
+}
+
+/// Prints a unified diff of each change, in the format of `git diff` so that
+/// patch tools can apply it. A file changing between a regular file and a
+/// symlink is shown as deleted and added again.
+pub fn diff_patch<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    entries: &[DiffEntry],
+    options: &PatchOptions,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    for entry in entries {
+        match entry.status {
+            DiffStatus::TypeChanged => {
+                for (status, old, new) in [
+                    (DiffStatus::Deleted, entry.old.clone(), None),
+                    (DiffStatus::Added, None, entry.new.clone()),
+                ] {
+                    let entry = DiffEntry { status, old, new };
+                    write_file_patch(repository, &entry, options, out)?;
+                }
+            }
+            _ => write_file_patch(repository, entry, options, out)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_file_patch<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    entry: &DiffEntry,
+    options: &PatchOptions,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let old_path = entry.old.as_ref().map_or(entry.path(), |side| &side.path);
+    let new_path = entry.new.as_ref().map_or(entry.path(), |side| &side.path);
+    writeln!(out, "diff --git a/{} b/{}", old_path, new_path)?;
+    match (&entry.old, &entry.new) {
+        (None, Some(new)) => writeln!(out, "new file mode {:06o}", new.mode)?,
+        (Some(old), None) => writeln!(out, "deleted file mode {:06o}", old.mode)?,
+        (Some(old), Some(new)) if old.mode != new.mode => {
+            writeln!(out, "old mode {:06o}", old.mode)?;
+            writeln!(out, "new mode {:06o}", new.mode)?;
+        }
+        _ => {}
+    }
+    match entry.status {
+        DiffStatus::Renamed(score) | DiffStatus::Copied(score) => {
+            let kind = match entry.status {
+                DiffStatus::Renamed(_) => "rename",
+                _ => "copy",
+            };
+            writeln!(out, "similarity index {}%", score)?;
+            writeln!(out, "{} from {}", kind, old_path)?;
+            writeln!(out, "{} to {}", kind, new_path)?;
+        }
+        _ => {}
+    }
+
+    let id = |side: &Option<DiffSide>| match side {
+        Some(side) => side.id[..7].to_string(),
+        None => "0".repeat(7),
+    };
+    let (old_id, new_id) = (id(&entry.old), id(&entry.new));
+    if old_id == new_id {
+        return Ok(());
+    }
+    match (&entry.old, &entry.new) {
+        (Some(old), Some(new)) if old.mode == new.mode => {
+            writeln!(out, "index {}..{} {:06o}", old_id, new_id, new.mode)?
+        }
+        _ => writeln!(out, "index {}..{}", old_id, new_id)?,
+    }
+
+    let contents = FileContents::read(repository, entry)?;
+    let old_name = match entry.old {
+        Some(_) => format!("a/{}", old_path),
+        None => "/dev/null".to_string(),
+    };
+    let new_name = match entry.new {
+        Some(_) => format!("b/{}", new_path),
+        None => "/dev/null".to_string(),
+    };
+    if contents.binary {
+        writeln!(out, "Binary files {} and {} differ", old_name, new_name)?;
+        return Ok(());
+    }
+    writeln!(out, "--- {}", old_name)?;
+    writeln!(out, "+++ {}", new_name)?;
+
+    let (old_lines, new_lines) = (split_lines(&contents.old), split_lines(&contents.new));
+    let edits = diff_lines(&old_lines, &new_lines, options.algorithm);
+    for hunk in hunks(&edits, options.context) {
+        writeln!(out, "{}", hunk.header())?;
+        for edit in hunk.edits {
+            let (prefix, line) = match edit {
+                Edit::Equal(i, _) => (b' ', old_lines[i]),
+                Edit::Delete(i) => (b'-', old_lines[i]),
+                Edit::Insert(j) => (b'+', new_lines[j]),
+            };
+            out.write_all(&[prefix])?;
+            out.write_all(line)?;
+            if !line.ends_with(b"\n") {
+                out.write_all(b"\n\\ No newline at end of file\n")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the lines added and deleted in each file, as `--numstat` numbers or
+/// as a `--stat` histogram followed by the totals.
+pub fn diff_stat<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    entries: &[DiffEntry],
+    options: &PatchOptions,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    // Path, then added and deleted lines, or the sizes of binary files.
+    let mut stats = Vec::new();
+    for entry in entries {
+        let path = match (&entry.status, &entry.old, &entry.new) {
+            (DiffStatus::Renamed(_) | DiffStatus::Copied(_), Some(old), Some(new)) => {
+                format!("{} => {}", old.path, new.path)
+            }
+            _ => entry.path().to_string(),
+        };
+        let contents = FileContents::read(repository, entry)?;
+        let counts = match contents.binary {
+            true => Err((contents.old.len(), contents.new.len())),
+            false => Ok(edit_counts(&contents.edits(options.algorithm))),
+        };
+        stats.push((path, counts));
+    }
+
+    if options.format == DiffFormat::NumStat {
+        for (path, counts) in stats {
+            match counts {
+                Ok((added, deleted)) => writeln!(out, "{}\t{}\t{}", added, deleted, path)?,
+                Err(_) => writeln!(out, "-\t-\t{}", path)?,
+            }
+        }
+        return Ok(());
+    }
+    if stats.is_empty() {
+        return Ok(());
+    }
+
+    let path_width = stats.iter().map(|(path, _)| path.len()).max().unwrap_or(0);
+    let changes = |counts: &Result<(usize, usize), (usize, usize)>| match counts {
+        Ok((added, deleted)) => added + deleted,
+        Err(_) => 0,
+    };
+    let most = stats
+        .iter()
+        .map(|(_, counts)| changes(counts))
+        .max()
+        .unwrap_or(0);
+    let count_width = most.to_string().len().max(3);
+    let (mut total_added, mut total_deleted) = (0, 0);
+    for (path, counts) in &stats {
+        match counts {
+            Ok((added, deleted)) => {
+                total_added += added;
+                total_deleted += deleted;
+                // Keeps at least one character for any change.
+                let scale = |n: usize| match most > STAT_GRAPH_WIDTH {
+                    true if n > 0 => (n * STAT_GRAPH_WIDTH / most).max(1),
+                    true => 0,
+                    false => n,
+                };
+                let line = format!(
+                    " {:path_width$} | {:>count_width$} {}{}",
+                    path,
+                    added + deleted,
+                    "+".repeat(scale(*added)),
+                    "-".repeat(scale(*deleted)),
+                );
+                writeln!(out, "{}", line.trim_end())?;
+            }
+            Err((old_size, new_size)) => writeln!(
+                out,
+                " {:path_width$} | {:>count_width$} {} -> {} bytes",
+                path, "Bin", old_size, new_size
+            )?,
+        }
+    }
+
+    let plural = |n: usize, word: &str| match n {
+        1 => format!("{} {}", n, word),
+        _ => format!("{} {}s", n, word),
+    };
+    let mut summary = vec![format!(" {} changed", plural(stats.len(), "file"))];
+    if total_added > 0 || total_deleted == 0 {
+        summary.push(format!(" {}(+)", plural(total_added, "insertion")));
+    }
+    if total_deleted > 0 || total_added == 0 {
+        summary.push(format!(" {}(-)", plural(total_deleted, "deletion")));
+    }
+    writeln!(out, "{}", summary.join(","))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_diff_stat {
+    use super::{diff_entries, diff_write, DiffFormat, PatchOptions};
+    use crate::gaal_core::{
+        internals::repository::diff::DiffOptions,
+        provider::memory::{repository, write, MemoryCoreDirectory},
+    };
+
+    #[test]
+    fn stat_output() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "1\n2\n3\n4\n5\n6\n7\n8\n");
+        write("work/b.txt", "a\nb\nc\nd\n");
+        write("work/image.bin", "\0\0\0");
+        repository
+            .index_add(&["a.txt", "b.txt", "image.bin"])
+            .unwrap();
+        repository.commit_index("First", false).unwrap();
+
+        repository.index_remove(&["a.txt"], false, false).unwrap();
+        write("work/moved/a.txt", "1\n2\n3\n4\n5\n6\n7\nx\n");
+        write("work/b.txt", "a\nB\nc\nd\ne\nf\n");
+        write("work/image.bin", "\0\0\0\0\0");
+        repository
+            .index_add(&["moved/a.txt", "b.txt", "image.bin"])
+            .unwrap();
+
+        let diff_options = DiffOptions {
+            renames: true,
+            ..DiffOptions::default()
+        };
+        let entries = diff_entries(&repository, &[], true, &diff_options).unwrap();
+        let output = |format| {
+            let options = PatchOptions {
+                format,
+                ..PatchOptions::default()
+            };
+            let mut out = Vec::new();
+            diff_write(&repository, &entries, &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(
+            output(DiffFormat::Stat),
+            concat!(
+                " b.txt                |   4 +++-\n",
+                " image.bin            | Bin 3 -> 5 bytes\n",
+                " a.txt => moved/a.txt |   2 +-\n",
+                " 3 files changed, 4 insertions(+), 2 deletions(-)\n",
+            )
+        );
+        assert_eq!(
+            output(DiffFormat::NumStat),
+            "3\t1\tb.txt\n-\t-\timage.bin\n1\t1\ta.txt => moved/a.txt\n"
+        );
+    }
+}
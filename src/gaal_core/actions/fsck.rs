@@ -0,0 +1,24 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+pub fn fsck<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    for issue in repository.fsck()? {
+        writeln!(out, "{}", issue)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Hashes `data` as an object of type `fmt` with the object format of `repository`
+/// (SHA-256 outside of a repository), storing it when `write` is set.
+pub fn hash_object<GCDA, GCDOA>(
+    repository: Option<&GaalRepository<GCDA, GCDOA>>,
+    write: bool,
+    fmt: &str,
+    data: Vec<u8>,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let format = repository
+        .map(|repository| repository.object_format)
+        .unwrap_or_default();
+    let object = GCDOA::new_object(fmt.to_string(), data, format)?;
+    let hash = match repository {
+        Some(repository) if write => repository.object_write(object)?,
+        _ => GCDOA::hash(object, format)?.0,
+    };
+    writeln!(out, "{}", hash)?;
+    Ok(())
+}
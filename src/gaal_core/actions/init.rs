@@ -1,2 +1,33 @@
+use std::io::Write;
 
+use crate::gaal_core::{
+    core::{GaalCore, GaalCoreTrait},
+    provider::{
+        directory::{GaalCoreDirectory, GaalCoreDirectoryActions},
+        directory_object::GaalCoreDirectoryObjectsActions,
+        object::hash::GaalHashAlgorithm,
+    },
+};
 
+use super::Error;
+
+pub fn init<GCDA, GCDOA>(
+    core: &GaalCore<GaalCoreDirectory<GCDA, GCDOA>>,
+    path: Vec<GCDA::PathItem>,
+    object_format: GaalHashAlgorithm,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let repository = core.init_with_object_format(path, object_format)?;
+    let gaal = repository
+        .gaal
+        .into_iter()
+        .map(|item| item.into())
+        .collect::<Vec<String>>()
+        .join("/");
+    writeln!(out, "Initialized empty Gaal repository in {}", gaal)?;
+    Ok(())
+}
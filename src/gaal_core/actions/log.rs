@@ -0,0 +1,205 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::{
+        date::format_date,
+        repository::{default::GaalRepository, walk::WalkOptions},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+        object::commit::Commit,
+    },
+};
+
+use super::Error;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Abbreviated id and subject.
+    Oneline,
+    /// Id, parents of merges, author, committer and the whole message.
+    #[default]
+    Full,
+}
+
+/// Columns of a `--graph` drawing, each waiting for the commit it holds.
+#[derive(Default)]
+struct Graph {
+    columns: Vec<String>,
+}
+
+impl Graph {
+    /// Places `hash`, returning the line holding it and the lines to draw after
+    /// it to connect its parents.
+    fn next(&mut self, hash: &str, parents: &[String]) -> (String, Vec<String>) {
+        let index = match self.columns.iter().position(|column| column == hash) {
+            Some(index) => index,
+            None => {
+                self.columns.push(hash.to_string());
+                self.columns.len() - 1
+            }
+        };
+        let line = (0..self.columns.len())
+            .map(|i| if i == index { "*" } else { "|" })
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        let mut after = Vec::new();
+        let new_parents = parents
+            .iter()
+            .skip(1)
+            .filter(|parent| !self.columns.contains(parent))
+            .cloned()
+            .collect::<Vec<String>>();
+        match parents.first() {
+            Some(first) if !self.columns.contains(first) => self.columns[index] = first.clone(),
+            first => {
+                // Ends this column, joining the one of the first parent if any.
+                self.columns.remove(index);
+                let joined = first.and_then(|first| self.columns.iter().position(|c| c == first));
+                let mut line = vec![' '; (self.columns.len() + 1) * 2];
+                for i in 0..index.min(self.columns.len()) {
+                    line[i * 2] = '|';
+                }
+                match joined {
+                    Some(joined) if joined < index => line[index * 2 - 1] = '/',
+                    Some(_) => line[index * 2] = '|',
+                    None => {}
+                }
+                for i in index + 1..=self.columns.len() {
+                    line[i * 2 - 1] = '/';
+                }
+                after.push(line);
+            }
+        }
+        if !new_parents.is_empty() {
+            // Opens a column right of this one for each other parent.
+            let shift = new_parents.len();
+            let mut line = vec![' '; (self.columns.len() + shift) * 2];
+            for i in 0..=index {
+                line[i * 2] = '|';
+            }
+            for i in index + 1..self.columns.len() + shift {
+                line[i * 2 - 1] = '\\';
+            }
+            let position = (index + 1).min(self.columns.len());
+            for (i, parent) in new_parents.into_iter().enumerate() {
+                self.columns.insert(position + i, parent);
+            }
+            after.push(line);
+        }
+        let after = after
+            .into_iter()
+            .map(|line| line.into_iter().collect::<String>().trim_end().to_string())
+            .filter(|line| line.contains(['|', '/', '\\']) && line != &self.padding())
+            .collect();
+        (line, after)
+    }
+
+    /// Prefix of the lines printed below a commit.
+    fn padding(&self) -> String {
+        vec!["|"; self.columns.len()].join(" ")
+    }
+}
+
+/// Prints `commit` the way `log` does, `first_prefix` and `prefix` being the
+/// graph drawn left of its first and other lines.
+pub(super) fn write_commit(
+    out: &mut impl Write,
+    format: LogFormat,
+    hash: &str,
+    commit: &Commit,
+    first_prefix: &str,
+    connectors: &[String],
+    prefix: &str,
+) -> Result<(), Error> {
+    // Separates the prefix of graph lines from the text.
+    let pad = |prefix: &str| match prefix {
+        "" => String::new(),
+        _ => format!("{} ", prefix),
+    };
+    let subject = commit.message.lines().next().unwrap_or_default();
+    if format == LogFormat::Oneline {
+        writeln!(out, "{}{} {}", pad(first_prefix), &hash[..7], subject)?;
+        for line in connectors {
+            writeln!(out, "{}", line)?;
+        }
+        return Ok(());
+    }
+
+    let mut lines = Vec::new();
+    if commit.parents.len() > 1 {
+        let parents = commit
+            .parents
+            .iter()
+            .map(|parent| &parent[..7])
+            .collect::<Vec<&str>>();
+        lines.push(format!("Merge: {}", parents.join(" ")));
+    }
+    lines.push(format!(
+        "Author: {} <{}>",
+        commit.author.name, commit.author.email
+    ));
+    lines.push(format!(
+        "Date:   {}",
+        format_date(commit.author.time, commit.author.offset)
+    ));
+    if commit.committer != commit.author {
+        lines.push(format!(
+            "Commit: {} <{}>",
+            commit.committer.name, commit.committer.email
+        ));
+    }
+    lines.push(String::new());
+    lines.extend(commit.message.lines().map(|line| format!("    {}", line)));
+    lines.push(String::new());
+
+    writeln!(out, "{}commit {}", pad(first_prefix), hash)?;
+    for line in connectors {
+        writeln!(out, "{}", line)?;
+    }
+    for line in lines {
+        writeln!(out, "{}", format!("{}{}", pad(prefix), line).trim_end())?;
+    }
+    Ok(())
+}
+
+/// Prints the commits selected by `revisions` and `options`.
+pub fn log<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    revisions: &[&str],
+    options: WalkOptions,
+    format: LogFormat,
+    graph: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let first_parent = options.first_parent;
+    let mut drawing = Graph::default();
+    for item in repository.rev_walk(revisions, options)? {
+        let (hash, commit) = item?;
+        if !graph {
+            write_commit(out, format, &hash, &commit, "", &[], "")?;
+            continue;
+        }
+
+        let parents = match first_parent {
+            true => &commit.parents[..commit.parents.len().min(1)],
+            false => &commit.parents[..],
+        };
+        let (line, after) = drawing.next(&hash, parents);
+        write_commit(
+            out,
+            format,
+            &hash,
+            &commit,
+            &line,
+            &after,
+            &drawing.padding(),
+        )?;
+    }
+    Ok(())
+}
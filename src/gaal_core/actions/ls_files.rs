@@ -0,0 +1,36 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Lists staged paths, with their mode, id and stage when `stage` is set.
+pub fn ls_files<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    stage: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    for entry in repository.index_read()?.entries {
+        match stage {
+            true => writeln!(
+                out,
+                "{} {} {}\t{}",
+                entry.mode_string(),
+                entry.id,
+                entry.stage(),
+                entry.path
+            )?,
+            false => writeln!(out, "{}", entry.path)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions,
+        directory_object::GaalCoreDirectoryObjectsActions,
+        object::{tree::Tree, ObjectError},
+    },
+};
+
+use super::Error;
+
+pub fn ls_tree<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    tree: &str,
+    recursive: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let hash = repository.object_find(tree, Some("tree"))?;
+    ls_tree_at(repository, &hash, recursive, "", out)
+}
+
+fn ls_tree_at<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    hash: &str,
+    recursive: bool,
+    prefix: &str,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let object = repository.object_read(hash.to_string())?;
+    let tree = Tree::parse(&object.serialize()?, repository.object_format)?;
+
+    for leaf in tree.leafs {
+        let fmt = match &leaf.mode[0..2] {
+            "04" => "tree",
+            "10" | "12" => "blob",
+            "16" => "commit",
+            _ => {
+                return Err(ObjectError::InvalidData(format!(
+                    "Unknown tree leaf mode `{}`",
+                    leaf.mode
+                ))
+                .into())
+            }
+        };
+
+        let path = format!("{}{}", prefix, leaf.path);
+        if recursive && fmt == "tree" {
+            ls_tree_at(repository, &leaf.sha, recursive, &format!("{}/", path), out)?;
+        } else {
+            writeln!(out, "{} {} {}\t{}", leaf.mode, fmt, leaf.sha, path)?;
+        }
+    }
+
+    Ok(())
+}
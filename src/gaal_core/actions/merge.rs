@@ -0,0 +1,68 @@
+use std::{collections::BTreeMap, io::Write};
+
+use crate::gaal_core::{
+    internals::repository::{
+        default::GaalRepository,
+        merge::{MergeConflict, MergeOptions, MergeOutcome},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::{commit::write_commit_summary, Error};
+
+/// Merges `revision` into the current branch, reporting a fast-forward, the
+/// merge commit or the conflicts left to resolve.
+pub fn merge<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    revision: &str,
+    options: &MergeOptions,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let head = repository.ref_resolve("HEAD")?;
+    match repository.merge(revision, options)? {
+        MergeOutcome::UpToDate => writeln!(out, "Already up to date.")?,
+        MergeOutcome::FastForward(hash) => {
+            match head {
+                Some(head) => writeln!(out, "Updating {}..{}", &head[..7], &hash[..7])?,
+                None => writeln!(out, "Updating to {}", &hash[..7])?,
+            }
+            writeln!(out, "Fast-forward")?;
+        }
+        MergeOutcome::Merged(hash) => write_commit_summary(repository, &hash, out)?,
+        MergeOutcome::Conflicts(conflicts) => {
+            write_conflicts(&conflicts, out)?;
+            return Err(Error::MergeConflicts);
+        }
+    }
+    Ok(())
+}
+
+/// Prints a `CONFLICT` line for each path a merge left unresolved.
+pub(super) fn write_conflicts(
+    conflicts: &BTreeMap<String, MergeConflict>,
+    out: &mut impl Write,
+) -> Result<(), Error> {
+    for (path, conflict) in conflicts {
+        if let Some(work_path) = &conflict.work_path {
+            writeln!(
+                out,
+                "CONFLICT (file/directory): Directory in the way of {}, adding it as {}",
+                path, work_path
+            )?;
+            continue;
+        }
+        let kind = match &conflict.stages {
+            [_, None, _] | [_, _, None] => "modify/delete",
+            [None, _, _] => "add/add",
+            _ => "content",
+        };
+        writeln!(out, "CONFLICT ({}): Merge conflict in {}", kind, path)?;
+    }
+    Ok(())
+}
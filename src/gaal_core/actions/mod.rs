@@ -1,7 +1,49 @@
+use crate::gaal_core::{internals::repository::RepositoryError, provider::object::ObjectError};
+
+pub mod add;
+pub mod branch;
+pub mod cat_file;
+pub mod checkout;
+pub mod cherry_pick;
+pub mod commit;
+pub mod diff;
+pub mod fsck;
+pub mod hash_object;
 pub mod init;
+pub mod log;
+pub mod ls_files;
+pub mod ls_tree;
+pub mod merge;
+pub mod rebase;
+pub mod rev_parse;
+pub mod revert;
+pub mod rm;
+pub mod show;
+pub mod show_ref;
+pub mod stash;
+pub mod status;
+pub mod tag;
+pub mod update_ref;
+pub mod write_tree;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+    #[error(transparent)]
+    Object(#[from] ObjectError),
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("Automatic merge failed; fix conflicts and then commit the result")]
+    MergeConflicts,
+    #[error("Could not {0} {1}; fix conflicts, add them, then continue or abort")]
+    PickConflicts(&'static str, String),
+    #[error("Conflicts while applying the stash; fix them, the stash was kept")]
+    StashConflicts,
+    #[error("Command `{0}` failed; fix the problem, then continue the rebase")]
+    ExecFailed(String),
 }
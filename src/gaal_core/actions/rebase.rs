@@ -0,0 +1,90 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::{
+        default::GaalRepository,
+        rebase::{RebaseHooks, RebaseOptions, RebaseOutcome, RebaseStop},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::{merge::write_conflicts, Error};
+
+/// What a `rebase` invocation asks for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RebaseAction {
+    /// Replay the commits missing from this upstream, the configured one when
+    /// `None`.
+    Start(Option<String>, RebaseOptions),
+    /// Resume after resolving conflicts or amending a commit.
+    Continue,
+    /// Drop the stopped commit and resume.
+    Skip,
+    /// Go back to where the rebase started.
+    Abort,
+    /// Edit the lines left in the todo list.
+    EditTodo,
+}
+
+/// Replays the commits of the current branch onto a new base, or drives the
+/// rebase in progress, reporting where it stopped.
+pub fn rebase<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    action: &RebaseAction,
+    mut hooks: RebaseHooks,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let outcome = match action {
+        RebaseAction::Start(upstream, options) => {
+            repository.rebase(upstream.as_deref(), options, &mut hooks)?
+        }
+        RebaseAction::Continue => repository.rebase_continue(&mut hooks)?,
+        RebaseAction::Skip => repository.rebase_skip(&mut hooks)?,
+        RebaseAction::Abort => return Ok(repository.rebase_abort()?),
+        RebaseAction::EditTodo => return Ok(repository.rebase_edit_todo(&mut hooks.edit_todo)?),
+    };
+    let branch = repository.head_branch()?;
+    match outcome {
+        RebaseOutcome::UpToDate => match branch {
+            Some(branch) => writeln!(out, "Current branch {} is up to date.", branch)?,
+            None => writeln!(out, "HEAD is up to date.")?,
+        },
+        RebaseOutcome::Done(hash) => match branch {
+            Some(branch) => writeln!(
+                out,
+                "Successfully rebased and updated refs/heads/{}.",
+                branch
+            )?,
+            None => writeln!(out, "Successfully rebased to {}.", &hash[..7])?,
+        },
+        RebaseOutcome::Stopped(RebaseStop::Edit(item)) => {
+            writeln!(
+                out,
+                "Stopped at {}... {}",
+                &item.argument[..7],
+                item.subject
+            )?;
+            writeln!(
+                out,
+                "You can amend the commit now, staging the changes, then continue the rebase."
+            )?;
+        }
+        RebaseOutcome::Stopped(RebaseStop::Conflicts(item, conflicts)) => {
+            write_conflicts(&conflicts, out)?;
+            return Err(Error::PickConflicts(
+                "apply",
+                format!("{}... {}", &item.argument[..7], item.subject),
+            ));
+        }
+        RebaseOutcome::Stopped(RebaseStop::ExecFailed(command)) => {
+            return Err(Error::ExecFailed(command))
+        }
+    }
+    Ok(())
+}
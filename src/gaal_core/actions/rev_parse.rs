@@ -0,0 +1,25 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+pub fn rev_parse<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    name: &str,
+    fmt: Option<&str>,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let hash = repository.object_find(name, fmt)?;
+    writeln!(out, "{}", hash)?;
+    Ok(())
+}
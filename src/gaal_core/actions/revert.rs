@@ -0,0 +1,27 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::{default::GaalRepository, sequencer::PickKind},
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::{
+    cherry_pick::{pick, PickAction},
+    Error,
+};
+
+/// Records a commit undoing the changes of another one on the current branch,
+/// or concludes the revert in progress.
+pub fn revert<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    action: &PickAction,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    pick(repository, PickKind::Revert, action, out)
+}
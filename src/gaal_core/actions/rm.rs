@@ -0,0 +1,30 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Unstages `paths`, given relative to the working directory, and deletes them
+/// unless `cached` is set.
+pub fn rm<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    paths: &[String],
+    cached: bool,
+    force: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let paths = paths.iter().map(String::as_str).collect::<Vec<&str>>();
+    for path in repository.index_remove(&paths, cached, force)? {
+        writeln!(out, "rm '{}'", path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::{
+        date::format_date,
+        repository::{default::GaalRepository, diff::DiffOptions},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+        index::MODE_TREE, object::tree::Tree,
+    },
+};
+
+use super::{
+    diff::{diff_write, PatchOptions},
+    log::{write_commit, LogFormat},
+    Error,
+};
+
+/// Prints `object`: a commit with its changes against its first parent (none
+/// for merges), an annotated tag followed by what it tags, the names in a tree
+/// or the content of a blob.
+pub fn show<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    object: &str,
+    options: &DiffOptions,
+    patch: &PatchOptions,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let mut hash = repository.rev_parse(object)?;
+    loop {
+        let fmt = repository.object_read(hash.clone())?.fmt()?;
+        match fmt.as_str() {
+            "tag" => {
+                let tag = repository.tag_read(&hash)?;
+                writeln!(out, "tag {}", tag.tag)?;
+                if let Some(tagger) = &tag.tagger {
+                    writeln!(out, "Tagger: {} <{}>", tagger.name, tagger.email)?;
+                    writeln!(out, "Date:   {}", format_date(tagger.time, tagger.offset))?;
+                }
+                writeln!(out)?;
+                writeln!(out, "{}", tag.message.trim_end())?;
+                writeln!(out)?;
+                hash = tag.object;
+            }
+            "commit" => {
+                let commit = repository.commit_read(&hash)?;
+                write_commit(out, LogFormat::Full, &hash, &commit, "", &[], "")?;
+                let entries = match commit.parents.as_slice() {
+                    [] => repository.diff_trees(None, Some(&commit.tree), options)?,
+                    [parent] => {
+                        let parent = repository.commit_read(parent)?;
+                        repository.diff_trees(Some(&parent.tree), Some(&commit.tree), options)?
+                    }
+                    _ => Vec::new(),
+                };
+                return diff_write(repository, &entries, patch, out);
+            }
+            "tree" => {
+                writeln!(out, "tree {}", object)?;
+                writeln!(out)?;
+                let data = repository.object_read(hash)?.serialize()?;
+                for leaf in Tree::parse(&data, repository.object_format)?.leafs {
+                    match leaf.mode_value()? {
+                        MODE_TREE => writeln!(out, "{}/", leaf.path)?,
+                        _ => writeln!(out, "{}", leaf.path)?,
+                    }
+                }
+                return Ok(());
+            }
+            _ => {
+                out.write_all(&repository.object_read(hash)?.serialize()?)?;
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+pub fn show_ref<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    for (name, hash) in repository.ref_list("refs")? {
+        writeln!(out, "{} {}", hash, name)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::{default::GaalRepository, diff::DiffOptions, stash::parse_stash_index},
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::{
+    diff::{diff_write, PatchOptions},
+    merge::write_conflicts,
+    Error,
+};
+
+/// What a `stash` invocation asks for. Stashes are named `stash@{<n>}` or `<n>`,
+/// the newest one when `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StashAction {
+    /// Set the local changes aside, with this message.
+    Push(Option<String>),
+    Pop(Option<String>),
+    Apply(Option<String>),
+    List,
+    Drop(Option<String>),
+    /// Show the changes of a stash against the commit it was made on.
+    Show(Option<String>, PatchOptions),
+}
+
+fn stash_index(name: &Option<String>) -> Result<usize, Error> {
+    match name {
+        Some(name) => parse_stash_index(name)
+            .ok_or_else(|| Error::InvalidArgument(format!("`{}` is not a stash", name))),
+        None => Ok(0),
+    }
+}
+
+/// Sets local changes aside on the stash stack and brings them back.
+pub fn stash<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    action: &StashAction,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    match action {
+        StashAction::Push(message) => {
+            let hash = repository.stash_push(message.as_deref())?;
+            let message = repository.commit_read(&hash)?.message;
+            writeln!(
+                out,
+                "Saved working directory and index state {}",
+                message.trim_end()
+            )?;
+        }
+        StashAction::Pop(name) | StashAction::Apply(name) => {
+            let index = stash_index(name)?;
+            let hash = repository.stash_get(index)?;
+            let conflicts = match action {
+                StashAction::Pop(_) => repository.stash_pop(index)?,
+                _ => repository.stash_apply(index)?,
+            };
+            if !conflicts.is_empty() {
+                write_conflicts(&conflicts, out)?;
+                return Err(Error::StashConflicts);
+            }
+            if let StashAction::Pop(_) = action {
+                writeln!(out, "Dropped stash@{{{}}} ({})", index, hash)?;
+            }
+        }
+        StashAction::List => {
+            for (index, entry) in repository.stash_list()?.iter().enumerate() {
+                writeln!(out, "stash@{{{}}}: {}", index, entry.message)?;
+            }
+        }
+        StashAction::Drop(name) => {
+            let index = stash_index(name)?;
+            let hash = repository.stash_drop(index)?;
+            writeln!(out, "Dropped stash@{{{}}} ({})", index, hash)?;
+        }
+        StashAction::Show(name, options) => {
+            let stash = repository.commit_read(&repository.stash_get(stash_index(name)?)?)?;
+            let base = match stash.parents.first() {
+                Some(base) => Some(repository.object_peel_to(base, "tree")?),
+                None => None,
+            };
+            let entries = repository.diff_trees(
+                base.as_deref(),
+                Some(&stash.tree),
+                &DiffOptions::default(),
+            )?;
+            diff_write(repository, &entries, options, out)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::{
+        default::GaalRepository,
+        status::{StatusChange, StatusEntry},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+fn short_line(entry: &StatusEntry) -> String {
+    let code = |change: &Option<StatusChange>| change.as_ref().map_or(' ', StatusChange::code);
+    let path = match &entry.staged {
+        Some(StatusChange::Renamed(from)) => format!("{} -> {}", from, entry.path),
+        _ => entry.path.clone(),
+    };
+    format!("{}{} {}", code(&entry.staged), code(&entry.unstaged), path)
+}
+
+fn long_line(change: &StatusChange, path: &str) -> String {
+    match change {
+        StatusChange::Added => format!("new file:   {}", path),
+        StatusChange::Modified => format!("modified:   {}", path),
+        StatusChange::Deleted => format!("deleted:    {}", path),
+        StatusChange::Renamed(from) => format!("renamed:    {} -> {}", from, path),
+        StatusChange::Unmerged => format!("unmerged:   {}", path),
+    }
+}
+
+/// Prints the working tree status, as `XY path` lines when `short` is set.
+pub fn status<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    short: bool,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let status = repository.status()?;
+
+    if short {
+        for entry in &status.entries {
+            writeln!(out, "{}", short_line(entry))?;
+        }
+        for path in &status.untracked {
+            writeln!(out, "?? {}", path)?;
+        }
+        return Ok(());
+    }
+
+    match &status.branch {
+        Some(branch) => writeln!(out, "On branch {}", branch)?,
+        None => writeln!(out, "HEAD detached")?,
+    }
+
+    let unmerged = status
+        .entries
+        .iter()
+        .filter(|entry| entry.staged == Some(StatusChange::Unmerged))
+        .collect::<Vec<_>>();
+    let staged = status
+        .entries
+        .iter()
+        .filter(|entry| entry.staged.is_some() && entry.staged != Some(StatusChange::Unmerged))
+        .collect::<Vec<_>>();
+    let unstaged = status
+        .entries
+        .iter()
+        .filter(|entry| entry.unstaged.is_some() && entry.unstaged != Some(StatusChange::Unmerged))
+        .collect::<Vec<_>>();
+
+    if !unmerged.is_empty() {
+        writeln!(out, "\nUnmerged paths:")?;
+        for entry in unmerged {
+            writeln!(out, "\tboth modified:   {}", entry.path)?;
+        }
+    }
+    if !staged.is_empty() {
+        writeln!(out, "\nChanges to be committed:")?;
+        for entry in staged {
+            if let Some(change) = &entry.staged {
+                writeln!(out, "\t{}", long_line(change, &entry.path))?;
+            }
+        }
+    }
+    if !unstaged.is_empty() {
+        writeln!(out, "\nChanges not staged for commit:")?;
+        for entry in unstaged {
+            if let Some(change) = &entry.unstaged {
+                writeln!(out, "\t{}", long_line(change, &entry.path))?;
+            }
+        }
+    }
+    if !status.untracked.is_empty() {
+        writeln!(out, "\nUntracked files:")?;
+        for path in &status.untracked {
+            writeln!(out, "\t{}", path)?;
+        }
+    }
+    if status.is_clean() {
+        writeln!(out, "nothing to commit, working tree clean")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Lists tags when `name` is `None`, otherwise creates a tag on `object`. The tag is
+/// annotated when `message` is given and lightweight otherwise.
+pub fn tag<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    name: Option<&str>,
+    object: &str,
+    message: Option<&str>,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let Some(name) = name else {
+        for (tag, _) in repository.ref_list("refs/tags")? {
+            writeln!(out, "{}", tag.trim_start_matches("refs/tags/"))?;
+        }
+        return Ok(());
+    };
+
+    let hash = repository.object_find(object, None)?;
+    let message = message.map(|message| format!("{}\n", message.trim_end_matches('\n')));
+    let annotation = match &message {
+        Some(message) => Some((repository.committer_signature()?, message.as_str())),
+        None => None,
+    };
+    repository.tag_create(name, &hash, annotation)?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::gaal_core::{
+    internals::repository::{default::GaalRepository, refs::PreviousValue},
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Points `name` at `new`, or deletes it when `new` is `None`. With `old` the
+/// change only happens if the ref still holds that value.
+pub fn update_ref<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    name: &str,
+    new: Option<&str>,
+    old: Option<&str>,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let previous = match old {
+        Some(old) => PreviousValue::MustBe(repository.object_find(old, None)?),
+        None => PreviousValue::Any,
+    };
+
+    match new {
+        Some(new) => {
+            let hash = repository.object_find(new, None)?;
+            repository.ref_update(name, &hash, previous)?
+        }
+        None => repository.ref_delete(name, previous)?,
+    }
+    Ok(())
+}
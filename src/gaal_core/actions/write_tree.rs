@@ -0,0 +1,29 @@
+use std::io::Write;
+
+use crate::gaal_core::{
+    internals::repository::default::GaalRepository,
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::Error;
+
+/// Writes the index, or the working directory below `work_dir_path` when given,
+/// as trees and prints the root tree id.
+pub fn write_tree<GCDA, GCDOA>(
+    repository: &GaalRepository<GCDA, GCDOA>,
+    work_dir_path: Option<&str>,
+    out: &mut impl Write,
+) -> Result<(), Error>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    let hash = match work_dir_path {
+        Some(path) => repository.tree_write_from_work_dir(path)?,
+        None => repository.tree_write_from_index()?,
+    };
+    writeln!(out, "{}", hash)?;
+    Ok(())
+}
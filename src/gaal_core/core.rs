@@ -3,6 +3,7 @@ use super::{
     provider::{
         directory::{GaalCoreDirectory, GaalCoreDirectoryActions},
         directory_object::GaalCoreDirectoryObjectsActions,
+        object::hash::GaalHashAlgorithm,
     },
 };
 
@@ -20,11 +21,16 @@ pub trait GaalCoreTrait<
     fn init(
         &self,
         path: Vec<GCDA::PathItem>,
-    ) -> Result<GaalRepository<GCDA, GCDOA>, RepositoryError>;
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError>;
+    fn init_with_object_format(
+        &self,
+        path: Vec<GCDA::PathItem>,
+        object_format: GaalHashAlgorithm,
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError>;
     fn derive_from_path(
         &self,
         path: Vec<GCDA::PathItem>,
-    ) -> Result<GaalRepository<GCDA, GCDOA>, RepositoryError>;
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError>;
 }
 
 impl<
@@ -38,13 +44,20 @@ impl<
     fn init(
         &self,
         path: Vec<GCDA::PathItem>,
-    ) -> Result<GaalRepository<GCDA, GCDOA>, RepositoryError> {
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError> {
         self.directory.init(path)
     }
+    fn init_with_object_format(
+        &self,
+        path: Vec<GCDA::PathItem>,
+        object_format: GaalHashAlgorithm,
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError> {
+        self.directory.init_with_object_format(path, object_format)
+    }
     fn derive_from_path(
         &self,
         path: Vec<<GCDA>::PathItem>,
-    ) -> Result<GaalRepository<GCDA, GCDOA>, RepositoryError> {
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError> {
         self.directory.derive_from_path(path)
     }
 }
@@ -0,0 +1,196 @@
+use crate::gaal_core::provider::object::commit::parse_offset;
+
+const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Year, month and day, from 1, of the day `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Counts from 0000-03-01 so that leap days end the 400 year eras.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days from 1970-01-01 to the given day, the inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Formats `time`, in seconds since the Unix epoch, as seen in the timezone
+/// `offset` minutes away from UTC: `Sun Oct 18 13:07:47 2026 +0200`.
+pub fn format_date(time: i64, offset: i32) -> String {
+    let local = time + i64::from(offset) * 60;
+    let (days, seconds) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!(
+        "{} {} {} {:02}:{:02}:{:02} {} {}{:02}{:02}",
+        DAYS[days.rem_euclid(7) as usize],
+        MONTHS[month as usize - 1],
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        year,
+        sign,
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Parses `YYYY-MM-DD`, followed by `HH:MM[:SS]` after a space or a `T`, and
+/// by a `+HHMM`/`-HHMM` offset or `Z`, UTC when missing.
+fn parse_absolute(date: &str) -> Option<i64> {
+    let number = |digits: &str, len: usize| -> Option<u32> {
+        if digits.len() != len || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let (day, rest) = date.split_at_checked(10)?;
+    let mut parts = day.split('-');
+    let (year, month, day) = (
+        number(parts.next()?, 4)?,
+        number(parts.next()?, 2)?,
+        number(parts.next()?, 2)?,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let rest = rest.trim_start_matches([' ', 'T']);
+    let (time, zone) = match rest.split_once([' ', '+', '-', 'Z']) {
+        Some((time, _)) => (time, rest[time.len()..].trim()),
+        None => (rest, ""),
+    };
+    let mut seconds = 0;
+    if !time.is_empty() {
+        let mut parts = time.split(':');
+        let hours = number(parts.next()?, 2)?;
+        let minutes = number(parts.next()?, 2)?;
+        let secs = match parts.next() {
+            Some(secs) => number(secs, 2)?,
+            None => 0,
+        };
+        if parts.next().is_some() || hours > 23 || minutes > 59 || secs > 60 {
+            return None;
+        }
+        seconds = i64::from(hours * 3600 + minutes * 60 + secs);
+    }
+    let offset = match zone {
+        "" | "Z" => 0,
+        zone => parse_offset(zone)?,
+    };
+    Some(days_from_civil(i64::from(year), month, day) * 86_400 + seconds - i64::from(offset) * 60)
+}
+
+/// Parses `<n> <unit> ago`, units from seconds to years, counted back from
+/// `now`.
+fn parse_relative(date: &str, now: i64) -> Option<i64> {
+    let mut parts = date.split_whitespace();
+    let count = parts.next()?.parse::<i64>().ok()?;
+    let unit = parts.next()?;
+    if parts.next()? != "ago" || parts.next().is_some() {
+        return None;
+    }
+    let seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 3600,
+        "day" => 86_400,
+        "week" => 7 * 86_400,
+        "month" => 30 * 86_400,
+        "year" => 365 * 86_400,
+        _ => return None,
+    };
+    Some(now - count.checked_mul(seconds)?)
+}
+
+/// Parses the date of a `--since` or `--until` filter into seconds since the
+/// Unix epoch: the seconds themselves, optionally after `@`, an ISO 8601 date
+/// such as `2026-10-18` or `2026-10-18 13:07:47 +0200`, or a relative date such
+/// as `2 weeks ago` counted back from `now`.
+pub fn parse_date_filter(date: &str, now: i64) -> Option<i64> {
+    let date = date.trim();
+    let seconds = date.strip_prefix('@').unwrap_or(date);
+    if let Ok(seconds) = seconds.parse::<i64>() {
+        return Some(seconds);
+    }
+    parse_absolute(date).or_else(|| parse_relative(date, now))
+}
+
+#[cfg(test)]
+mod test_date {
+    use super::{civil_from_days, days_from_civil, format_date, parse_date_filter};
+
+    #[test]
+    fn date_formatting() {
+        assert_eq!(format_date(0, 0), "Thu Jan 1 00:00:00 1970 +0000");
+        assert_eq!(format_date(1792321667, 0), "Sun Oct 18 11:07:47 2026 +0000");
+        assert_eq!(
+            format_date(1792321667, 120),
+            "Sun Oct 18 13:07:47 2026 +0200"
+        );
+        assert_eq!(
+            format_date(1792321667, -690),
+            "Sat Oct 17 23:37:47 2026 -1130"
+        );
+        assert_eq!(format_date(951782400, 0), "Tue Feb 29 00:00:00 2000 +0000");
+        assert_eq!(format_date(-1, 0), "Wed Dec 31 23:59:59 1969 +0000");
+        for days in [-800_000, -1, 0, 11_016, 20_742, 800_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn date_filter_parsing() {
+        let now = 1792321667;
+        assert_eq!(parse_date_filter("1792321667", now), Some(1792321667));
+        assert_eq!(parse_date_filter("@1792321667", now), Some(1792321667));
+        assert_eq!(parse_date_filter("2026-10-18", now), Some(1792281600));
+        assert_eq!(
+            parse_date_filter("2026-10-18 11:07:47", now),
+            Some(1792321667)
+        );
+        assert_eq!(
+            parse_date_filter("2026-10-18T13:07:47+0200", now),
+            Some(1792321667)
+        );
+        assert_eq!(
+            parse_date_filter("2026-10-18 13:07 +0200", now),
+            Some(1792321620)
+        );
+        assert_eq!(
+            parse_date_filter("2026-10-18T11:07:47Z", now),
+            Some(1792321667)
+        );
+        assert_eq!(parse_date_filter("2 weeks ago", now), Some(now - 1_209_600));
+        assert_eq!(parse_date_filter("1 day ago", now), Some(now - 86_400));
+        for invalid in [
+            "yesterday",
+            "2026-13-01",
+            "2026-10-16 25:00",
+            "3 fortnights ago",
+        ] {
+            assert_eq!(parse_date_filter(invalid, now), None);
+        }
+    }
+}
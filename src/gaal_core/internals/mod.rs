@@ -1 +1,4 @@
+pub mod date;
 pub mod repository;
+pub mod text_diff;
+pub mod text_merge;
@@ -0,0 +1,386 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+/// Reached from the first side of a query.
+const SIDE_A: u8 = 1;
+/// Reached from the second side of a query.
+const SIDE_B: u8 = 2;
+/// Below a common ancestor already found.
+const STALE: u8 = 4;
+
+/// Reads the parents of a commit.
+pub type ParentsReader<'r> = dyn FnMut(&str) -> Result<Vec<String>, RepositoryError> + 'r;
+
+/// Parents and generation number of a commit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitNode {
+    pub parents: Vec<String>,
+    /// 1 for root commits, one more than the highest of the parents otherwise.
+    /// A commit can only reach commits with a lower generation, so walks in
+    /// decreasing generation see every child of a commit before the commit.
+    pub generation: u64,
+}
+
+/// Commits read so far with their generation numbers, so that ancestry queries
+/// only read each commit once. Generations aren't stored in the repository: the
+/// first query reads every ancestor of the commits it involves down to the
+/// roots, and only later queries on the same graph skip those reads.
+#[derive(Clone, Debug, Default)]
+pub struct CommitGraph {
+    nodes: HashMap<String, CommitNode>,
+}
+
+impl CommitGraph {
+    /// Node of `hash`, reading it and any ancestor whose generation isn't known
+    /// yet with `read`.
+    pub fn node(
+        &mut self,
+        hash: &str,
+        read: &mut ParentsReader,
+    ) -> Result<&CommitNode, RepositoryError> {
+        // Ancestors are computed first, without recursing on long histories.
+        let mut pending = vec![(hash.to_string(), None)];
+        while let Some((hash, parents)) = pending.pop() {
+            if self.nodes.contains_key(&hash) {
+                continue;
+            }
+            let parents: Vec<String> = match parents {
+                Some(parents) => parents,
+                None => read(&hash)?,
+            };
+            let missing = parents
+                .iter()
+                .filter(|parent| !self.nodes.contains_key(*parent))
+                .cloned()
+                .collect::<Vec<String>>();
+            if missing.is_empty() {
+                let generation = parents
+                    .iter()
+                    .map(|parent| self.nodes[parent].generation)
+                    .max()
+                    .unwrap_or(0)
+                    + 1;
+                self.nodes.insert(
+                    hash,
+                    CommitNode {
+                        parents,
+                        generation,
+                    },
+                );
+            } else {
+                pending.push((hash, Some(parents)));
+                pending.extend(missing.into_iter().map(|parent| (parent, None)));
+            }
+        }
+        Ok(&self.nodes[hash])
+    }
+
+    /// Whether `ancestor` can be reached from `descendant`, both included.
+    pub fn is_ancestor(
+        &mut self,
+        ancestor: &str,
+        descendant: &str,
+        read: &mut ParentsReader,
+    ) -> Result<bool, RepositoryError> {
+        if ancestor == descendant {
+            return Ok(true);
+        }
+        let generation = self.node(ancestor, read)?.generation;
+        let mut seen = HashSet::new();
+        let mut pending = vec![descendant.to_string()];
+        while let Some(hash) = pending.pop() {
+            if hash == ancestor {
+                return Ok(true);
+            }
+            let node = self.node(&hash, read)?;
+            // Commits at or below the generation of `ancestor` can't reach it.
+            if node.generation <= generation || !seen.insert(hash) {
+                continue;
+            }
+            pending.extend(node.parents.clone());
+        }
+        Ok(false)
+    }
+
+    /// Marks the ancestors of `a` and `b` with the sides reaching them, walking
+    /// down by generation until only commits below a common ancestor are left.
+    /// Returns the marks and the common ancestors in the order they were found.
+    fn paint(
+        &mut self,
+        a: &str,
+        b: &str,
+        read: &mut ParentsReader,
+    ) -> Result<(HashMap<String, u8>, Vec<String>), RepositoryError> {
+        let mut marks: HashMap<String, u8> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        // Entries of the queue per commit, and how many are for commits not below
+        // a common ancestor, so the walk stops without scanning the queue.
+        let mut queued: HashMap<String, usize> = HashMap::new();
+        let mut non_stale = 0;
+        for (hash, side) in [(a, SIDE_A), (b, SIDE_B)] {
+            *marks.entry(hash.to_string()).or_default() |= side;
+            queue.push((self.node(hash, read)?.generation, hash.to_string()));
+            *queued.entry(hash.to_string()).or_default() += 1;
+            non_stale += 1;
+        }
+
+        let mut common = Vec::new();
+        while non_stale > 0 {
+            let Some((_, hash)) = queue.pop() else {
+                break;
+            };
+            if let Some(count) = queued.get_mut(&hash) {
+                *count -= 1;
+            }
+            let mut mark = marks[&hash];
+            if mark & STALE == 0 {
+                non_stale -= 1;
+            }
+            if mark & (SIDE_A | SIDE_B) == SIDE_A | SIDE_B && mark & STALE == 0 {
+                common.push(hash.clone());
+                mark |= STALE;
+                marks.insert(hash.clone(), mark);
+                non_stale -= queued[&hash];
+            }
+            for parent in self.node(&hash, read)?.parents.clone() {
+                let parent_mark = marks.entry(parent.clone()).or_default();
+                if *parent_mark & mark != mark {
+                    let was_stale = *parent_mark & STALE != 0;
+                    *parent_mark |= mark;
+                    let stale = *parent_mark & STALE != 0;
+                    let count = queued.entry(parent.clone()).or_default();
+                    if stale && !was_stale {
+                        non_stale -= *count;
+                    }
+                    *count += 1;
+                    if !stale {
+                        non_stale += 1;
+                    }
+                    queue.push((self.node(&parent, read)?.generation, parent));
+                }
+            }
+        }
+        Ok((marks, common))
+    }
+
+    /// Drops the commits of `hashes` that are ancestors of another one, and sorts
+    /// the others by decreasing generation.
+    fn remove_redundant(
+        &mut self,
+        hashes: Vec<String>,
+        read: &mut ParentsReader,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let mut kept = Vec::new();
+        for (i, hash) in hashes.iter().enumerate() {
+            let mut redundant = false;
+            for (j, other) in hashes.iter().enumerate() {
+                if i != j && hash != other && self.is_ancestor(hash, other, read)? {
+                    redundant = true;
+                    break;
+                }
+            }
+            if !redundant && !kept.contains(hash) {
+                kept.push(hash.clone());
+            }
+        }
+        let mut generations = Vec::new();
+        for hash in kept {
+            generations.push((self.node(&hash, read)?.generation, hash));
+        }
+        generations.sort_by(|a, b| b.cmp(a));
+        Ok(generations.into_iter().map(|(_, hash)| hash).collect())
+    }
+
+    /// Best common ancestors of `a` and `b`: reachable from both and not an
+    /// ancestor of another one. Several are possible after criss-cross merges.
+    pub fn merge_bases(
+        &mut self,
+        a: &str,
+        b: &str,
+        read: &mut ParentsReader,
+    ) -> Result<Vec<String>, RepositoryError> {
+        if a == b {
+            return Ok(vec![a.to_string()]);
+        }
+        let (_, common) = self.paint(a, b, read)?;
+        self.remove_redundant(common, read)
+    }
+
+    /// Best common ancestors of all of `hashes`, found by merging the bases so far
+    /// with each commit in turn.
+    pub fn merge_bases_octopus(
+        &mut self,
+        hashes: &[String],
+        read: &mut ParentsReader,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let Some((first, others)) = hashes.split_first() else {
+            return Ok(Vec::new());
+        };
+        let mut bases = vec![first.clone()];
+        for hash in others {
+            let mut next = Vec::new();
+            for base in &bases {
+                next.extend(self.merge_bases(base, hash, read)?);
+            }
+            bases = self.remove_redundant(next, read)?;
+        }
+        Ok(bases)
+    }
+
+    /// Numbers of commits reachable from `a` but not `b`, and from `b` but not
+    /// `a`.
+    pub fn ahead_behind(
+        &mut self,
+        a: &str,
+        b: &str,
+        read: &mut ParentsReader,
+    ) -> Result<(usize, usize), RepositoryError> {
+        let (marks, _) = self.paint(a, b, read)?;
+        let count = |side: u8| {
+            marks
+                .values()
+                .filter(|mark| **mark & (SIDE_A | SIDE_B) == side)
+                .count()
+        };
+        Ok((count(SIDE_A), count(SIDE_B)))
+    }
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    fn commit_parents(&self, hash: &str) -> Result<Vec<String>, RepositoryError> {
+        Ok(self.commit_read(hash)?.parents)
+    }
+
+    /// A best common ancestor of the commits `a` and `b`, the one with the
+    /// highest generation when there are several. `None` for unrelated histories.
+    pub fn merge_base(&self, a: &str, b: &str) -> Result<Option<String>, RepositoryError> {
+        Ok(self.merge_bases(a, b)?.into_iter().next())
+    }
+
+    /// Every best common ancestor of the commits `a` and `b`, by decreasing
+    /// generation.
+    pub fn merge_bases(&self, a: &str, b: &str) -> Result<Vec<String>, RepositoryError> {
+        self.commit_graph
+            .borrow_mut()
+            .merge_bases(a, b, &mut |hash| self.commit_parents(hash))
+    }
+
+    /// Best common ancestors of all the commits `hashes`, as needed to merge them
+    /// at once.
+    pub fn merge_base_octopus(&self, hashes: &[String]) -> Result<Vec<String>, RepositoryError> {
+        self.commit_graph
+            .borrow_mut()
+            .merge_bases_octopus(hashes, &mut |hash| self.commit_parents(hash))
+    }
+
+    /// Whether `ancestor` can be reached from `descendant` by following parents,
+    /// which includes both being the same commit.
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool, RepositoryError> {
+        self.commit_graph
+            .borrow_mut()
+            .is_ancestor(ancestor, descendant, &mut |hash| self.commit_parents(hash))
+    }
+
+    /// Numbers of commits `a` has that `b` lacks and the other way around, e.g. how
+    /// far a branch is ahead of and behind its upstream.
+    pub fn ahead_behind(&self, a: &str, b: &str) -> Result<(usize, usize), RepositoryError> {
+        self.commit_graph
+            .borrow_mut()
+            .ahead_behind(a, b, &mut |hash| self.commit_parents(hash))
+    }
+}
+
+#[cfg(test)]
+mod test_commit_graph {
+    use std::collections::HashMap;
+
+    use super::CommitGraph;
+    use crate::gaal_core::internals::repository::RepositoryError;
+
+    /// ```text
+    /// r - a - b - m1 - d
+    ///      \    X
+    ///       c - m2 - e
+    /// ```
+    /// `m1` and `m2` both merge `b` and `c`, so `d` and `e` have two bases.
+    fn history() -> HashMap<String, Vec<String>> {
+        [
+            ("r", vec![]),
+            ("a", vec!["r"]),
+            ("b", vec!["a"]),
+            ("c", vec!["a"]),
+            ("m1", vec!["b", "c"]),
+            ("m2", vec!["c", "b"]),
+            ("d", vec!["m1"]),
+            ("e", vec!["m2"]),
+            ("x", vec![]),
+        ]
+        .into_iter()
+        .map(|(hash, parents)| {
+            let parents = parents.into_iter().map(String::from).collect();
+            (hash.to_string(), parents)
+        })
+        .collect()
+    }
+
+    fn query<T>(
+        query: impl FnOnce(&mut CommitGraph, &mut super::ParentsReader) -> Result<T, RepositoryError>,
+    ) -> T {
+        let history = history();
+        let mut read = |hash: &str| Ok(history[hash].clone());
+        query(&mut CommitGraph::default(), &mut read).unwrap()
+    }
+
+    #[test]
+    fn generations() {
+        let generation = query(|graph, read| Ok(graph.node("d", read)?.generation));
+        assert_eq!(generation, 5);
+    }
+
+    #[test]
+    fn ancestry() {
+        assert!(query(|graph, read| graph.is_ancestor("a", "e", read)));
+        assert!(query(|graph, read| graph.is_ancestor("e", "e", read)));
+        assert!(!query(|graph, read| graph.is_ancestor("d", "e", read)));
+        assert!(!query(|graph, read| graph.is_ancestor("x", "d", read)));
+    }
+
+    #[test]
+    fn merge_bases() {
+        let mut bases = query(|graph, read| graph.merge_bases("d", "e", read));
+        bases.sort();
+        assert_eq!(bases, vec!["b", "c"]);
+        assert_eq!(
+            query(|graph, read| graph.merge_bases("b", "d", read)),
+            vec!["b"]
+        );
+        assert!(query(|graph, read| graph.merge_bases("d", "x", read)).is_empty());
+
+        let hashes = ["d", "e", "c"].map(String::from);
+        assert_eq!(
+            query(|graph, read| graph.merge_bases_octopus(&hashes, read)),
+            vec!["c"]
+        );
+    }
+
+    #[test]
+    fn ahead_behind() {
+        assert_eq!(
+            query(|graph, read| graph.ahead_behind("d", "c", read)),
+            (3, 0)
+        );
+        assert_eq!(
+            query(|graph, read| graph.ahead_behind("d", "e", read)),
+            (2, 2)
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions, directory_config::GaalDirectoryConfigActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+};
+
+use super::{
+    default::GaalRepository,
+    refs::{is_valid_ref_name, PreviousValue},
+    RepositoryError,
+};
+
+const BRANCH_PREFIX: &str = "refs/heads/";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Branch {
+    /// Name without `refs/heads/`.
+    pub name: String,
+    pub hash: String,
+    /// Whether `HEAD` points to this branch.
+    pub current: bool,
+    /// Ref the branch tracks, e.g. `refs/remotes/origin/master`.
+    pub upstream: Option<String>,
+}
+
+fn branch_ref(name: &str) -> Result<String, RepositoryError> {
+    let full_name = format!("{}{}", BRANCH_PREFIX, name);
+    if name == "HEAD" || name.starts_with('-') || !is_valid_ref_name(&full_name) {
+        return Err(RepositoryError::InvalidReference(name.to_string()));
+    }
+    Ok(full_name)
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Every branch, sorted by name.
+    pub fn branch_list(&self) -> Result<Vec<Branch>, RepositoryError> {
+        let current = self.head_branch()?;
+        let mut branches = Vec::new();
+        for (full_name, hash) in self.ref_list("refs/heads")? {
+            let name = full_name[BRANCH_PREFIX.len()..].to_string();
+            branches.push(Branch {
+                current: current.as_deref() == Some(name.as_str()),
+                upstream: self.branch_upstream_ref(Some(&name)).ok(),
+                name,
+                hash,
+            });
+        }
+        Ok(branches)
+    }
+
+    /// Creates the branch `name` at the commit `start` resolves to and returns its
+    /// id. An existing branch is only moved when `force` is set, and never when it
+    /// is checked out.
+    pub fn branch_create(
+        &self,
+        name: &str,
+        start: &str,
+        force: bool,
+    ) -> Result<String, RepositoryError> {
+        let full_name = branch_ref(name)?;
+        let hash = self.object_peel_to(&self.rev_parse(start)?, "commit")?;
+        let previous = match self.ref_resolve(&full_name)? {
+            Some(_) if !force => return Err(RepositoryError::ReferenceExists(full_name)),
+            Some(_) if self.head_branch()?.as_deref() == Some(name) => {
+                return Err(RepositoryError::CurrentBranch(name.to_string()))
+            }
+            Some(old) => PreviousValue::MustBe(old),
+            None => PreviousValue::MustNotExist,
+        };
+        self.ref_update(&full_name, &hash, previous)?;
+        Ok(hash)
+    }
+
+    /// Renames the branch `old` to `new` along with its config section, keeping
+    /// `HEAD` on it when it is checked out. An existing `new` branch is only
+    /// replaced when `force` is set.
+    pub fn branch_rename(
+        &mut self,
+        old: &str,
+        new: &str,
+        force: bool,
+    ) -> Result<(), RepositoryError> {
+        let old_name = branch_ref(old)?;
+        let new_name = branch_ref(new)?;
+        let hash = self
+            .ref_resolve(&old_name)?
+            .ok_or_else(|| RepositoryError::UnknownReference(old_name.clone()))?;
+        if old == new {
+            return Ok(());
+        }
+        let current = self.head_branch()?;
+        let previous = match self.ref_resolve(&new_name)? {
+            Some(_) if !force || current.as_deref() == Some(new) => {
+                return Err(RepositoryError::ReferenceExists(new_name))
+            }
+            Some(replaced) => PreviousValue::MustBe(replaced),
+            None => PreviousValue::MustNotExist,
+        };
+
+        self.ref_update(&new_name, &hash, previous)?;
+        self.ref_delete(&old_name, PreviousValue::MustBe(hash))?;
+        if current.as_deref() == Some(old) {
+            self.ref_update_symbolic("HEAD", &new_name)?;
+        }
+
+        let section = self.config.get_section(&["branch", old]).cloned();
+        self.config.delete_section(&["branch", old]);
+        self.config.delete_section(&["branch", new]);
+        if let Some(section) = section {
+            self.config.insert_section(&["branch", new], section, true);
+        }
+        self.config_save()
+    }
+
+    /// Deletes the branch `name` and its config section. Branches whose commit
+    /// can't be reached from `HEAD` are only deleted when `force` is set.
+    pub fn branch_delete(&mut self, name: &str, force: bool) -> Result<String, RepositoryError> {
+        let full_name = branch_ref(name)?;
+        let hash = self
+            .ref_resolve(&full_name)?
+            .ok_or_else(|| RepositoryError::UnknownReference(full_name.clone()))?;
+        if self.head_branch()?.as_deref() == Some(name) {
+            return Err(RepositoryError::CurrentBranch(name.to_string()));
+        }
+        if !force {
+            let merged = match self.ref_resolve("HEAD")? {
+                Some(head) => self.is_ancestor(&hash, &head)?,
+                None => false,
+            };
+            if !merged {
+                return Err(RepositoryError::BranchNotMerged(name.to_string()));
+            }
+        }
+
+        self.ref_delete(&full_name, PreviousValue::MustBe(hash.clone()))?;
+        self.config_remove_section(&["branch", name])?;
+        Ok(hash)
+    }
+
+    /// Makes `name` track `upstream`: a remote-tracking branch such as
+    /// `origin/master`, or a local branch. Stored as `branch.<name>.remote` and
+    /// `branch.<name>.merge`, `.` being the remote of local branches.
+    pub fn branch_set_upstream(
+        &mut self,
+        name: &str,
+        upstream: &str,
+    ) -> Result<(), RepositoryError> {
+        let full_name = branch_ref(name)?;
+        if self.ref_resolve(&full_name)?.is_none() {
+            return Err(RepositoryError::UnknownReference(full_name));
+        }
+
+        let remote_name = format!("refs/remotes/{}", upstream);
+        let tracking = match upstream.split_once('/') {
+            Some((remote, branch)) if is_valid_ref_name(&remote_name) => self
+                .ref_resolve(&remote_name)?
+                .map(|_| (remote.to_string(), format!("{}{}", BRANCH_PREFIX, branch))),
+            _ => None,
+        };
+        let (remote, merge) = match tracking {
+            Some(tracking) => tracking,
+            None => {
+                let local = branch_ref(upstream)?;
+                if self.ref_resolve(&local)?.is_none() {
+                    return Err(RepositoryError::UnknownReference(upstream.to_string()));
+                }
+                (".".to_string(), local)
+            }
+        };
+        self.config_set(&["branch", name, "remote"], &remote)?;
+        self.config_set(&["branch", name, "merge"], &merge)
+    }
+
+    /// Stops `name` from tracking anything.
+    pub fn branch_unset_upstream(&mut self, name: &str) -> Result<(), RepositoryError> {
+        branch_ref(name)?;
+        self.config_unset(&["branch", name, "remote"])?;
+        self.config_unset(&["branch", name, "merge"])
+    }
+}
+
+#[cfg(test)]
+mod test_branch_name {
+    use super::branch_ref;
+
+    #[test]
+    fn branch_name_validation() {
+        assert_eq!(branch_ref("topic/x").unwrap(), "refs/heads/topic/x");
+        assert!(branch_ref("HEAD").is_err());
+        assert!(branch_ref("-d").is_err());
+        assert!(branch_ref("a..b").is_err());
+        assert!(branch_ref("").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_branch {
+    use super::Branch;
+    use crate::gaal_core::{
+        internals::repository::RepositoryError,
+        provider::memory::{repository, write, MemoryCoreDirectory},
+    };
+
+    #[test]
+    fn branch_delete_unmerged() {
+        let directory = MemoryCoreDirectory::default();
+        let mut repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+        repository.branch_create("merged", "HEAD", false).unwrap();
+        repository.branch_create("topic", "HEAD", false).unwrap();
+        repository.checkout("topic", false).unwrap();
+        write("work/a.txt", "topic\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let topic = repository.commit_index("Topic", false).unwrap();
+
+        assert!(matches!(
+            repository.branch_delete("topic", false),
+            Err(RepositoryError::CurrentBranch(_))
+        ));
+        repository.checkout("master", false).unwrap();
+        assert!(matches!(
+            repository.branch_delete("topic", false),
+            Err(RepositoryError::BranchNotMerged(name)) if name == "topic"
+        ));
+        assert_eq!(repository.rev_parse("topic").unwrap(), topic);
+        assert_eq!(repository.branch_delete("merged", false).unwrap(), first);
+        assert_eq!(repository.branch_delete("topic", true).unwrap(), topic);
+        assert!(matches!(
+            repository.branch_delete("topic", true),
+            Err(RepositoryError::UnknownReference(_))
+        ));
+        let names = repository.branch_list().unwrap();
+        let names = names.iter().map(|branch| branch.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), vec!["master"]);
+    }
+
+    #[test]
+    fn branch_rename_and_upstream() {
+        let directory = MemoryCoreDirectory::default();
+        let mut repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+        repository
+            .ref_create("refs/remotes/origin/main", &first)
+            .unwrap();
+        repository.branch_create("topic", "HEAD", false).unwrap();
+
+        repository
+            .branch_set_upstream("topic", "origin/main")
+            .unwrap();
+        assert_eq!(
+            repository.branch_upstream_ref(Some("topic")).unwrap(),
+            "refs/remotes/origin/main"
+        );
+        repository.branch_set_upstream("master", "topic").unwrap();
+        assert_eq!(
+            repository
+                .config_get(&["branch", "master", "remote"])
+                .as_deref(),
+            Some(".")
+        );
+        assert_eq!(repository.rev_parse("@{u}").unwrap(), first);
+        assert!(matches!(
+            repository.branch_set_upstream("master", "missing"),
+            Err(RepositoryError::UnknownReference(_))
+        ));
+
+        // Renaming the current branch keeps `HEAD` and the upstream on it.
+        assert!(matches!(
+            repository.branch_rename("master", "topic", false),
+            Err(RepositoryError::ReferenceExists(_))
+        ));
+        repository.branch_rename("master", "main", false).unwrap();
+        assert_eq!(repository.head_branch().unwrap().as_deref(), Some("main"));
+        assert_eq!(repository.ref_resolve("refs/heads/master").unwrap(), None);
+        assert_eq!(
+            repository.branch_upstream_ref(Some("main")).unwrap(),
+            "refs/heads/topic"
+        );
+        assert!(matches!(
+            repository.branch_upstream_ref(Some("master")),
+            Err(RepositoryError::NoUpstream(_))
+        ));
+        assert_eq!(
+            repository.branch_list().unwrap(),
+            vec![
+                Branch {
+                    name: "main".to_string(),
+                    hash: first.clone(),
+                    current: true,
+                    upstream: Some("refs/heads/topic".to_string()),
+                },
+                Branch {
+                    name: "topic".to_string(),
+                    hash: first.clone(),
+                    current: false,
+                    upstream: Some("refs/remotes/origin/main".to_string()),
+                },
+            ]
+        );
+
+        repository.branch_unset_upstream("topic").unwrap();
+        assert!(repository.branch_upstream_ref(Some("topic")).is_err());
+        assert!(matches!(
+            repository.branch_rename("topic", "main", true),
+            Err(RepositoryError::ReferenceExists(_))
+        ));
+        repository.branch_rename("topic", "other", false).unwrap();
+        assert_eq!(repository.rev_parse("other").unwrap(), first);
+    }
+}
@@ -0,0 +1,465 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::gaal_core::provider::{
+    directory::{GaalCoreDirectoryActions, GaalEntryMetadata},
+    directory_object::GaalCoreDirectoryObjectsActions,
+    index::{Index, IndexEntry, MODE_EXECUTABLE, MODE_SYMLINK, MODE_TREE},
+    object::{
+        tree::{is_valid_entry_name, TreeLeaf},
+        ObjectError,
+    },
+};
+
+use super::{default::GaalRepository, refs::is_valid_ref_name, RepositoryError};
+
+/// What a checkout moved `HEAD` to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckoutTarget {
+    /// `HEAD` now points to this branch, without `refs/heads/`.
+    Branch(String),
+    /// `HEAD` now holds this commit id.
+    Detached(String),
+    /// A bare tree was checked out, `HEAD` didn't move.
+    Tree(String),
+}
+
+/// Whether two versions of a path hold the same content and mode.
+pub(super) fn same_leaf(a: Option<&TreeLeaf>, b: Option<&TreeLeaf>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.sha == b.sha && a.mode == b.mode,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Makes the working directory and the index match `revision`, then points
+    /// `HEAD` at it: at the branch when `revision` names one, at the commit
+    /// otherwise. Paths that differ between `HEAD` and the target must have no
+    /// staged or unstaged changes, and untracked files are never overwritten,
+    /// unless `force` is set. Changes to other paths are carried over.
+    pub fn checkout(&self, revision: &str, force: bool) -> Result<CheckoutTarget, RepositoryError> {
+        let branch = format!("refs/heads/{}", revision);
+        let (target, hash) = match is_valid_ref_name(&branch) {
+            true => match self.ref_resolve(&branch)? {
+                Some(hash) => (Some(CheckoutTarget::Branch(revision.to_string())), hash),
+                None => (None, self.rev_parse(revision)?),
+            },
+            false => (None, self.rev_parse(revision)?),
+        };
+        let target = match target {
+            Some(target) => target,
+            None => match self.object_peel(&hash)? {
+                (hash, fmt) if fmt == "commit" => CheckoutTarget::Detached(hash),
+                (hash, _) => CheckoutTarget::Tree(hash),
+            },
+        };
+        let tree = self.object_peel_to(&hash, "tree")?;
+        let files = self
+            .tree_files(&tree)?
+            .into_iter()
+            // Submodules point into another repository.
+            .filter(|(_, leaf)| !leaf.mode.starts_with("16"))
+            .collect::<BTreeMap<String, TreeLeaf>>();
+        self.checkout_files(&files, force)?;
+
+        match &target {
+            CheckoutTarget::Branch(name) => {
+                self.ref_update_symbolic("HEAD", &format!("refs/heads/{}", name))?
+            }
+            CheckoutTarget::Detached(hash) => self.ref_detach("HEAD", hash)?,
+            CheckoutTarget::Tree(_) => {}
+        }
+        Ok(target)
+    }
+
+    /// Moves the working directory and the index from the tree of `HEAD` to
+    /// `files`, with the same safety checks as `checkout`, leaving `HEAD` alone.
+    pub(super) fn checkout_files(
+        &self,
+        files: &BTreeMap<String, TreeLeaf>,
+        force: bool,
+    ) -> Result<(), RepositoryError> {
+        let head = self.head_files()?;
+        self.index_update(|index| {
+            if !force {
+                self.checkout_check(&head, files, index)?;
+            }
+
+            for path in head.keys().filter(|path| !files.contains_key(*path)) {
+                if self.work_metadata(path)?.is_some() {
+                    self.directory().remove_entry(self.work_path(path))?;
+                    self.work_prune(path)?;
+                }
+                index.remove(path);
+            }
+            if force {
+                index
+                    .entries
+                    .retain(|entry| files.contains_key(&entry.path));
+            }
+            for (path, leaf) in files {
+                let unchanged = match force {
+                    true => index.get(path).is_some_and(|entry| {
+                        entry.id == leaf.sha
+                            && entry.mode_string() == leaf.mode
+                            && !self.work_is_modified(path, entry).unwrap_or(true)
+                    }),
+                    false => same_leaf(head.get(path), Some(leaf)),
+                };
+                if !unchanged {
+                    index.insert(self.checkout_file(leaf)?);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Makes the working directory and the index match `files` exactly, dropping
+    /// local changes and the files tracked by the index but not by `files`.
+    pub(super) fn checkout_reset(
+        &self,
+        files: &BTreeMap<String, TreeLeaf>,
+    ) -> Result<(), RepositoryError> {
+        for entry in self.index_read()?.entries {
+            if !files.contains_key(&entry.path) && self.work_metadata(&entry.path)?.is_some() {
+                self.directory().remove_entry(self.work_path(&entry.path))?;
+                self.work_prune(&entry.path)?;
+            }
+        }
+        self.checkout_files(files, true)
+    }
+
+    /// Moves the index and the working directory from `HEAD` to `files`, as
+    /// `reset --merge` does when an operation is given up: only the paths that
+    /// are staged differently from `HEAD`, conflicted or changed by `files` are
+    /// reset, and unstaged changes to the other paths are kept. Fails, changing
+    /// nothing, when a path `files` changes has unstaged changes or an untracked
+    /// file.
+    pub(super) fn checkout_reset_merge(
+        &self,
+        files: &BTreeMap<String, TreeLeaf>,
+    ) -> Result<(), RepositoryError> {
+        let head = self.head_files()?;
+        self.index_update(|index| {
+            let paths = head
+                .keys()
+                .chain(files.keys())
+                .chain(index.entries.iter().map(|entry| &entry.path))
+                .cloned()
+                .collect::<BTreeSet<String>>();
+            let mut reset = Vec::new();
+            for path in paths {
+                let from = head.get(&path);
+                let staged = index
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.path == path)
+                    .map(|entry| {
+                        entry.stage() != 0
+                            || from.is_none_or(|from| {
+                                entry.id != from.sha || entry.mode_string() != from.mode
+                            })
+                    })
+                    .collect::<Vec<bool>>();
+                let staged = match staged.as_slice() {
+                    [] => from.is_some(),
+                    staged => staged.iter().any(|staged| *staged),
+                };
+                if staged {
+                    reset.push(path);
+                    continue;
+                }
+                if same_leaf(from, files.get(&path)) {
+                    continue;
+                }
+                // Unchanged in the index, any change in the working directory is
+                // the user's.
+                match index.get(&path) {
+                    Some(entry) if self.work_is_modified(&path, entry)? => {
+                        return Err(RepositoryError::LocalChanges(path))
+                    }
+                    None if self.work_metadata(&path)?.is_some() => {
+                        return Err(RepositoryError::UntrackedOverwritten(path))
+                    }
+                    _ => {}
+                }
+                reset.push(path);
+            }
+
+            for path in reset {
+                index.remove(&path);
+                match files.get(&path) {
+                    Some(leaf) => index.insert(self.checkout_file(leaf)?),
+                    None => {
+                        if self.work_metadata(&path)?.is_some() {
+                            self.directory().remove_entry(self.work_path(&path))?;
+                            self.work_prune(&path)?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Fails when moving from `head` to `files` would lose local changes or
+    /// overwrite untracked files.
+    pub(super) fn checkout_check(
+        &self,
+        head: &BTreeMap<String, TreeLeaf>,
+        files: &BTreeMap<String, TreeLeaf>,
+        index: &Index,
+    ) -> Result<(), RepositoryError> {
+        let mut conflicts = index
+            .entries
+            .iter()
+            .filter(|entry| entry.stage() != 0)
+            .map(|entry| entry.path.clone())
+            .collect::<Vec<String>>();
+        if !conflicts.is_empty() {
+            conflicts.dedup();
+            return Err(RepositoryError::UnmergedPaths(conflicts));
+        }
+
+        let tracked = index
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<BTreeSet<&str>>();
+        for path in head.keys().chain(files.keys()).collect::<BTreeSet<_>>() {
+            let from = head.get(path);
+            if same_leaf(from, files.get(path)) {
+                continue;
+            }
+            match (index.get(path), from) {
+                (Some(entry), Some(from)) => {
+                    if entry.id != from.sha || entry.mode_string() != from.mode {
+                        return Err(RepositoryError::LocalChanges(path.clone()));
+                    }
+                    if self.work_is_modified(path, entry)? {
+                        return Err(RepositoryError::LocalChanges(path.clone()));
+                    }
+                }
+                // Staged as added, or staged as deleted.
+                (Some(_), None) | (None, Some(_)) => {
+                    return Err(RepositoryError::LocalChanges(path.clone()))
+                }
+                (None, None) => {
+                    let Some(metadata) = self.work_metadata(path)? else {
+                        continue;
+                    };
+                    // A directory is fine as long as everything in it goes away.
+                    let untracked = match metadata.mode {
+                        MODE_TREE => self.work_files(path)?.iter().any(|file| {
+                            !tracked.contains(file.as_str()) || files.contains_key(file)
+                        }),
+                        _ => true,
+                    };
+                    if untracked {
+                        return Err(RepositoryError::UntrackedOverwritten(path.clone()));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the blob of `leaf` at its path in the working directory and returns
+    /// the matching index entry.
+    pub(super) fn checkout_file(&self, leaf: &TreeLeaf) -> Result<IndexEntry, RepositoryError> {
+        let mode = leaf.mode_value()?;
+        let object = self.object_read(leaf.sha.clone())?;
+        let fmt = object.fmt()?;
+        if fmt != "blob" {
+            return Err(ObjectError::InvalidData(format!(
+                "`{}` is a {}, not a blob",
+                leaf.sha, fmt
+            ))
+            .into());
+        }
+        let data = object.serialize()?;
+
+        let metadata = self.work_write(&leaf.path, mode, &data)?;
+        let mut entry = IndexEntry::new(&leaf.path, &leaf.sha, &metadata);
+        entry.mode = mode;
+        Ok(entry)
+    }
+
+    /// Writes `data` at `path` in the working directory as a file with `mode`,
+    /// replacing whatever is there, and returns its new metadata.
+    pub(super) fn work_write(
+        &self,
+        path: &str,
+        mode: u32,
+        data: &[u8],
+    ) -> Result<GaalEntryMetadata, RepositoryError> {
+        // Trees are checked when parsed, this keeps any other caller inside the
+        // working directory too.
+        if !path.split('/').all(is_valid_entry_name) {
+            return Err(RepositoryError::InvalidPath(path.to_string()));
+        }
+        let work_path = self.work_path(path);
+        match self.work_metadata(path)? {
+            Some(metadata) if metadata.mode == MODE_TREE => {
+                self.directory().remove_path(work_path.clone())?
+            }
+            Some(_) => self.directory().remove_entry(work_path.clone())?,
+            None => {}
+        }
+        self.directory()
+            .make_path(work_path[0..work_path.len() - 1].to_vec())?;
+        match mode {
+            MODE_SYMLINK => self
+                .directory()
+                .make_link(work_path.clone(), &String::from_utf8_lossy(data))?,
+            _ => {
+                self.directory()
+                    .save_data(work_path.clone(), data.to_vec().into(), false)?;
+                if mode == MODE_EXECUTABLE {
+                    self.directory().set_executable(work_path, true)?;
+                }
+            }
+        }
+
+        self.work_metadata(path)?
+            .ok_or_else(|| RepositoryError::InvalidPath(path.to_string()))
+    }
+
+    /// Removes the directories holding `path` that were left empty, up to the
+    /// working directory.
+    pub(super) fn work_prune(&self, path: &str) -> Result<(), RepositoryError> {
+        let mut dir = path;
+        while let Some((parent, _)) = dir.rsplit_once('/') {
+            if !self
+                .directory()
+                .list_path(self.work_path(parent))?
+                .is_empty()
+            {
+                break;
+            }
+            self.directory().remove_path(self.work_path(parent))?;
+            dir = parent;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_checkout {
+    use super::CheckoutTarget;
+    use crate::gaal_core::internals::repository::RepositoryError;
+    use crate::gaal_core::provider::{
+        index::MODE_FILE,
+        memory::{read, remove, repository, store_object, write, MemoryCoreDirectory},
+    };
+
+    #[test]
+    fn checkout_tree_escaping_the_work_dir() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        let blob = store_object(&repository, "blob", b"escaped\n");
+        let digest = |hash: &str| hex::decode(hash).unwrap();
+        for name in ["..", ".gal", "."] {
+            let mut inner = b"100644 escaped.txt\x00".to_vec();
+            inner.extend(digest(&blob));
+            let inner = store_object(&repository, "tree", &inner);
+            let mut outer = format!("40000 {}\x00", name).into_bytes();
+            outer.extend(digest(&inner));
+            let outer = store_object(&repository, "tree", &outer);
+
+            assert!(repository.checkout(&outer, false).is_err());
+            assert!(repository.checkout(&outer, true).is_err());
+        }
+        assert_eq!(read("escaped.txt"), None);
+        assert_eq!(read("work/.gal/escaped.txt"), None);
+
+        for path in ["../escaped.txt", ".gal/config", "a/./b", ""] {
+            assert!(matches!(
+                repository.work_write(path, MODE_FILE, b"escaped"),
+                Err(RepositoryError::InvalidPath(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn checkout_between_file_and_directory() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/d", "file");
+        repository.index_add(&["d"]).unwrap();
+        let first = repository.commit_index("File", false).unwrap();
+
+        repository.ref_create("refs/heads/side", &first).unwrap();
+        repository.checkout("side", false).unwrap();
+        repository.index_remove(&["d"], false, false).unwrap();
+        write("work/d/f", "nested");
+        repository.index_add(&["d"]).unwrap();
+        repository.commit_index("Directory", false).unwrap();
+
+        repository.checkout("master", false).unwrap();
+        assert_eq!(read("work/d").as_deref(), Some("file"));
+        assert!(repository.status().unwrap().is_clean());
+
+        repository.checkout("side", false).unwrap();
+        assert_eq!(read("work/d"), None);
+        assert_eq!(read("work/d/f").as_deref(), Some("nested"));
+        assert!(repository.status().unwrap().is_clean());
+    }
+
+    #[test]
+    fn checkout_refuses_to_lose_changes() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "1");
+        write("work/same.txt", "same");
+        repository.index_add(&["."]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+        repository.ref_create("refs/heads/side", &first).unwrap();
+        repository.checkout("side", false).unwrap();
+        write("work/a.txt", "2");
+        write("work/new.txt", "n");
+        repository.index_add(&["."]).unwrap();
+        let side = repository.commit_index("Side", false).unwrap();
+        repository.checkout("master", false).unwrap();
+
+        // Unstaged, then staged changes to a path that differs.
+        write("work/a.txt", "local");
+        assert!(matches!(
+            repository.checkout("side", false),
+            Err(RepositoryError::LocalChanges(path)) if path == "a.txt"
+        ));
+        repository.index_add(&["a.txt"]).unwrap();
+        assert!(matches!(
+            repository.checkout("side", false),
+            Err(RepositoryError::LocalChanges(path)) if path == "a.txt"
+        ));
+        assert_eq!(repository.head_branch().unwrap().as_deref(), Some("master"));
+        assert_eq!(read("work/a.txt").as_deref(), Some("local"));
+        repository.checkout("master", true).unwrap();
+        assert_eq!(read("work/a.txt").as_deref(), Some("1"));
+
+        // An untracked file in the way.
+        write("work/new.txt", "untracked");
+        assert!(matches!(
+            repository.checkout("side", false),
+            Err(RepositoryError::UntrackedOverwritten(path)) if path == "new.txt"
+        ));
+        assert_eq!(read("work/new.txt").as_deref(), Some("untracked"));
+        remove("work/new.txt");
+
+        // Changes to paths both sides agree on are carried over.
+        write("work/same.txt", "carried");
+        assert_eq!(
+            repository.checkout(&side, false).unwrap(),
+            CheckoutTarget::Detached(side.clone())
+        );
+        assert_eq!(read("work/a.txt").as_deref(), Some("2"));
+        assert_eq!(read("work/same.txt").as_deref(), Some("carried"));
+        assert_eq!(repository.ref_resolve("HEAD").unwrap(), Some(side));
+    }
+}
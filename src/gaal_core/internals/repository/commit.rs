@@ -0,0 +1,135 @@
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    object::commit::{Commit, Signature},
+};
+
+use super::{default::GaalRepository, refs::PreviousValue, RepositoryError};
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Records the index on top of `HEAD` with the configured identity and returns
+    /// the new commit id. Refuses to record a tree identical to the parent's unless
+    /// `allow_empty` is set. While a merge is in progress the commit also gets
+    /// `MERGE_HEAD` as parent, which concludes the merge, and while a cherry-pick
+    /// is in progress it keeps the author of the picked commit.
+    pub fn commit_index(
+        &self,
+        message: &str,
+        allow_empty: bool,
+    ) -> Result<String, RepositoryError> {
+        let head = self.ref_resolve("HEAD")?;
+        let merge_head = self.merge_head()?;
+        let tree = self.tree_write_from_index()?;
+        if !allow_empty && merge_head.is_none() {
+            let parent_tree = match &head {
+                Some(head) => Some(self.commit_read(head)?.tree),
+                None => None,
+            };
+            if parent_tree.as_ref() == Some(&tree) {
+                return Err(RepositoryError::NothingToCommit);
+            }
+        }
+
+        let parents = head.into_iter().chain(merge_head).collect();
+        let author = match self.pick_author()? {
+            Some(author) => author,
+            None => self.author_signature()?,
+        };
+        let hash = self.commit_tree(&tree, parents, author, message)?;
+        self.merge_state_clear()?;
+        Ok(hash)
+    }
+
+    /// Writes a commit of `tree` with `parents` and moves `HEAD` to it: the branch
+    /// `HEAD` points to, or `HEAD` itself when detached. The update fails if `HEAD`
+    /// moved since it was read.
+    pub fn commit_tree(
+        &self,
+        tree: &str,
+        parents: Vec<String>,
+        author: Signature,
+        message: &str,
+    ) -> Result<String, RepositoryError> {
+        let head = self.ref_resolve("HEAD")?;
+        let commit = Commit::builder()
+            .tree(tree)
+            .parents(&parents)
+            .author(author)
+            .committer(self.committer_signature()?)
+            .message(&format!("{}\n", message.trim_end_matches('\n')))
+            .build()?;
+        let hash = self.commit_write(commit)?;
+
+        let previous = match head {
+            Some(head) => PreviousValue::MustBe(head),
+            None => PreviousValue::MustNotExist,
+        };
+        self.ref_update("HEAD", &hash, previous)?;
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod test_commit_index {
+    use crate::gaal_core::internals::repository::{refs::RefValue, RepositoryError};
+    use crate::gaal_core::provider::memory::{repository, write, MemoryCoreDirectory};
+
+    #[test]
+    fn commit_advances_the_branch() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        // The first commit has no parent tree to compare with.
+        repository.commit_index("Empty root", false).unwrap();
+        let root = repository
+            .ref_resolve("refs/heads/master")
+            .unwrap()
+            .unwrap();
+        assert!(repository.commit_read(&root).unwrap().parents.is_empty());
+
+        assert!(matches!(
+            repository.commit_index("Again", false),
+            Err(RepositoryError::NothingToCommit)
+        ));
+        let empty = repository.commit_index("Empty", true).unwrap();
+        let commit = repository.commit_read(&empty).unwrap();
+        assert_eq!(commit.parents, vec![root]);
+        assert_eq!(commit.message, "Empty\n");
+        assert_eq!(commit.author.name, "Alice");
+        assert_eq!(
+            repository.ref_resolve("refs/heads/master").unwrap(),
+            Some(empty)
+        );
+    }
+
+    #[test]
+    fn commit_on_detached_head() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a");
+        repository.index_add(&["a.txt"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+
+        repository.ref_detach("HEAD", &first).unwrap();
+        write("work/a.txt", "b");
+        repository.index_add(&["a.txt"]).unwrap();
+        let second = repository.commit_index("Second", false).unwrap();
+
+        assert_eq!(
+            repository.ref_read("HEAD").unwrap(),
+            Some(RefValue::Direct(second.clone()))
+        );
+        assert_eq!(repository.head_branch().unwrap(), None);
+        assert_eq!(
+            repository.ref_resolve("refs/heads/master").unwrap(),
+            Some(first.clone())
+        );
+        assert_eq!(
+            repository.commit_read(&second).unwrap().parents,
+            vec![first]
+        );
+    }
+}
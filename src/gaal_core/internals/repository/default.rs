@@ -1,18 +1,30 @@
 use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActionsType,
     directory::{GaalCoreDirectory, GaalCoreDirectoryActions},
-    directory_object::{GaalCoreDirectoryObjectType, GaalCoreDirectoryObjectsActions},
-    object::{default::GaalObjectAction, ObjectError},
+    directory_config::{
+        GaalDirectoryConfigActions, GaalDirectoryConfigSectionActions,
+        GaalDirectoryConfigSectionItemActions,
+    },
+    directory_object::{GaalCoreDirectoryObjectsActions, GaalCoreDirectoryObjectsType},
+    object::{commit::Commit, hash::GaalHashAlgorithm, kvlm::Kvlm, tag::Tag, ObjectError},
 };
 
-use super::RepositoryError;
+use std::cell::RefCell;
+
+use super::{ancestry::CommitGraph, RepositoryError};
 
 pub struct GaalRepository<
     'a,
     GCDA: GaalCoreDirectoryActions + Clone,
     GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
 > {
+    pub work_dir: Vec<GCDA::PathItem>,
     pub gaal: Vec<GCDA::PathItem>,
     pub config: GCDA::Config,
+    pub object_format: GaalHashAlgorithm,
+    /// Commits read by ancestry queries, kept in memory for the next ones made
+    /// through this value.
+    pub(super) commit_graph: RefCell<CommitGraph>,
     _directory: &'a GaalCoreDirectory<GCDA, GCDOA>,
 }
 
@@ -26,6 +38,7 @@ impl<
         work_dir: Vec<GCDA::PathItem>,
         _directory: &'a GaalCoreDirectory<GCDA, GCDOA>,
         force: bool,
+        object_format: GaalHashAlgorithm,
     ) -> Result<Self, RepositoryError> {
         let default_gal_dir = _directory.defaults.default_gal_dir.clone();
         let mut gaal_path = [&work_dir[..]].concat();
@@ -45,10 +58,17 @@ impl<
             path
         };
 
-        if !force && !_directory.is_config(config_path.clone()) {
-            return Err(RepositoryError::Inexistent(format!("{:?}", config_path)));
-        } else {
-            _directory.save_config(config_path.clone(), GCDA::Config::default())?;
+        if !_directory.is_config(config_path.clone()) {
+            if !force {
+                return Err(RepositoryError::Inexistent(format!("{:?}", config_path)));
+            }
+            let mut config = GCDA::Config::default();
+            let object_format_item =
+                <<GCDA as GaalCoreDirectoryActionsType>::ConfigSection as GaalDirectoryConfigSectionActions>::GCDSCI::new(
+                    &object_format.name().to_string().into(),
+                );
+            config.insert_item(&["extensions", "objectformat"], object_format_item, true);
+            _directory.save_config(config_path.clone(), config)?;
         }
 
         let branch_path: Vec<GCDA::PathItem> = {
@@ -107,11 +127,12 @@ impl<
             description_path
         };
 
-        if !_directory.is_path(description_path.clone()) {
+        if !_directory.is_entry(description_path.clone()) {
             _directory.save_data(
                 description_path,
                 "Unnamed repository; edit this file 'description' to name the repository."
-                    .to_string()
+                    .as_bytes()
+                    .to_vec()
                     .into(),
                 false,
             )?;
@@ -123,10 +144,10 @@ impl<
             head_path
         };
 
-        if !_directory.is_path(head_path.clone()) {
+        if !_directory.is_entry(head_path.clone()) {
             _directory.save_data(
                 head_path,
-                "ref: refs/heads/master".to_string().into(),
+                "ref: refs/heads/master".as_bytes().to_vec().into(),
                 false,
             )?;
         }
@@ -136,9 +157,18 @@ impl<
             Err(e) => return Err(RepositoryError::IoError(e)),
         };
 
+        // Repositories created before the object format was configurable use SHA-256.
+        let object_format = match config.get_item(&["extensions", "objectformat"]) {
+            Some(item) => Into::<String>::into(item.get_value().clone()).parse()?,
+            None => GaalHashAlgorithm::Sha256,
+        };
+
         Ok(Self {
+            work_dir,
             gaal: gaal_path,
             config,
+            object_format,
+            commit_graph: RefCell::default(),
             _directory,
         })
     }
@@ -147,7 +177,15 @@ impl<
         work_dir: Vec<GCDA::PathItem>,
         _directory: &'a GaalCoreDirectory<GCDA, GCDOA>,
     ) -> Result<Self, RepositoryError> {
-        Self::new(work_dir, _directory, true)
+        Self::new(work_dir, _directory, true, GaalHashAlgorithm::default())
+    }
+
+    pub fn create_with_object_format(
+        work_dir: Vec<GCDA::PathItem>,
+        _directory: &'a GaalCoreDirectory<GCDA, GCDOA>,
+        object_format: GaalHashAlgorithm,
+    ) -> Result<Self, RepositoryError> {
+        Self::new(work_dir, _directory, true, object_format)
     }
 
     pub fn derive_from_path(
@@ -159,7 +197,7 @@ impl<
         gaal_path.push(default_gal_dir);
 
         if _directory.is_path(gaal_path.clone()) {
-            return Self::new(work_dir, _directory, false);
+            return Self::new(work_dir, _directory, false, GaalHashAlgorithm::default());
         }
 
         let parent = {
@@ -177,20 +215,62 @@ impl<
         Self::derive_from_path(parent, _directory)
     }
 
+    pub fn directory(&self) -> &'a GaalCoreDirectory<GCDA, GCDOA> {
+        self._directory
+    }
+
+    /// Reads a repository config value, e.g. `&["core", "bare"]`.
+    pub fn config_get(&self, path: &[&str]) -> Option<String> {
+        self.config
+            .get_item(path)
+            .map(|item| item.get_value().clone().into())
+    }
+
+    /// Sets a repository config value, creating missing sections, and saves the
+    /// config.
+    pub fn config_set(&mut self, path: &[&str], value: &str) -> Result<(), RepositoryError> {
+        let item = <GCDA::ConfigSection as GaalDirectoryConfigSectionActions>::GCDSCI::new(
+            &value.to_string().into(),
+        );
+        self.config.insert_item(path, item, true);
+        self.config_save()
+    }
+
+    /// Removes a repository config value and saves the config.
+    pub fn config_unset(&mut self, path: &[&str]) -> Result<(), RepositoryError> {
+        self.config.delete_item(path);
+        self.config_save()
+    }
+
+    /// Removes a repository config section, e.g. `&["branch", "topic"]`, and saves
+    /// the config.
+    pub fn config_remove_section(&mut self, path: &[&str]) -> Result<(), RepositoryError> {
+        self.config.delete_section(path);
+        self.config_save()
+    }
+
+    pub(super) fn config_save(&self) -> Result<(), RepositoryError> {
+        let mut path = self.gaal.clone();
+        path.push(self.directory().defaults.default_gal_config.clone());
+        Ok(self.directory().save_config(path, self.config.clone())?)
+    }
+
+    pub fn gaal_path(&self, items: &[&str]) -> Vec<GCDA::PathItem> {
+        let mut path = self.gaal.clone();
+        for item in items {
+            path.push(item.to_string().into());
+        }
+        path
+    }
+
     pub fn object_write(
         &self,
-        obj: GaalCoreDirectoryObjectType<
-            GCDA,
-            GCDOA::GaalBlob,
-            GCDOA::GaalCommit,
-            GCDOA::GaalTag,
-            GCDOA::GaalTree,
-        >,
+        obj: GaalCoreDirectoryObjectsType<GCDA, GCDOA>,
     ) -> Result<String, ObjectError>
     where
         GCDA: GaalCoreDirectoryActions,
     {
-        let (hash, result) = GCDOA::hash(obj.clone())?;
+        let (hash, result) = GCDOA::hash(obj.clone(), self.object_format)?;
 
         let path = self._directory.hash_object_to_path(hash.clone());
         let object_path = {
@@ -212,23 +292,9 @@ impl<
         Ok(hash)
     }
 
-    pub fn object_read(
-        &self,
-        hash: String,
-    ) -> Result<
-        GaalCoreDirectoryObjectType<
-            GCDA,
-            GCDOA::GaalBlob,
-            GCDOA::GaalCommit,
-            GCDOA::GaalTag,
-            GCDOA::GaalTree,
-        >,
-        ObjectError,
-    >
-    where
-        GCDA: GaalCoreDirectoryActions,
-    {
-        let path = self._directory.hash_object_to_path(hash);
+    /// Reads the decompressed bytes of an object, header included.
+    pub fn object_read_raw(&self, hash: &str) -> Result<Vec<u8>, ObjectError> {
+        let path = self._directory.hash_object_to_path(hash.to_string());
         let object_path = {
             let mut objects_path = self.gaal.clone();
             objects_path.push("objects".to_string().into());
@@ -242,8 +308,94 @@ impl<
             return Err(ObjectError::Inexistent(format!("{:?}", object_path)));
         }
 
-        let data = self._directory.get_data(object_path, true)?;
+        let data = self
+            ._directory
+            .get_data(object_path, true)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::InvalidData => ObjectError::CorruptZlib(e.to_string()),
+                _ => ObjectError::IoError(e),
+            })?;
+
+        Ok(data.into())
+    }
+
+    pub fn object_read(
+        &self,
+        hash: String,
+    ) -> Result<GaalCoreDirectoryObjectsType<GCDA, GCDOA>, ObjectError>
+    where
+        GCDA: GaalCoreDirectoryActions,
+    {
+        let data = self.object_read_raw(&hash)?;
+
+        if self.config_get(&["core", "verifyobjects"]).as_deref() == Some("true") {
+            let actual = self.object_format.hash(&data);
+            if actual != hash {
+                return Err(ObjectError::HashMismatch {
+                    expected: hash,
+                    actual,
+                });
+            }
+        }
+
+        GCDOA::from_hash(data, self.object_format)
+    }
+
+    /// Reads `hash` as a commit.
+    pub fn commit_read(&self, hash: &str) -> Result<Commit, ObjectError> {
+        let object = self.object_read(hash.to_string())?;
+        let fmt = object.fmt()?;
+        if fmt != "commit" {
+            return Err(ObjectError::InvalidData(format!(
+                "`{}` is a {}, not a commit",
+                hash, fmt
+            )));
+        }
+        Commit::try_from(Kvlm::try_from(object.serialize()?)?)
+    }
+
+    pub fn commit_write(&self, commit: Commit) -> Result<String, ObjectError> {
+        let data: Vec<u8> = Kvlm::try_from(commit)?.into();
+        self.object_write(GCDOA::new_object(
+            "commit".to_string(),
+            data,
+            self.object_format,
+        )?)
+    }
+
+    /// Resolves a user supplied revision (`HEAD`, an object id, a tag or a branch,
+    /// with any suffix `rev_parse` accepts) to an object id. When `fmt` is given,
+    /// tags and commits are followed until an object of that type is reached.
+    pub fn object_find(&self, name: &str, fmt: Option<&str>) -> Result<String, RepositoryError> {
+        let hash = self.rev_parse(name)?;
+        match fmt {
+            Some(fmt) => Ok(self.object_peel_to(&hash, fmt)?),
+            None => Ok(hash),
+        }
+    }
+
+    /// Follows tags, and commits to their tree, from `hash` until an object of type
+    /// `fmt` is reached.
+    pub fn object_peel_to(&self, hash: &str, fmt: &str) -> Result<String, ObjectError> {
+        let mut hash = hash.to_string();
+        loop {
+            let object = self.object_read(hash.clone())?;
+            let object_fmt = object.fmt()?;
+            if object_fmt == fmt {
+                return Ok(hash);
+            }
 
-        GCDOA::from_hash(data.into())
+            let kvlm = Kvlm::try_from(object.serialize()?)?;
+            hash = match object_fmt.as_str() {
+                "tag" => Tag::try_from(kvlm)?.object,
+                "commit" if fmt == "tree" => Commit::try_from(kvlm)?.tree,
+                _ => {
+                    return Err(ObjectError::InvalidData(format!(
+                        "`{}` is a {}, not a {}",
+                        hash, object_fmt, fmt
+                    )))
+                }
+            };
+        }
     }
 }
@@ -0,0 +1,553 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    index::{MODE_SYMLINK, MODE_TREE},
+    object::ObjectError,
+};
+
+use super::{default::GaalRepository, index::is_under, RepositoryError};
+
+/// Inexact rename and copy detection compares every pair of candidates, so it
+/// is skipped above this many pairs.
+const RENAME_LIMIT: usize = 250_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffStatus {
+    Added,
+    Deleted,
+    Modified,
+    /// Changed between a regular file and a symlink.
+    TypeChanged,
+    /// Moved, with the similarity of both sides in percent.
+    Renamed(u8),
+    /// Created from a file that is kept, with their similarity in percent.
+    Copied(u8),
+}
+
+impl DiffStatus {
+    /// Letter used by `--name-status`, followed by the similarity for renames
+    /// and copies, e.g. `R087`.
+    pub fn code(&self) -> String {
+        match self {
+            DiffStatus::Added => "A".to_string(),
+            DiffStatus::Deleted => "D".to_string(),
+            DiffStatus::Modified => "M".to_string(),
+            DiffStatus::TypeChanged => "T".to_string(),
+            DiffStatus::Renamed(score) => format!("R{:03}", score),
+            DiffStatus::Copied(score) => format!("C{:03}", score),
+        }
+    }
+}
+
+/// One version of a changed file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffSide {
+    pub path: String,
+    /// One of the `MODE_*` constants.
+    pub mode: u32,
+    pub id: String,
+    /// Whether the content is read from the working directory, where it may not
+    /// have been stored as a blob.
+    pub work: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub status: DiffStatus,
+    /// `None` for added files.
+    pub old: Option<DiffSide>,
+    /// `None` for deleted files.
+    pub new: Option<DiffSide>,
+}
+
+impl DiffEntry {
+    /// Path on the new side, or the old one for deletions.
+    pub fn path(&self) -> &str {
+        match (&self.new, &self.old) {
+            (Some(side), _) | (None, Some(side)) => &side.path,
+            (None, None) => "",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Pair deleted and added files with similar content as renames.
+    pub renames: bool,
+    /// Also pair added files with similar modified files as copies.
+    pub copies: bool,
+    /// Minimum similarity, in percent, of a rename or copy.
+    pub threshold: u8,
+    /// Only paths at or below one of these, all paths when empty.
+    pub paths: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            renames: false,
+            copies: false,
+            threshold: 50,
+            paths: Vec::new(),
+        }
+    }
+}
+
+/// Files of one side of a diff: path to mode and id.
+type DiffFiles = BTreeMap<String, (u32, String)>;
+
+/// Similarity of two contents in percent: the size of the lines they share over
+/// the size of the larger one.
+pub fn similarity(a: &[u8], b: &[u8]) -> u8 {
+    let larger = a.len().max(b.len());
+    if larger == 0 {
+        return 100;
+    }
+    let mut lines = HashMap::new();
+    for line in a.split_inclusive(|b| *b == b'\n') {
+        *lines.entry(line).or_insert(0usize) += 1;
+    }
+    let mut common = 0;
+    for line in b.split_inclusive(|b| *b == b'\n') {
+        if let Some(count) = lines.get_mut(line).filter(|count| **count > 0) {
+            *count -= 1;
+            common += line.len();
+        }
+    }
+    (common * 100 / larger) as u8
+}
+
+/// Whether two modes are both files, both symlinks or both something else.
+fn same_kind(a: u32, b: u32) -> bool {
+    (a == MODE_SYMLINK) == (b == MODE_SYMLINK)
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Compares the trees `old` and `new`, `None` standing for an empty tree.
+    /// Subtrees with the same id on both sides aren't read.
+    pub fn diff_trees(
+        &self,
+        old: Option<&str>,
+        new: Option<&str>,
+        options: &DiffOptions,
+    ) -> Result<Vec<DiffEntry>, RepositoryError> {
+        let mut old_files = DiffFiles::new();
+        let mut new_files = DiffFiles::new();
+        self.diff_trees_at(old, new, "", &mut old_files, &mut new_files)?;
+        self.diff_files(old_files, new_files, false, options)
+    }
+
+    /// Compares the tree `old` (`None` for an empty tree) with the index.
+    pub fn diff_tree_to_index(
+        &self,
+        old: Option<&str>,
+        options: &DiffOptions,
+    ) -> Result<Vec<DiffEntry>, RepositoryError> {
+        let old_files = self.diff_tree_files(old)?;
+        self.diff_files(old_files, self.diff_index_files()?, false, options)
+    }
+
+    /// Compares the index with the working directory. Untracked files are left
+    /// out.
+    pub fn diff_index_to_work(
+        &self,
+        options: &DiffOptions,
+    ) -> Result<Vec<DiffEntry>, RepositoryError> {
+        let new_files = self.diff_work_files()?;
+        self.diff_files(self.diff_index_files()?, new_files, true, options)
+    }
+
+    /// Compares the tree `old` (`None` for an empty tree) with the tracked files
+    /// of the working directory.
+    pub fn diff_tree_to_work(
+        &self,
+        old: Option<&str>,
+        options: &DiffOptions,
+    ) -> Result<Vec<DiffEntry>, RepositoryError> {
+        let old_files = self.diff_tree_files(old)?;
+        self.diff_files(old_files, self.diff_work_files()?, true, options)
+    }
+
+    /// Content of one side of a diff, a symlink being its target.
+    pub fn diff_content(&self, side: &DiffSide) -> Result<Vec<u8>, RepositoryError> {
+        if !side.work {
+            return Ok(self.object_read(side.id.clone())?.serialize()?);
+        }
+        Ok(match side.mode {
+            MODE_SYMLINK => self
+                .directory()
+                .read_link(self.work_path(&side.path))?
+                .into_bytes(),
+            _ => self
+                .directory()
+                .get_data(self.work_path(&side.path), false)?
+                .into(),
+        })
+    }
+
+    fn diff_trees_at(
+        &self,
+        old: Option<&str>,
+        new: Option<&str>,
+        prefix: &str,
+        old_files: &mut DiffFiles,
+        new_files: &mut DiffFiles,
+    ) -> Result<(), ObjectError> {
+        let leafs = |tree: Option<&str>| -> Result<BTreeMap<_, _>, ObjectError> {
+            Ok(match tree {
+                Some(tree) => self
+                    .tree_read(tree)?
+                    .leafs
+                    .into_iter()
+                    .map(|leaf| (leaf.path.clone(), leaf))
+                    .collect(),
+                None => BTreeMap::new(),
+            })
+        };
+        let old_leafs = leafs(old)?;
+        let new_leafs = leafs(new)?;
+
+        let mut names = old_leafs.keys().chain(new_leafs.keys()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        for name in names {
+            let path = match prefix {
+                "" => name.clone(),
+                _ => format!("{}/{}", prefix, name),
+            };
+            let old_leaf = old_leafs.get(name);
+            let new_leaf = new_leafs.get(name);
+            if old_leaf == new_leaf {
+                continue;
+            }
+            // A path can hold a tree on one side and a file on the other.
+            let old_tree = old_leaf
+                .filter(|leaf| leaf.is_tree())
+                .map(|leaf| leaf.sha.as_str());
+            let new_tree = new_leaf
+                .filter(|leaf| leaf.is_tree())
+                .map(|leaf| leaf.sha.as_str());
+            if old_tree.is_some() || new_tree.is_some() {
+                self.diff_trees_at(old_tree, new_tree, &path, old_files, new_files)?;
+            }
+            if let Some(leaf) = old_leaf.filter(|leaf| !leaf.is_tree()) {
+                old_files.insert(path.clone(), (leaf.mode_value()?, leaf.sha.clone()));
+            }
+            if let Some(leaf) = new_leaf.filter(|leaf| !leaf.is_tree()) {
+                new_files.insert(path, (leaf.mode_value()?, leaf.sha.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    fn diff_tree_files(&self, tree: Option<&str>) -> Result<DiffFiles, RepositoryError> {
+        let Some(tree) = tree else {
+            return Ok(DiffFiles::new());
+        };
+        self.tree_files(tree)?
+            .into_iter()
+            .map(|(path, leaf)| Ok((path, (leaf.mode_value()?, leaf.sha))))
+            .collect()
+    }
+
+    /// Staged files, conflicted paths left out.
+    fn diff_index_files(&self) -> Result<DiffFiles, RepositoryError> {
+        Ok(self
+            .index_read()?
+            .entries
+            .into_iter()
+            .filter(|entry| entry.stage() == 0)
+            .map(|entry| (entry.path, (entry.mode, entry.id)))
+            .collect())
+    }
+
+    /// Tracked files as found in the working directory, hashed only when their
+    /// stat data changed since they were staged.
+    fn diff_work_files(&self) -> Result<DiffFiles, RepositoryError> {
+        let mut files = DiffFiles::new();
+        for entry in self.index_read()?.entries {
+            if entry.stage() != 0 {
+                continue;
+            }
+            let Some(metadata) = self.work_metadata(&entry.path)? else {
+                continue;
+            };
+            if metadata.mode == MODE_TREE {
+                continue;
+            }
+            let id = match entry.is_up_to_date(&metadata) {
+                true => entry.id,
+                false => self.work_blob(&entry.path, &metadata, false)?,
+            };
+            files.insert(entry.path, (metadata.mode, id));
+        }
+        Ok(files)
+    }
+
+    fn diff_files(
+        &self,
+        old_files: DiffFiles,
+        new_files: DiffFiles,
+        new_in_work: bool,
+        options: &DiffOptions,
+    ) -> Result<Vec<DiffEntry>, RepositoryError> {
+        let selected = |path: &str| {
+            options.paths.is_empty() || options.paths.iter().any(|prefix| is_under(path, prefix))
+        };
+        let side = |path: &str, (mode, id): &(u32, String), work: bool| DiffSide {
+            path: path.to_string(),
+            mode: *mode,
+            id: id.clone(),
+            work,
+        };
+
+        let mut entries = Vec::new();
+        for (path, old) in old_files.iter().filter(|(path, _)| selected(path)) {
+            let new = new_files.get(path);
+            let status = match new {
+                None => DiffStatus::Deleted,
+                Some(new) if new == old => continue,
+                Some(new) if !same_kind(old.0, new.0) => DiffStatus::TypeChanged,
+                Some(_) => DiffStatus::Modified,
+            };
+            entries.push(DiffEntry {
+                status,
+                old: Some(side(path, old, false)),
+                new: new.map(|new| side(path, new, new_in_work)),
+            });
+        }
+        for (path, new) in new_files.iter().filter(|(path, _)| selected(path)) {
+            if !old_files.contains_key(path) {
+                entries.push(DiffEntry {
+                    status: DiffStatus::Added,
+                    old: None,
+                    new: Some(side(path, new, new_in_work)),
+                });
+            }
+        }
+
+        if options.renames || options.copies {
+            entries = self.diff_detect_renames(entries, options)?;
+        }
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+        Ok(entries)
+    }
+
+    /// Turns added files into renames of deleted files, and with
+    /// `options.copies` into copies of modified files, when their content is
+    /// similar enough. Identical contents are paired first.
+    fn diff_detect_renames(
+        &self,
+        entries: Vec<DiffEntry>,
+        options: &DiffOptions,
+    ) -> Result<Vec<DiffEntry>, RepositoryError> {
+        let (added, entries): (Vec<DiffEntry>, Vec<DiffEntry>) = entries
+            .into_iter()
+            .partition(|entry| entry.status == DiffStatus::Added);
+        let mut added = added
+            .into_iter()
+            .filter_map(|entry| entry.new)
+            .collect::<Vec<DiffSide>>();
+        // Old sides that can be renamed or copied from; only deleted files can
+        // be renamed.
+        let mut sources = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                let deleted = entry.status == DiffStatus::Deleted;
+                let source =
+                    match deleted || (options.copies && entry.status == DiffStatus::Modified) {
+                        true => entry.old.clone()?,
+                        false => return None,
+                    };
+                Some((i, source, deleted))
+            })
+            .collect::<Vec<(usize, DiffSide, bool)>>();
+        if !options.renames {
+            sources.retain(|(_, _, deleted)| !deleted);
+        }
+
+        let mut pairs = Vec::new();
+        for (a, new) in added.iter().enumerate() {
+            for (s, (_, old, _)) in sources.iter().enumerate() {
+                if old.id == new.id && same_kind(old.mode, new.mode) {
+                    pairs.push((100, a, s));
+                }
+            }
+        }
+        if added.len() * sources.len() <= RENAME_LIMIT {
+            let mut contents = HashMap::new();
+            for (a, new) in added.iter().enumerate() {
+                let new_content = self.diff_content(new)?;
+                for (s, (_, old, _)) in sources.iter().enumerate() {
+                    if old.id == new.id || !same_kind(old.mode, new.mode) {
+                        continue;
+                    }
+                    let old_content = match contents.entry(s) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => entry.insert(self.diff_content(old)?),
+                    };
+                    let score = similarity(old_content, &new_content);
+                    if score >= options.threshold && !new_content.is_empty() {
+                        pairs.push((score, a, s));
+                    }
+                }
+            }
+        }
+        // Best scores first; renames win over copies and, among equals, the
+        // source with the same file name.
+        pairs.sort_by_key(|(score, a, s)| {
+            let (_, old, deleted) = &sources[*s];
+            let same_name = old.path.rsplit('/').next() == added[*a].path.rsplit('/').next();
+            (std::cmp::Reverse(*score), !deleted, !same_name, *a, *s)
+        });
+
+        let mut paired = vec![false; added.len()];
+        let mut renamed = vec![false; sources.len()];
+        let mut found = Vec::new();
+        for (score, a, s) in pairs {
+            let (_, old, deleted) = &sources[s];
+            if paired[a] || (*deleted && renamed[s]) {
+                continue;
+            }
+            let status = match deleted {
+                true => DiffStatus::Renamed(score),
+                false => DiffStatus::Copied(score),
+            };
+            paired[a] = true;
+            if *deleted {
+                renamed[s] = true;
+            }
+            found.push(DiffEntry {
+                status,
+                old: Some(old.clone()),
+                new: Some(added[a].clone()),
+            });
+        }
+
+        let renamed_entries = sources
+            .iter()
+            .zip(&renamed)
+            .filter(|(_, renamed)| **renamed)
+            .map(|((i, _, _), _)| *i)
+            .collect::<Vec<usize>>();
+        let mut entries = entries
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !renamed_entries.contains(i))
+            .map(|(_, entry)| entry)
+            .collect::<Vec<DiffEntry>>();
+        entries.extend(found);
+        for (a, new) in added.drain(..).enumerate() {
+            if !paired[a] {
+                entries.push(DiffEntry {
+                    status: DiffStatus::Added,
+                    old: None,
+                    new: Some(new),
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod test_similarity {
+    use super::similarity;
+
+    #[test]
+    fn similarity_score() {
+        assert_eq!(similarity(b"a\nb\nc\nd\n", b"a\nb\nc\nd\n"), 100);
+        assert_eq!(similarity(b"a\nb\nc\nd\n", b"a\nb\nx\ny\n"), 50);
+        assert_eq!(similarity(b"a\nb\n", b"a\nb\nc\nd\n"), 50);
+        assert_eq!(similarity(b"a\n", b"b\n"), 0);
+        assert_eq!(similarity(b"", b""), 100);
+    }
+}
+
+#[cfg(test)]
+mod test_diff_renames {
+    use super::{DiffOptions, DiffStatus};
+    use crate::gaal_core::provider::memory::{repository, write, MemoryCoreDirectory};
+
+    #[test]
+    fn rename_and_copy_detection() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "1\n2\n3\n4\n5\n6\n7\n8\n");
+        write("work/b.txt", "a\nb\nc\nd\n");
+        repository.index_add(&["a.txt", "b.txt"]).unwrap();
+        repository.commit_index("First", false).unwrap();
+        let head = repository.object_find("HEAD", Some("tree")).unwrap();
+
+        repository.index_remove(&["a.txt"], false, false).unwrap();
+        write("work/moved/a.txt", "1\n2\n3\n4\n5\n6\n7\nx\n");
+        write("work/b.txt", "a\nb\nc\nd\ne\n");
+        write("work/c.txt", "a\nb\nc\nd\n");
+        repository
+            .index_add(&["moved/a.txt", "b.txt", "c.txt"])
+            .unwrap();
+
+        let summary = |options: &DiffOptions| {
+            let entries = repository.diff_tree_to_index(Some(&head), options).unwrap();
+            entries
+                .iter()
+                .map(|entry| {
+                    let old = entry.old.as_ref().map(|side| side.path.as_str());
+                    (
+                        entry.status,
+                        old.unwrap_or("").to_string(),
+                        entry.path().to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let entry = |status, old: &str, new: &str| (status, old.to_string(), new.to_string());
+
+        assert_eq!(
+            summary(&DiffOptions::default()),
+            vec![
+                entry(DiffStatus::Deleted, "a.txt", "a.txt"),
+                entry(DiffStatus::Modified, "b.txt", "b.txt"),
+                entry(DiffStatus::Added, "", "c.txt"),
+                entry(DiffStatus::Added, "", "moved/a.txt"),
+            ]
+        );
+        let renames = DiffOptions {
+            renames: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            summary(&renames),
+            vec![
+                entry(DiffStatus::Modified, "b.txt", "b.txt"),
+                entry(DiffStatus::Added, "", "c.txt"),
+                entry(DiffStatus::Renamed(87), "a.txt", "moved/a.txt"),
+            ]
+        );
+        let copies = DiffOptions {
+            copies: true,
+            ..renames.clone()
+        };
+        assert_eq!(
+            summary(&copies),
+            vec![
+                entry(DiffStatus::Modified, "b.txt", "b.txt"),
+                entry(DiffStatus::Copied(100), "b.txt", "c.txt"),
+                entry(DiffStatus::Renamed(87), "a.txt", "moved/a.txt"),
+            ]
+        );
+        // Below the threshold the files stay added and deleted.
+        let strict = DiffOptions {
+            threshold: 90,
+            ..renames
+        };
+        assert!(summary(&strict).contains(&entry(DiffStatus::Added, "", "moved/a.txt")));
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    object::{
+        commit::Commit, hash::GaalHashAlgorithm, header::object_parse, kvlm::Kvlm, tree::Tree,
+        ObjectError,
+    },
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FsckIssue {
+    /// The stored object can't be decoded or doesn't hash to its id.
+    Corrupt { hash: String, reason: String },
+    /// An object referenced by a ref or by another object isn't stored.
+    Missing { hash: String, referenced_by: String },
+    /// A stored object that no ref and no other object points to.
+    Dangling { hash: String, fmt: String },
+}
+
+impl fmt::Display for FsckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsckIssue::Corrupt { hash, reason } => write!(f, "corrupt {}: {}", hash, reason),
+            FsckIssue::Missing {
+                hash,
+                referenced_by,
+            } => write!(f, "missing {} (referenced by {})", hash, referenced_by),
+            FsckIssue::Dangling { hash, fmt } => write!(f, "dangling {} {}", fmt, hash),
+        }
+    }
+}
+
+/// Returns the ids an object points to: the tree and parents of a commit, the
+/// target of a tag and the entries of a tree.
+fn object_links(
+    fmt: &str,
+    payload: &[u8],
+    format: GaalHashAlgorithm,
+) -> Result<Vec<String>, ObjectError> {
+    let links = match fmt {
+        "commit" => {
+            let commit = Commit::try_from(Kvlm::try_from(payload.to_vec())?)?;
+            std::iter::once(commit.tree).chain(commit.parents).collect()
+        }
+        "tag" => Kvlm::try_from(payload.to_vec())?
+            .get_all("object")
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        "tree" => Tree::parse(payload, format)?
+            .leafs
+            .into_iter()
+            // Submodule entries point into another repository.
+            .filter(|leaf| !leaf.mode.starts_with("16"))
+            .map(|leaf| leaf.sha)
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(links)
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Lists the ids of every loose object in the repository, sorted.
+    pub fn object_list(&self) -> Result<Vec<String>, RepositoryError> {
+        let mut objects = Vec::new();
+        self.object_list_at(self.gaal_path(&["objects"]), String::new(), &mut objects)?;
+        objects.sort();
+        Ok(objects)
+    }
+
+    fn object_list_at(
+        &self,
+        path: Vec<GCDA::PathItem>,
+        prefix: String,
+        objects: &mut Vec<String>,
+    ) -> Result<(), RepositoryError> {
+        for entry in self.directory().list_path(path.clone())? {
+            let mut entry_path = path.clone();
+            entry_path.push(entry.clone());
+            let name = format!("{}{}", prefix, entry.into());
+
+            if self.directory().is_path(entry_path.clone()) {
+                self.object_list_at(entry_path, name, objects)?;
+            } else {
+                objects.push(name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-hashes a stored object and returns its type and the ids it links to.
+    fn object_check(&self, hash: &str) -> Result<(String, Vec<String>), ObjectError> {
+        let raw = self.object_read_raw(hash)?;
+        let actual = self.object_format.hash(&raw);
+        if actual != hash {
+            return Err(ObjectError::HashMismatch {
+                expected: hash.to_string(),
+                actual,
+            });
+        }
+
+        let (fmt, payload) = object_parse(&raw)?;
+        Ok((
+            fmt.to_string(),
+            object_links(fmt, payload, self.object_format)?,
+        ))
+    }
+
+    /// Checks every loose object, every ref, every reflog entry, every staged
+    /// file and every link between objects, reporting corrupt, missing and dangling objects.
+    pub fn fsck(&self) -> Result<Vec<FsckIssue>, RepositoryError> {
+        let mut issues = Vec::new();
+        let objects = self.object_list()?;
+        let stored = objects.iter().cloned().collect::<HashSet<String>>();
+
+        let mut checked = HashMap::new();
+        for hash in &objects {
+            match self.object_check(hash) {
+                Ok(result) => {
+                    checked.insert(hash.clone(), result);
+                }
+                Err(e) => issues.push(FsckIssue::Corrupt {
+                    hash: hash.clone(),
+                    reason: e.to_string(),
+                }),
+            }
+        }
+
+        let mut referenced = HashSet::new();
+        let mut roots = self.ref_list("refs")?;
+        if let Some(head) = self.ref_resolve("HEAD")? {
+            roots.insert(0, ("HEAD".to_string(), head));
+        }
+        // Logged values stay reachable, e.g. the older stash entries.
+        let null = "0".repeat(self.object_format.hex_len());
+        for name in self.reflog_list()? {
+            for entry in self.reflog_read(&name)? {
+                for hash in [entry.old, entry.new] {
+                    if hash != null {
+                        roots.push((format!("reflog of `{}`", name), hash));
+                    }
+                }
+            }
+        }
+        for entry in self.index_read()?.entries {
+            roots.push((format!("index entry `{}`", entry.path), entry.id));
+        }
+        for (name, hash) in roots {
+            if !stored.contains(&hash) {
+                issues.push(FsckIssue::Missing {
+                    hash: hash.clone(),
+                    referenced_by: name,
+                });
+            }
+            referenced.insert(hash);
+        }
+
+        for hash in &objects {
+            let Some((_, links)) = checked.get(hash) else {
+                continue;
+            };
+            for link in links {
+                if !stored.contains(link) {
+                    issues.push(FsckIssue::Missing {
+                        hash: link.clone(),
+                        referenced_by: hash.clone(),
+                    });
+                }
+                referenced.insert(link.clone());
+            }
+        }
+
+        for hash in &objects {
+            if let Some((fmt, _)) = checked.get(hash) {
+                if !referenced.contains(hash) {
+                    issues.push(FsckIssue::Dangling {
+                        hash: hash.clone(),
+                        fmt: fmt.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod test_fsck {
+    use super::FsckIssue;
+    use crate::gaal_core::provider::{
+        directory::GaalCoreDirectoryActionsType,
+        directory_object::GaalCoreDirectoryObjectsActionsType,
+        memory::{repository, write, MemoryCoreDirectory, MemoryDirectory, MemoryObjects},
+        object::ObjectError,
+    };
+
+    /// Replaces the object stored at `path`, compressing `data` unless `raw` is set.
+    fn overwrite(path: Vec<String>, data: &[u8], raw: bool) {
+        MemoryDirectory::save_data(path, data.to_vec(), !raw).unwrap();
+    }
+
+    #[test]
+    fn fsck_clean_and_dangling() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        repository.commit_index("First", false).unwrap();
+        assert_eq!(repository.fsck().unwrap(), vec![]);
+
+        let blob = MemoryObjects::new_object(
+            "blob".to_string(),
+            b"unreferenced".to_vec(),
+            repository.object_format,
+        )
+        .unwrap();
+        let hash = repository.object_write(blob).unwrap();
+        assert_eq!(
+            repository.fsck().unwrap(),
+            vec![FsckIssue::Dangling {
+                hash,
+                fmt: "blob".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn fsck_reflog_roots() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        repository.commit_index("First", false).unwrap();
+        for content in ["first\n", "second\n"] {
+            write("work/a.txt", content);
+            repository.stash_push(None).unwrap();
+        }
+        // The first stash entry is only in the reflog of `refs/stash`.
+        assert_eq!(repository.reflog_read("refs/stash").unwrap().len(), 2);
+        assert_eq!(repository.fsck().unwrap(), vec![]);
+
+        let lost = repository.object_format.hash(b"lost");
+        repository
+            .reflog_append("refs/heads/gone", None, &lost, "gone")
+            .unwrap();
+        assert_eq!(
+            repository.fsck().unwrap(),
+            vec![FsckIssue::Missing {
+                hash: lost,
+                referenced_by: "reflog of `refs/heads/gone`".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn fsck_missing() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        let tree = repository.object_format.hash(b"tree 0\0");
+        let author = repository.author_signature().unwrap();
+        let commit = repository
+            .commit_tree(&tree, vec![], author, "Lost tree")
+            .unwrap();
+        assert_eq!(
+            repository.fsck().unwrap(),
+            vec![FsckIssue::Missing {
+                hash: tree,
+                referenced_by: commit
+            }]
+        );
+    }
+
+    #[test]
+    fn fsck_corrupt() {
+        let directory = MemoryCoreDirectory::default();
+        let mut repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        write("work/b.txt", "b\n");
+        repository.index_add(&["a.txt", "b.txt"]).unwrap();
+        let index = repository.index_read().unwrap();
+        let (a, b) = (index.entries[0].id.clone(), index.entries[1].id.clone());
+        let path = |hash: &str| {
+            let mut path = repository.gaal_path(&["objects"]);
+            path.extend(MemoryDirectory::hash_object_to_path(hash.to_string()));
+            path
+        };
+        overwrite(path(&a), b"blob\x002\x00z\n", false);
+        overwrite(path(&b), b"not zlib", true);
+
+        let issues = repository.fsck().unwrap();
+        assert_eq!(issues.len(), 2);
+        assert!(issues
+            .iter()
+            .all(|issue| matches!(issue, FsckIssue::Corrupt { .. })));
+
+        // Reads only re-hash objects when asked to.
+        repository.object_read(a.clone()).unwrap();
+        repository
+            .config_set(&["core", "verifyobjects"], "true")
+            .unwrap();
+        assert!(matches!(
+            repository.object_read(a),
+            Err(ObjectError::HashMismatch { .. })
+        ));
+        assert!(matches!(
+            repository.object_read(b),
+            Err(ObjectError::CorruptZlib(_))
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    object::commit::{parse_date, Signature},
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Builds a signature from `user.name` and `user.email`, which the
+    /// `GAAL_<ROLE>_NAME` and `GAAL_<ROLE>_EMAIL` environment variables override.
+    /// `GAAL_<ROLE>_DATE` (`<seconds> <+HHMM>`) replaces the current time, which is
+    /// otherwise recorded in UTC.
+    fn identity(&self, role: &str) -> Result<Signature, RepositoryError> {
+        let lookup = |field: &str, key: &str| {
+            std::env::var(format!("GAAL_{}_{}", role, field))
+                .ok()
+                .or_else(|| self.config_get(&["user", key]))
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| RepositoryError::MissingIdentity(format!("user.{}", key)))
+        };
+        let name = lookup("NAME", "name")?;
+        let email = lookup("EMAIL", "email")?;
+
+        let (time, offset) = match std::env::var(format!("GAAL_{}_DATE", role)) {
+            Ok(date) => parse_date(&date).ok_or_else(|| {
+                RepositoryError::InvalidIdentity(format!("GAAL_{}_DATE={}", role, date))
+            })?,
+            Err(_) => {
+                let time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs() as i64)
+                    .unwrap_or_default();
+                (time, 0)
+            }
+        };
+        Ok(Signature::new(&name, &email, time, offset))
+    }
+
+    /// Signature of whoever wrote a change, see `GAAL_AUTHOR_*`.
+    pub fn author_signature(&self) -> Result<Signature, RepositoryError> {
+        self.identity("AUTHOR")
+    }
+
+    /// Signature of whoever records a commit or a tag, see `GAAL_COMMITTER_*`.
+    pub fn committer_signature(&self) -> Result<Signature, RepositoryError> {
+        self.identity("COMMITTER")
+    }
+}
@@ -0,0 +1,427 @@
+use crate::gaal_core::provider::{
+    directory::{GaalCoreDirectoryActions, GaalEntryMetadata},
+    directory_object::GaalCoreDirectoryObjectsActions,
+    index::{Index, IndexEntry, MODE_SYMLINK, MODE_TREE},
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+const INDEX_FILE: &str = "index";
+const INDEX_LOCK_FILE: &str = "index.lock";
+
+/// Checks a slash separated path relative to the working directory, returning it
+/// without surrounding slashes. An empty path stands for the whole working tree.
+pub fn normalize_work_path(path: &str) -> Result<String, RepositoryError> {
+    let path = path.trim_matches('/');
+    let components = path
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<&str>>();
+    if components.contains(&"..") || path.contains('\0') {
+        return Err(RepositoryError::InvalidPath(path.to_string()));
+    }
+    Ok(components.join("/"))
+}
+
+/// Whether `path` is `prefix` itself or lies below it.
+pub fn is_under(path: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || path == prefix
+        || (path.starts_with(prefix) && path.as_bytes().get(prefix.len()) == Some(&b'/'))
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Provider path of `path`, relative to the working directory.
+    pub fn work_path(&self, path: &str) -> Vec<GCDA::PathItem> {
+        let mut items = self.work_dir.clone();
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            items.push(component.to_string().into());
+        }
+        items
+    }
+
+    /// Stat data of `path` in the working directory, `None` when it doesn't exist,
+    /// including when one of its parents is a file.
+    pub fn work_metadata(&self, path: &str) -> Result<Option<GaalEntryMetadata>, RepositoryError> {
+        match self.directory().metadata(self.work_path(path)) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Files below `path` in the working directory (or `path` itself when it's a
+    /// file), sorted, skipping the repository directory.
+    pub fn work_files(&self, path: &str) -> Result<Vec<String>, RepositoryError> {
+        let mut files = Vec::new();
+        if let Some(metadata) = self.work_metadata(path)? {
+            self.work_files_at(path, metadata, &mut files)?;
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn work_files_at(
+        &self,
+        path: &str,
+        metadata: GaalEntryMetadata,
+        files: &mut Vec<String>,
+    ) -> Result<(), RepositoryError> {
+        if metadata.mode != MODE_TREE {
+            files.push(path.to_string());
+            return Ok(());
+        }
+
+        let gaal_dir: String = self.directory().defaults.default_gal_dir.clone().into();
+        for entry in self.directory().list_path(self.work_path(path))? {
+            let entry: String = entry.into();
+            if path.is_empty() && entry == gaal_dir {
+                continue;
+            }
+            let entry_path = match path {
+                "" => entry,
+                _ => format!("{}/{}", path, entry),
+            };
+            if let Some(metadata) = self.work_metadata(&entry_path)? {
+                self.work_files_at(&entry_path, metadata, files)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Hashes the file at `path` as a blob, storing it when `write` is set. Symlinks
+    /// are stored as their target.
+    pub fn work_blob(
+        &self,
+        path: &str,
+        metadata: &GaalEntryMetadata,
+        write: bool,
+    ) -> Result<String, RepositoryError> {
+        let data: Vec<u8> = match metadata.mode {
+            MODE_SYMLINK => self
+                .directory()
+                .read_link(self.work_path(path))?
+                .into_bytes(),
+            _ => self
+                .directory()
+                .get_data(self.work_path(path), false)?
+                .into(),
+        };
+        let blob = GCDOA::new_object("blob".to_string(), data, self.object_format)?;
+        Ok(match write {
+            true => self.object_write(blob)?,
+            false => GCDOA::hash(blob, self.object_format)?.0,
+        })
+    }
+
+    /// Whether the file at `path` differs from what `entry` recorded, re-hashing it
+    /// only when the stat data changed.
+    pub fn work_is_modified(
+        &self,
+        path: &str,
+        entry: &IndexEntry,
+    ) -> Result<bool, RepositoryError> {
+        let Some(metadata) = self.work_metadata(path)? else {
+            return Ok(true);
+        };
+        if entry.is_up_to_date(&metadata) {
+            return Ok(false);
+        }
+        Ok(metadata.mode != entry.mode || self.work_blob(path, &metadata, false)? != entry.id)
+    }
+
+    /// Reads the staging area, which is empty until something is added.
+    pub fn index_read(&self) -> Result<Index, RepositoryError> {
+        let path = self.gaal_path(&[INDEX_FILE]);
+        if !self.directory().is_entry(path.clone()) {
+            return Ok(Index::default());
+        }
+        let data: Vec<u8> = self.directory().get_data(path, false)?.into();
+        Ok(Index::parse(&data, self.object_format)?)
+    }
+
+    /// Replaces the staging area, going through `index.lock` so readers never see
+    /// a partially written file.
+    pub fn index_write(&self, index: &Index) -> Result<(), RepositoryError> {
+        let lock = self.index_lock()?;
+        self.index_commit(index, lock)
+    }
+
+    /// Reads the staging area while holding `index.lock`, lets `update` change it
+    /// and writes it back, so concurrent writers fail instead of losing updates.
+    /// Nothing is written when `update` fails.
+    pub fn index_update<T>(
+        &self,
+        update: impl FnOnce(&mut Index) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        let lock = self.index_lock()?;
+        let result = self.index_read().and_then(|mut index| {
+            let value = update(&mut index)?;
+            Ok((index, value))
+        });
+        match result {
+            Ok((index, value)) => {
+                self.index_commit(&index, lock)?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.directory().remove_entry(lock)?;
+                Err(e)
+            }
+        }
+    }
+
+    fn index_lock(&self) -> Result<Vec<GCDA::PathItem>, RepositoryError> {
+        let lock = self.gaal_path(&[INDEX_LOCK_FILE]);
+        match self
+            .directory()
+            .create_data(lock.clone(), Vec::new().into())
+        {
+            Ok(()) => Ok(lock),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                Err(RepositoryError::LockedIndex)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes `index` to the held `lock` and moves it in place.
+    fn index_commit(
+        &self,
+        index: &Index,
+        lock: Vec<GCDA::PathItem>,
+    ) -> Result<(), RepositoryError> {
+        let result = index
+            .serialize(self.object_format)
+            .map_err(RepositoryError::from)
+            .and_then(|data| {
+                self.directory()
+                    .save_data(lock.clone(), data.into(), false)?;
+                self.directory()
+                    .rename_entry(lock.clone(), self.gaal_path(&[INDEX_FILE]))?;
+                Ok(())
+            });
+        if result.is_err() && self.directory().is_entry(lock.clone()) {
+            self.directory().remove_entry(lock)?;
+        }
+        result
+    }
+
+    /// Stages the files at or below each of `paths`, storing their content as blobs.
+    /// Staged files that no longer exist are unstaged. Returns the staged paths.
+    pub fn index_add(&self, paths: &[&str]) -> Result<Vec<String>, RepositoryError> {
+        self.index_update(|index| self.index_add_to(index, paths))
+    }
+
+    fn index_add_to(
+        &self,
+        index: &mut Index,
+        paths: &[&str],
+    ) -> Result<Vec<String>, RepositoryError> {
+        let mut added = Vec::new();
+
+        for path in paths {
+            let path = normalize_work_path(path)?;
+            let files = self.work_files(&path)?;
+            let removed = index
+                .entries
+                .iter()
+                .filter(|entry| is_under(&entry.path, &path) && !files.contains(&entry.path))
+                .map(|entry| entry.path.clone())
+                .collect::<Vec<String>>();
+            if files.is_empty() && removed.is_empty() {
+                return Err(RepositoryError::PathspecNoMatch(path));
+            }
+
+            for file in files {
+                let Some(metadata) = self.work_metadata(&file)? else {
+                    continue;
+                };
+                if index
+                    .get(&file)
+                    .is_some_and(|entry| entry.is_up_to_date(&metadata))
+                {
+                    continue;
+                }
+                let id = self.work_blob(&file, &metadata, true)?;
+                index.insert(IndexEntry::new(&file, &id, &metadata));
+                added.push(file);
+            }
+            for file in removed {
+                index.remove(&file);
+            }
+        }
+        Ok(added)
+    }
+
+    /// Unstages the files at or below each of `paths` and, unless `cached` is set,
+    /// deletes them from the working directory. Files whose content differs from
+    /// the staged one are kept unless `force` is set. Returns the removed paths.
+    pub fn index_remove(
+        &self,
+        paths: &[&str],
+        cached: bool,
+        force: bool,
+    ) -> Result<Vec<String>, RepositoryError> {
+        self.index_update(|index| self.index_remove_from(index, paths, cached, force))
+    }
+
+    fn index_remove_from(
+        &self,
+        index: &mut Index,
+        paths: &[&str],
+        cached: bool,
+        force: bool,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let mut matched: Vec<IndexEntry> = Vec::new();
+        for path in paths {
+            let path = normalize_work_path(path)?;
+            let entries = index
+                .entries
+                .iter()
+                .filter(|entry| is_under(&entry.path, &path))
+                .collect::<Vec<&IndexEntry>>();
+            if entries.is_empty() {
+                return Err(RepositoryError::PathspecNoMatch(path));
+            }
+            for entry in entries {
+                if !matched.iter().any(|other| other.path == entry.path) {
+                    matched.push(entry.clone());
+                }
+            }
+        }
+
+        // Every file is checked before any is deleted, so a refusal leaves the
+        // working directory as it was.
+        let mut existing = Vec::new();
+        for entry in &matched {
+            if !cached && self.work_metadata(&entry.path)?.is_some() {
+                if !force && self.work_is_modified(&entry.path, entry)? {
+                    return Err(RepositoryError::LocalChanges(entry.path.clone()));
+                }
+                existing.push(&entry.path);
+            }
+        }
+        for path in existing {
+            self.directory().remove_entry(self.work_path(path))?;
+        }
+
+        let mut removed = Vec::new();
+        for entry in matched {
+            index.remove(&entry.path);
+            removed.push(entry.path);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod test_work_path {
+    use super::{is_under, normalize_work_path};
+
+    #[test]
+    fn work_path_normalize() {
+        assert_eq!(
+            normalize_work_path("./src//main.rs/").unwrap(),
+            "src/main.rs"
+        );
+        assert_eq!(normalize_work_path(".").unwrap(), "");
+        assert!(normalize_work_path("../outside").is_err());
+    }
+
+    #[test]
+    fn work_path_is_under() {
+        assert!(is_under("src/main.rs", "src"));
+        assert!(is_under("src", "src"));
+        assert!(is_under("src/main.rs", ""));
+        assert!(!is_under("srcs/main.rs", "src"));
+    }
+}
+
+#[cfg(test)]
+mod test_index_lock {
+    use crate::gaal_core::internals::repository::RepositoryError;
+    use crate::gaal_core::provider::memory::{
+        read, remove, repository, write, MemoryCoreDirectory,
+    };
+
+    #[test]
+    fn index_add_holds_the_lock() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        write("work/b.txt", "b\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        assert!(read("work/.gal/index.lock").is_none());
+
+        write("work/.gal/index.lock", "");
+        assert!(matches!(
+            repository.index_add(&["b.txt"]),
+            Err(RepositoryError::LockedIndex)
+        ));
+        assert!(matches!(
+            repository.index_remove(&["a.txt"], true, false),
+            Err(RepositoryError::LockedIndex)
+        ));
+        remove("work/.gal/index.lock");
+
+        // A failed update leaves the index alone and releases the lock.
+        assert!(repository.index_add(&["missing.txt"]).is_err());
+        assert!(read("work/.gal/index.lock").is_none());
+        repository.index_add(&["b.txt"]).unwrap();
+        let paths = repository
+            .index_read()
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<String>>();
+        assert_eq!(paths, vec!["a.txt", "b.txt"]);
+    }
+}
+
+#[cfg(test)]
+mod test_index_remove {
+    use crate::gaal_core::internals::repository::RepositoryError;
+    use crate::gaal_core::provider::memory::{read, repository, write, MemoryCoreDirectory};
+
+    #[test]
+    fn index_remove_refuses_before_deleting() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/d/a", "a\n");
+        write("work/d/b", "b\n");
+        repository.index_add(&["d"]).unwrap();
+        repository.commit_index("First", false).unwrap();
+        write("work/d/b", "changed\n");
+
+        assert!(matches!(
+            repository.index_remove(&["d"], false, false),
+            Err(RepositoryError::LocalChanges(path)) if path == "d/b"
+        ));
+        assert_eq!(read("work/d/a").as_deref(), Some("a\n"));
+        assert_eq!(read("work/d/b").as_deref(), Some("changed\n"));
+        let status = repository.status().unwrap();
+        assert_eq!(status.entries.len(), 1);
+        assert_eq!(status.entries[0].path, "d/b");
+
+        assert_eq!(
+            repository.index_remove(&["d", "d/a"], false, true).unwrap(),
+            vec!["d/a", "d/b"]
+        );
+        assert_eq!(read("work/d/a"), None);
+        assert_eq!(read("work/d/b"), None);
+        assert!(repository.index_read().unwrap().entries.is_empty());
+    }
+}
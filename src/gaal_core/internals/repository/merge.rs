@@ -0,0 +1,669 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::gaal_core::{
+    internals::{
+        text_diff::{is_binary, DiffAlgorithm},
+        text_merge::{merge_lines, ConflictStyle, MergeLabels},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions,
+        directory_object::GaalCoreDirectoryObjectsActions,
+        index::{IndexEntry, MODE_EXECUTABLE, MODE_FILE},
+        object::tree::TreeLeaf,
+    },
+};
+
+use super::{checkout::same_leaf, default::GaalRepository, refs::PreviousValue, RepositoryError};
+
+const MERGE_HEAD: &str = "MERGE_HEAD";
+const MERGE_MSG: &str = "MERGE_MSG";
+/// Label of the base merged from several common ancestors.
+const VIRTUAL_BASE: &str = "merged common ancestors";
+
+/// How a merge ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeOutcome {
+    /// The merged commit was already reachable from `HEAD`.
+    UpToDate,
+    /// `HEAD` moved forward to this commit.
+    FastForward(String),
+    /// This merge commit was recorded.
+    Merged(String),
+    /// These paths were left with conflict stages in the index and markers in
+    /// the working directory; committing once they are added records the merge.
+    Conflicts(BTreeMap<String, MergeConflict>),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+    /// Record a merge commit even when `HEAD` could fast-forward.
+    pub no_ff: bool,
+    /// Fail unless `HEAD` can fast-forward.
+    pub ff_only: bool,
+    /// `merge.conflictstyle`, or `merge`, when `None`.
+    pub style: Option<ConflictStyle>,
+    pub algorithm: DiffAlgorithm,
+    /// Message of the merge commit, `Merge branch '<name>'` by default.
+    pub message: Option<String>,
+}
+
+/// A path both sides changed in incompatible ways.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// Versions of the base, ours and theirs, recorded as stages 1 to 3.
+    pub stages: [Option<TreeLeaf>; 3],
+    /// What to leave in the working directory: the merged content with conflict
+    /// markers, or the version that wasn't deleted.
+    pub content: Vec<u8>,
+    pub mode: u32,
+    /// Where `content` goes instead of the path itself, taken by a directory on
+    /// the other side, as `d~HEAD`.
+    pub work_path: Option<String>,
+}
+
+/// Result of a three-way merge of trees.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TreeMerge {
+    /// Merged files, conflicted paths left out.
+    pub files: BTreeMap<String, TreeLeaf>,
+    pub conflicts: BTreeMap<String, MergeConflict>,
+}
+
+/// Whether a file can be merged line by line.
+fn is_regular(leaf: &TreeLeaf) -> bool {
+    leaf.mode.starts_with("100")
+}
+
+/// A name next to `path` for the version of `side`, `<path>~<side>`, that no
+/// merged path nor directory uses.
+fn aside_path(path: &str, side: &str, merge: &TreeMerge, dirs: &BTreeSet<String>) -> String {
+    let name = format!("{}~{}", path, side.replace('/', "_"));
+    let taken = |candidate: &String| {
+        merge.files.contains_key(candidate)
+            || merge.conflicts.contains_key(candidate)
+            || merge
+                .conflicts
+                .values()
+                .any(|conflict| conflict.work_path.as_ref() == Some(candidate))
+            || dirs.contains(candidate)
+    };
+    std::iter::once(name.clone())
+        .chain((0..).map(|n| format!("{}_{}", name, n)))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or(name)
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Merges the changes `base` to `theirs` into `ours`, all trees, `None`
+    /// standing for an empty base. Files changed on one side take that version,
+    /// regular files changed on both are merged line by line, and anything else
+    /// changed on both sides is a conflict. So is a file where the other side
+    /// has a directory, its content kept aside under `<path>~<side>`.
+    pub fn merge_trees(
+        &self,
+        base: Option<&str>,
+        ours: &str,
+        theirs: &str,
+        labels: MergeLabels,
+        style: ConflictStyle,
+        algorithm: DiffAlgorithm,
+    ) -> Result<TreeMerge, RepositoryError> {
+        let files = |tree: Option<&str>| match tree {
+            Some(tree) => self.tree_files(tree),
+            None => Ok(BTreeMap::new()),
+        };
+        let base_files = files(base)?;
+        let our_files = files(Some(ours))?;
+        let their_files = files(Some(theirs))?;
+        let paths = base_files
+            .keys()
+            .chain(our_files.keys())
+            .chain(their_files.keys())
+            .collect::<BTreeSet<&String>>();
+
+        let read = |leaf: Option<&TreeLeaf>| match leaf {
+            Some(leaf) => {
+                Ok::<_, RepositoryError>(self.object_read(leaf.sha.clone())?.serialize()?)
+            }
+            None => Ok(Vec::new()),
+        };
+
+        let mut merge = TreeMerge::default();
+        for path in paths {
+            let (o, a, b) = (
+                base_files.get(path),
+                our_files.get(path),
+                their_files.get(path),
+            );
+            let clean = if same_leaf(a, b) || same_leaf(o, b) {
+                Some(a)
+            } else if same_leaf(o, a) {
+                Some(b)
+            } else {
+                None
+            };
+            if let Some(leaf) = clean {
+                if let Some(leaf) = leaf {
+                    merge.files.insert(path.clone(), leaf.clone());
+                }
+                continue;
+            }
+
+            let stages = [o.cloned(), a.cloned(), b.cloned()];
+            let (a, b) = match (a, b) {
+                (Some(a), Some(b)) if is_regular(a) && is_regular(b) => (a, b),
+                // Modified and deleted, or not regular files: keeps what is left.
+                (a, b) => {
+                    let leaf = a
+                        .or(b)
+                        .ok_or_else(|| RepositoryError::InvalidPath(path.clone()))?;
+                    merge.conflicts.insert(
+                        path.clone(),
+                        MergeConflict {
+                            stages,
+                            content: read(Some(leaf))?,
+                            mode: leaf.mode_value()?,
+                            work_path: None,
+                        },
+                    );
+                    continue;
+                }
+            };
+
+            // Takes the mode change of either side, ours when both changed it.
+            let base_mode = o.filter(|o| is_regular(o)).map(|o| o.mode.as_str());
+            let (mode, mode_conflict) = match (a.mode.as_str(), b.mode.as_str()) {
+                (a_mode, b_mode) if a_mode == b_mode => (a_mode, false),
+                (a_mode, b_mode) if Some(a_mode) == base_mode => (b_mode, false),
+                (a_mode, b_mode) if Some(b_mode) == base_mode => (a_mode, false),
+                (a_mode, _) => (a_mode, true),
+            };
+            let mode = match mode {
+                "100755" => MODE_EXECUTABLE,
+                _ => MODE_FILE,
+            };
+
+            let base_data = read(o.filter(|o| is_regular(o)))?;
+            let (our_data, their_data) = (read(Some(a))?, read(Some(b))?);
+            let (content, conflicts) = if a.sha == b.sha {
+                (our_data, 0)
+            } else if is_binary(&base_data) || is_binary(&our_data) || is_binary(&their_data) {
+                (our_data, 1)
+            } else {
+                let result =
+                    merge_lines(&base_data, &our_data, &their_data, labels, style, algorithm);
+                (result.content, result.conflicts)
+            };
+            if conflicts > 0 || mode_conflict {
+                merge.conflicts.insert(
+                    path.clone(),
+                    MergeConflict {
+                        stages,
+                        content,
+                        mode,
+                        work_path: None,
+                    },
+                );
+                continue;
+            }
+            let sha = self.object_write(GCDOA::new_object(
+                "blob".to_string(),
+                content,
+                self.object_format,
+            )?)?;
+            merge.files.insert(
+                path.clone(),
+                TreeLeaf {
+                    mode: format!("{:06o}", mode),
+                    path: path.clone(),
+                    sha,
+                },
+            );
+        }
+
+        let dirs = merge
+            .files
+            .keys()
+            .chain(merge.conflicts.keys())
+            .flat_map(|path| path.match_indices('/').map(|(i, _)| path[..i].to_string()))
+            .collect::<BTreeSet<String>>();
+        for path in &dirs {
+            let (o, a, b) = (
+                base_files.get(path),
+                our_files.get(path),
+                their_files.get(path),
+            );
+            // The directory comes from the side without the file.
+            let side = if a.is_some() {
+                labels.ours
+            } else {
+                labels.theirs
+            };
+            let work_path = aside_path(path, side, &merge, &dirs);
+            let mut conflict = match merge.files.remove(path) {
+                Some(leaf) => MergeConflict {
+                    stages: [o.cloned(), a.cloned(), b.cloned()],
+                    content: read(Some(&leaf))?,
+                    mode: leaf.mode_value()?,
+                    work_path: None,
+                },
+                None => match merge.conflicts.remove(path) {
+                    Some(conflict) => conflict,
+                    None => continue,
+                },
+            };
+            conflict.work_path = Some(work_path);
+            merge.conflicts.insert(path.clone(), conflict);
+        }
+        Ok(merge)
+    }
+
+    /// Moves the working directory and the index from the tree of `HEAD` to the
+    /// result of `merge`, with the checks of `checkout`. Conflicted paths get
+    /// their content in the working directory and their stages in the index.
+    pub fn merge_apply(&self, merge: &TreeMerge) -> Result<(), RepositoryError> {
+        let head = self.head_files()?;
+        self.index_update(|index| {
+            // Conflicted paths always change, which the checks see as a leaf no tree
+            // holds.
+            let mut target = merge.files.clone();
+            for (path, conflict) in &merge.conflicts {
+                let path = conflict.work_path.as_ref().unwrap_or(path);
+                let leaf = TreeLeaf {
+                    mode: format!("{:06o}", conflict.mode),
+                    path: path.clone(),
+                    sha: String::new(),
+                };
+                target.insert(path.clone(), leaf);
+            }
+            self.checkout_check(&head, &target, index)?;
+
+            for path in head.keys().filter(|path| !target.contains_key(*path)) {
+                if self.work_metadata(path)?.is_some() {
+                    self.directory().remove_entry(self.work_path(path))?;
+                    self.work_prune(path)?;
+                }
+                index.remove(path);
+            }
+            for (path, leaf) in &merge.files {
+                if !same_leaf(head.get(path), Some(leaf)) {
+                    index.insert(self.checkout_file(leaf)?);
+                }
+            }
+            for (path, conflict) in &merge.conflicts {
+                let work_path = conflict.work_path.as_ref().unwrap_or(path);
+                self.work_write(work_path, conflict.mode, &conflict.content)?;
+                index.remove(path);
+                for (stage, leaf) in conflict.stages.iter().enumerate() {
+                    let Some(leaf) = leaf else {
+                        continue;
+                    };
+                    let mut entry = IndexEntry {
+                        path: path.clone(),
+                        mode: leaf.mode_value()?,
+                        id: leaf.sha.clone(),
+                        ..Default::default()
+                    };
+                    entry.set_stage(stage as u8 + 1);
+                    index.insert_stage(entry);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Merges `revision` into `HEAD`. `HEAD` is fast-forwarded when it is an
+    /// ancestor of `revision`, unless `options.no_ff` is set. Otherwise the trees
+    /// are merged from their common ancestors, merged together first when there
+    /// are several, and a commit with both parents is recorded, or, on
+    /// conflicts, `MERGE_HEAD` and `MERGE_MSG` are kept for the commit
+    /// concluding the merge. The index must match `HEAD`.
+    pub fn merge(
+        &self,
+        revision: &str,
+        options: &MergeOptions,
+    ) -> Result<MergeOutcome, RepositoryError> {
+        let theirs = self.object_peel_to(&self.rev_parse(revision)?, "commit")?;
+        let Some(head) = self.ref_resolve("HEAD")? else {
+            self.checkout_files(
+                &self.tree_files(&self.object_peel_to(&theirs, "tree")?)?,
+                false,
+            )?;
+            self.ref_update("HEAD", &theirs, PreviousValue::MustNotExist)?;
+            return Ok(MergeOutcome::FastForward(theirs));
+        };
+        self.operation_check()?;
+
+        let bases = self.merge_bases(&head, &theirs)?;
+        if bases.contains(&theirs) {
+            return Ok(MergeOutcome::UpToDate);
+        }
+        let fast_forward = bases.contains(&head);
+        if options.ff_only && !fast_forward {
+            return Err(RepositoryError::NotFastForward(revision.to_string()));
+        }
+        if fast_forward && !options.no_ff {
+            self.checkout_files(
+                &self.tree_files(&self.object_peel_to(&theirs, "tree")?)?,
+                false,
+            )?;
+            self.ref_update("HEAD", &theirs, PreviousValue::MustBe(head))?;
+            return Ok(MergeOutcome::FastForward(theirs));
+        }
+        let Some(base) = bases.first() else {
+            return Err(RepositoryError::UnrelatedHistories(revision.to_string()));
+        };
+
+        self.merge_check_index()?;
+
+        let style = self.conflict_style(options.style)?;
+        let base_label = match bases.len() {
+            1 => &base[..7],
+            _ => VIRTUAL_BASE,
+        };
+        let labels = MergeLabels {
+            base: base_label,
+            ours: "HEAD",
+            theirs: revision,
+        };
+        let tree = |commit: &str| self.object_peel_to(commit, "tree");
+        let merge = self.merge_trees(
+            self.merge_base_tree(&bases, style, options.algorithm)?
+                .as_deref(),
+            &tree(&head)?,
+            &tree(&theirs)?,
+            labels,
+            style,
+            options.algorithm,
+        )?;
+        self.merge_apply(&merge)?;
+
+        let message = match &options.message {
+            Some(message) => message.clone(),
+            None => match self.ref_resolve(&format!("refs/heads/{}", revision)) {
+                Ok(Some(_)) => format!("Merge branch '{}'", revision),
+                _ => format!("Merge commit '{}'", revision),
+            },
+        };
+        if !merge.conflicts.is_empty() {
+            self.ref_detach(MERGE_HEAD, &theirs)?;
+            self.merge_message_write(&message)?;
+            return Ok(MergeOutcome::Conflicts(merge.conflicts));
+        }
+        let tree = self.tree_write_from_index()?;
+        let hash = self.commit_tree(
+            &tree,
+            vec![head, theirs],
+            self.author_signature()?,
+            &message,
+        )?;
+        Ok(MergeOutcome::Merged(hash))
+    }
+
+    /// Tree a merge of commits with the common ancestors `bases` starts from:
+    /// the tree of the only one, or, after criss-cross merges, a virtual base
+    /// merging them one by one from their own common ancestors, as the
+    /// recursive strategy of git does. Conflicts stay in it with their markers.
+    /// `None` without any base.
+    fn merge_base_tree(
+        &self,
+        bases: &[String],
+        style: ConflictStyle,
+        algorithm: DiffAlgorithm,
+    ) -> Result<Option<String>, RepositoryError> {
+        let Some((first, others)) = bases.split_first() else {
+            return Ok(None);
+        };
+        let mut merged = vec![first.clone()];
+        let mut tree = self.object_peel_to(first, "tree")?;
+        for other in others {
+            let mut inner = Vec::new();
+            for base in &merged {
+                inner.extend(self.merge_bases(base, other)?);
+            }
+            inner.sort();
+            inner.dedup();
+            let labels = MergeLabels {
+                base: VIRTUAL_BASE,
+                ours: "Temporary merge branch 1",
+                theirs: "Temporary merge branch 2",
+            };
+            let merge = self.merge_trees(
+                self.merge_base_tree(&inner, style, algorithm)?.as_deref(),
+                &tree,
+                &self.object_peel_to(other, "tree")?,
+                labels,
+                style,
+                algorithm,
+            )?;
+
+            let mut files = Vec::new();
+            for (path, leaf) in merge.files {
+                files.push((path, leaf.mode_value()?, leaf.sha));
+            }
+            for (path, conflict) in merge.conflicts {
+                let id = self.object_write(GCDOA::new_object(
+                    "blob".to_string(),
+                    conflict.content,
+                    self.object_format,
+                )?)?;
+                files.push((conflict.work_path.unwrap_or(path), conflict.mode, id));
+            }
+            tree = self.tree_write_files(&files)?;
+            merged.push(other.clone());
+        }
+        Ok(Some(tree))
+    }
+
+    /// Fails unless the index matches `HEAD`: staged changes would end up in the
+    /// commit recording a merge.
+    pub(super) fn merge_check_index(&self) -> Result<(), RepositoryError> {
+        let head_files = self.head_files()?;
+        let index = self.index_read()?;
+        let staged_paths = index.entries.iter().map(|entry| &entry.path);
+        for path in head_files
+            .keys()
+            .chain(staged_paths)
+            .collect::<BTreeSet<_>>()
+        {
+            let staged = match (head_files.get(path), index.get(path)) {
+                (Some(leaf), Some(entry)) => {
+                    leaf.sha != entry.id || leaf.mode != entry.mode_string()
+                }
+                _ => true,
+            };
+            if staged {
+                return Err(RepositoryError::LocalChanges(path.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `style`, or `merge.conflictstyle` when `None`.
+    pub(super) fn conflict_style(
+        &self,
+        style: Option<ConflictStyle>,
+    ) -> Result<ConflictStyle, RepositoryError> {
+        match style {
+            Some(style) => Ok(style),
+            None => Ok(self
+                .config_get(&["merge", "conflictstyle"])
+                .map(|style| style.parse().map_err(RepositoryError::InvalidConfig))
+                .transpose()?
+                .unwrap_or_default()),
+        }
+    }
+
+    /// Keeps `message` for the commit concluding a conflicted operation.
+    pub(super) fn merge_message_write(&self, message: &str) -> Result<(), RepositoryError> {
+        self.directory().save_data(
+            self.gaal_path(&[MERGE_MSG]),
+            format!("{}\n", message.trim_end_matches('\n'))
+                .into_bytes()
+                .into(),
+            false,
+        )?;
+        Ok(())
+    }
+
+    /// The commit being merged while a merge waits for its conflicts to be
+    /// resolved.
+    pub fn merge_head(&self) -> Result<Option<String>, RepositoryError> {
+        self.ref_resolve(MERGE_HEAD)
+    }
+
+    /// Message prepared for the commit concluding a merge, a cherry-pick or a
+    /// revert.
+    pub fn merge_message(&self) -> Result<Option<String>, RepositoryError> {
+        let path = self.gaal_path(&[MERGE_MSG]);
+        if !self.directory().is_entry(path.clone()) {
+            return Ok(None);
+        }
+        let data: Vec<u8> = self.directory().get_data(path, false)?.into();
+        Ok(Some(String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    /// Forgets the merge, cherry-pick or revert in progress, if any.
+    pub fn merge_state_clear(&self) -> Result<(), RepositoryError> {
+        self.pick_state_clear()?;
+        if self.merge_head()?.is_some() {
+            self.ref_delete(MERGE_HEAD, PreviousValue::Any)?;
+        }
+        let path = self.gaal_path(&[MERGE_MSG]);
+        if self.directory().is_entry(path.clone()) {
+            self.directory().remove_entry(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_merge {
+    use super::{MergeOptions, MergeOutcome};
+    use crate::gaal_core::provider::memory::{read, repository, write, MemoryCoreDirectory};
+
+    /// ```text
+    /// r - a - m1 - x
+    ///   \   X
+    ///     b - m2 - y
+    /// ```
+    /// `x` and `y` have two common ancestors, `a` and `b`: either alone as the
+    /// base makes the change of the other one look like a conflict.
+    #[test]
+    fn merge_criss_cross() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        let commit = |path: &str, content: &str| {
+            write(&format!("work/{}", path), content);
+            repository.index_add(&[path]).unwrap();
+            repository.commit_index(path, false).unwrap()
+        };
+        let root = commit("f", "r\n");
+        repository.ref_create("refs/heads/side", &root).unwrap();
+        let a = commit("f", "a\n");
+        repository.checkout("side", false).unwrap();
+        let b = commit("g", "b\n");
+        let options = MergeOptions::default();
+        assert!(matches!(
+            repository.merge(&a, &options).unwrap(),
+            MergeOutcome::Merged(_)
+        ));
+        repository.checkout("master", false).unwrap();
+        assert!(matches!(
+            repository.merge(&b, &options).unwrap(),
+            MergeOutcome::Merged(_)
+        ));
+        let x = commit("f", "x\n");
+        repository.checkout("side", false).unwrap();
+        let y = commit("g", "y\n");
+        repository.checkout("master", false).unwrap();
+        assert_eq!(repository.merge_bases(&x, &y).unwrap().len(), 2);
+
+        let outcome = repository.merge("side", &options).unwrap();
+        assert!(matches!(outcome, MergeOutcome::Merged(_)));
+        assert_eq!(read("work/f").as_deref(), Some("x\n"));
+        assert_eq!(read("work/g").as_deref(), Some("y\n"));
+    }
+
+    #[test]
+    fn merge_file_and_directory() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+
+        repository.ref_create("refs/heads/side", &first).unwrap();
+        repository.checkout("side", false).unwrap();
+        write("work/d/f", "nested\n");
+        repository.index_add(&["d"]).unwrap();
+        repository.commit_index("Directory", false).unwrap();
+
+        repository.checkout("master", false).unwrap();
+        write("work/d", "file\n");
+        repository.index_add(&["d"]).unwrap();
+        repository.commit_index("File", false).unwrap();
+
+        let MergeOutcome::Conflicts(conflicts) =
+            repository.merge("side", &MergeOptions::default()).unwrap()
+        else {
+            panic!("expected conflicts");
+        };
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts["d"];
+        assert_eq!(conflict.work_path.as_deref(), Some("d~HEAD"));
+        assert!(matches!(conflict.stages, [None, Some(_), None]));
+        assert_eq!(read("work/d~HEAD").as_deref(), Some("file\n"));
+        assert_eq!(read("work/d/f").as_deref(), Some("nested\n"));
+
+        let index = repository.index_read().unwrap();
+        let staged = index
+            .entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.stage()))
+            .collect::<Vec<_>>();
+        assert_eq!(staged, vec![("a.txt", 0), ("d", 2), ("d/f", 0)]);
+    }
+
+    #[test]
+    fn merge_directory_and_file() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+
+        repository
+            .ref_create("refs/heads/feature/file", &first)
+            .unwrap();
+        repository.checkout("feature/file", false).unwrap();
+        write("work/d", "file\n");
+        repository.index_add(&["d"]).unwrap();
+        repository.commit_index("File", false).unwrap();
+
+        repository.checkout("master", false).unwrap();
+        write("work/d/f", "nested\n");
+        repository.index_add(&["d"]).unwrap();
+        repository.commit_index("Directory", false).unwrap();
+
+        let MergeOutcome::Conflicts(conflicts) = repository
+            .merge("feature/file", &MergeOptions::default())
+            .unwrap()
+        else {
+            panic!("expected conflicts");
+        };
+        let conflict = &conflicts["d"];
+        assert_eq!(conflict.work_path.as_deref(), Some("d~feature_file"));
+        assert!(matches!(conflict.stages, [None, None, Some(_)]));
+        assert_eq!(read("work/d~feature_file").as_deref(), Some("file\n"));
+        assert_eq!(read("work/d/f").as_deref(), Some("nested\n"));
+        let index = repository.index_read().unwrap();
+        assert_eq!(index.entries[1].path, "d");
+        assert_eq!(index.entries[1].stage(), 3);
+    }
+}
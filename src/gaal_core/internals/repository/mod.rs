@@ -1,6 +1,27 @@
 use thiserror::Error;
 
+use crate::gaal_core::provider::object::ObjectError;
+
+pub mod ancestry;
+pub mod branch;
+pub mod checkout;
+pub mod commit;
 pub mod default;
+pub mod diff;
+pub mod fsck;
+pub mod identity;
+pub mod index;
+pub mod merge;
+pub mod rebase;
+pub mod reflog;
+pub mod refs;
+pub mod revision;
+pub mod sequencer;
+pub mod stash;
+pub mod status;
+pub mod tag;
+pub mod tree;
+pub mod walk;
 
 #[derive(Error, Debug)]
 pub enum RepositoryError {
@@ -8,4 +29,77 @@ pub enum RepositoryError {
     Inexistent(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("Object error: {0}")]
+    ObjectError(#[from] ObjectError),
+    #[error("No such reference: `{0}`")]
+    UnknownReference(String),
+    #[error("Reference `{0}` is ambiguous: {1:?}")]
+    AmbiguousReference(String, Vec<String>),
+    #[error("Reference `{0}` already exists")]
+    ReferenceExists(String),
+    #[error("Invalid revision: `{0}`")]
+    InvalidRevision(String),
+    #[error("Branch `{0}` has no upstream configured")]
+    NoUpstream(String),
+    #[error("Path `{path}` does not exist in `{revision}`")]
+    UnknownPath { path: String, revision: String },
+    #[error("Path `{0}` did not match any file")]
+    PathspecNoMatch(String),
+    #[error("Invalid path: `{0}`")]
+    InvalidPath(String),
+    #[error("The index is locked by another process")]
+    LockedIndex,
+    #[error("Unmerged paths: {0:?}")]
+    UnmergedPaths(Vec<String>),
+    #[error("`{0}` has local changes")]
+    LocalChanges(String),
+    #[error("Untracked file `{0}` would be overwritten")]
+    UntrackedOverwritten(String),
+    #[error("Invalid reference name: `{0}`")]
+    InvalidReference(String),
+    #[error("Symbolic reference loop through `{0}`")]
+    SymbolicRefLoop(String),
+    #[error("Reference `{0}` is locked by another update")]
+    LockedReference(String),
+    #[error("Reference `{name}` doesn't hold the expected value")]
+    ReferenceMismatch {
+        name: String,
+        actual: Option<String>,
+    },
+    #[error("No identity configured, set `{0}`")]
+    MissingIdentity(String),
+    #[error("Invalid identity: `{0}`")]
+    InvalidIdentity(String),
+    #[error("Nothing to commit")]
+    NothingToCommit,
+    #[error("Branch `{0}` is checked out")]
+    CurrentBranch(String),
+    #[error("Branch `{0}` is not fully merged")]
+    BranchNotMerged(String),
+    #[error("A merge is in progress, commit it first")]
+    MergeInProgress,
+    #[error("A {0} is in progress, continue or abort it first")]
+    PickInProgress(String),
+    #[error("No cherry-pick or revert in progress")]
+    NoPickInProgress,
+    #[error("Invalid mainline for `{0}`: {1}")]
+    InvalidMainline(String, String),
+    #[error("A rebase is in progress, continue, skip or abort it first")]
+    RebaseInProgress,
+    #[error("No rebase in progress")]
+    NoRebaseInProgress,
+    #[error("Invalid todo list: {0}")]
+    InvalidTodo(String),
+    #[error("Aborting due to empty commit message")]
+    EmptyMessage,
+    #[error("No local changes to save")]
+    NoLocalChanges,
+    #[error("No stash entry `{0}`")]
+    NoSuchStash(String),
+    #[error("Can't fast-forward to `{0}`")]
+    NotFastForward(String),
+    #[error("No common ancestor with `{0}`")]
+    UnrelatedHistories(String),
+    #[error("Invalid config value: {0}")]
+    InvalidConfig(String),
 }
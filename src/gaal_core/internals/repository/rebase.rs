@@ -0,0 +1,760 @@
+use std::collections::BTreeMap;
+
+use crate::gaal_core::{
+    internals::{text_diff::DiffAlgorithm, text_merge::MergeLabels},
+    provider::{
+        directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    },
+};
+
+use super::{
+    default::GaalRepository,
+    merge::MergeConflict,
+    refs::PreviousValue,
+    walk::{WalkOptions, WalkOrder},
+    RepositoryError,
+};
+
+/// Directory of the repository holding the state of a rebase in progress.
+const REBASE_DIR: &str = "rebase-merge";
+/// Branch being rebased, or `detached HEAD`.
+const HEAD_NAME: &str = "head-name";
+const ORIG_HEAD: &str = "orig-head";
+const ONTO: &str = "onto";
+/// Lines left to run.
+const TODO: &str = "todo";
+/// Lines already run, the last one being the current one.
+const DONE: &str = "done";
+/// Commit whose changes stopped on conflicts.
+const STOPPED_SHA: &str = "stopped-sha";
+/// Message of the commit recording the resolved conflicts.
+const MESSAGE: &str = "message";
+/// Commit recorded by an `edit` line, amended with the changes staged before
+/// continuing.
+const AMEND: &str = "amend";
+const DETACHED_HEAD: &str = "detached HEAD";
+
+const TODO_HELP: &str = "
+# Commands:
+# p, pick <commit> = use commit
+# r, reword <commit> = use commit, but edit the commit message
+# e, edit <commit> = use commit, but stop for amending
+# s, squash <commit> = use commit, but meld into previous commit
+# f, fixup <commit> = like \"squash\", but discard this commit's message
+# x, exec <command> = run command using shell
+# d, drop <commit> = remove commit
+#
+# These lines can be re-ordered; they are executed from top to bottom.
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TodoCommand {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+    Exec,
+}
+
+impl TodoCommand {
+    pub fn name(self) -> &'static str {
+        match self {
+            TodoCommand::Pick => "pick",
+            TodoCommand::Reword => "reword",
+            TodoCommand::Edit => "edit",
+            TodoCommand::Squash => "squash",
+            TodoCommand::Fixup => "fixup",
+            TodoCommand::Drop => "drop",
+            TodoCommand::Exec => "exec",
+        }
+    }
+}
+
+impl std::str::FromStr for TodoCommand {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "p" | "pick" => Ok(TodoCommand::Pick),
+            "r" | "reword" => Ok(TodoCommand::Reword),
+            "e" | "edit" => Ok(TodoCommand::Edit),
+            "s" | "squash" => Ok(TodoCommand::Squash),
+            "f" | "fixup" => Ok(TodoCommand::Fixup),
+            "d" | "drop" => Ok(TodoCommand::Drop),
+            "x" | "exec" => Ok(TodoCommand::Exec),
+            _ => Err(format!("Unknown command `{}`", name)),
+        }
+    }
+}
+
+/// A line of the todo list of a rebase.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TodoItem {
+    pub command: TodoCommand,
+    /// Commit to apply, or the shell command of `exec`.
+    pub argument: String,
+    /// Subject of the commit, only there for the reader.
+    pub subject: String,
+}
+
+impl std::fmt::Display for TodoItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.command.name(), self.argument)?;
+        if !self.subject.is_empty() {
+            write!(f, " {}", self.subject)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a todo list, skipping blank lines and `#` comments.
+pub fn parse_todo(text: &str) -> Result<Vec<TodoItem>, String> {
+    let mut items: Vec<TodoItem> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let command: TodoCommand = command.parse()?;
+        let rest = rest.trim();
+        let (argument, subject) = match command {
+            TodoCommand::Exec => (rest, ""),
+            _ => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+        };
+        if argument.is_empty() {
+            return Err(format!("Missing argument in `{}`", line));
+        }
+        items.push(TodoItem {
+            command,
+            argument: argument.to_string(),
+            subject: subject.trim().to_string(),
+        });
+    }
+    Ok(items)
+}
+
+/// Writes `items` one per line, as read by `parse_todo`.
+pub fn format_todo(items: &[TodoItem]) -> String {
+    items.iter().map(|item| format!("{}\n", item)).collect()
+}
+
+/// Drops the `#` comments and the surrounding blank lines of an edited message.
+pub fn clean_message(message: &str) -> String {
+    message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Lets the user change a text, e.g. in an editor, returning the new version.
+pub type TextEditor<'e> = dyn FnMut(&str) -> Result<String, RepositoryError> + 'e;
+/// Runs a shell command, returning whether it succeeded.
+pub type CommandRunner<'r> = dyn FnMut(&str) -> Result<bool, RepositoryError> + 'r;
+
+/// What a rebase asks of the user.
+pub struct RebaseHooks<'h> {
+    /// Edits the todo list of an interactive rebase.
+    pub edit_todo: Box<TextEditor<'h>>,
+    /// Edits the message of a `reword` or a `squash`.
+    pub edit_message: Box<TextEditor<'h>>,
+    /// Runs the command of an `exec`.
+    pub exec: Box<CommandRunner<'h>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RebaseOptions {
+    /// Base the commits are replayed onto, the upstream when `None`.
+    pub onto: Option<String>,
+    /// Let the user edit the todo list before starting.
+    pub interactive: bool,
+}
+
+/// Why a rebase stopped before the end of its todo list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RebaseStop {
+    /// Applying the commit of this line left these paths with conflicts.
+    Conflicts(TodoItem, BTreeMap<String, MergeConflict>),
+    /// This `edit` line was applied; staged changes amend it when continuing.
+    Edit(TodoItem),
+    /// This `exec` command failed.
+    ExecFailed(String),
+}
+
+/// How a rebase ended, or paused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RebaseOutcome {
+    /// The commits already sat on the new base.
+    UpToDate,
+    /// Every line ran, the branch now points to this commit.
+    Done(String),
+    Stopped(RebaseStop),
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Replays the commits of `HEAD` missing from `upstream` (the upstream of the
+    /// current branch when `None`) onto `options.onto`, merge commits left out.
+    /// `HEAD` is detached while the todo list runs, and the branch only moves
+    /// once every line succeeded. The index must match `HEAD`.
+    pub fn rebase(
+        &self,
+        upstream: Option<&str>,
+        options: &RebaseOptions,
+        hooks: &mut RebaseHooks,
+    ) -> Result<RebaseOutcome, RepositoryError> {
+        self.operation_check()?;
+        let head = self
+            .ref_resolve("HEAD")?
+            .ok_or_else(|| RepositoryError::UnknownReference("HEAD".to_string()))?;
+        self.merge_check_index()?;
+        let commit = |revision: &str| -> Result<String, RepositoryError> {
+            Ok(self.object_peel_to(&self.rev_parse(revision)?, "commit")?)
+        };
+        let upstream = commit(upstream.unwrap_or("@{upstream}"))?;
+        let onto = match &options.onto {
+            Some(onto) => commit(onto)?,
+            None => upstream.clone(),
+        };
+        if !options.interactive && self.merge_base(&upstream, &head)? == Some(onto.clone()) {
+            return Ok(RebaseOutcome::UpToDate);
+        }
+
+        let walk_options = WalkOptions {
+            order: WalkOrder::Topological,
+            reverse: true,
+            ..Default::default()
+        };
+        let mut todo = Vec::new();
+        for entry in self.rev_walk(&[&format!("{}..{}", upstream, head)], walk_options)? {
+            let (hash, commit) = entry?;
+            if commit.parents.len() <= 1 {
+                todo.push(TodoItem {
+                    command: TodoCommand::Pick,
+                    argument: hash,
+                    subject: commit
+                        .message
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            }
+        }
+        if options.interactive {
+            todo = self.rebase_todo_edit(&todo, hooks.edit_todo.as_mut())?;
+            if todo.is_empty() {
+                return Err(RepositoryError::InvalidTodo("Nothing to do".to_string()));
+            }
+        }
+        self.rebase_todo_expand(&mut todo)?;
+        let first = todo
+            .iter()
+            .find(|item| !matches!(item.command, TodoCommand::Drop | TodoCommand::Exec));
+        if let Some(TodoItem {
+            command: command @ (TodoCommand::Squash | TodoCommand::Fixup),
+            ..
+        }) = first
+        {
+            return Err(RepositoryError::InvalidTodo(format!(
+                "Cannot `{}` without a previous commit",
+                command.name()
+            )));
+        }
+
+        let head_name = match self.head_branch()? {
+            Some(branch) => format!("refs/heads/{}", branch),
+            None => DETACHED_HEAD.to_string(),
+        };
+        self.checkout_files(
+            &self.tree_files(&self.object_peel_to(&onto, "tree")?)?,
+            false,
+        )?;
+        self.ref_detach("HEAD", &onto)?;
+        self.directory().make_path(self.gaal_path(&[REBASE_DIR]))?;
+        self.rebase_write(HEAD_NAME, &head_name)?;
+        self.rebase_write(ORIG_HEAD, &head)?;
+        self.rebase_write(ONTO, &onto)?;
+        self.rebase_write(TODO, &format_todo(&todo))?;
+        self.rebase_write(DONE, "")?;
+        self.rebase_run(hooks)
+    }
+
+    /// Whether a rebase waits to be continued, skipped or aborted.
+    pub fn rebase_in_progress(&self) -> bool {
+        self.directory().is_path(self.gaal_path(&[REBASE_DIR]))
+    }
+
+    /// Resumes the rebase in progress: records the resolved conflicts of the
+    /// stopped line, or amends the commit of an `edit` line with the staged
+    /// changes, then runs the rest of the todo list.
+    pub fn rebase_continue(
+        &self,
+        hooks: &mut RebaseHooks,
+    ) -> Result<RebaseOutcome, RepositoryError> {
+        if !self.rebase_in_progress() {
+            return Err(RepositoryError::NoRebaseInProgress);
+        }
+        if let Some(hash) = self.rebase_read(STOPPED_SHA)? {
+            let done = parse_todo(&self.rebase_read(DONE)?.unwrap_or_default())
+                .map_err(RepositoryError::InvalidTodo)?;
+            let item = done
+                .last()
+                .ok_or_else(|| RepositoryError::InvalidTodo("No stopped line".to_string()))?;
+            let message = self.rebase_read(MESSAGE)?.unwrap_or_default();
+            let stop = self.rebase_commit(item, &hash, &message, hooks)?;
+            self.rebase_remove(STOPPED_SHA)?;
+            self.rebase_remove(MESSAGE)?;
+            if let Some(stop) = stop {
+                return Ok(RebaseOutcome::Stopped(stop));
+            }
+        } else if let Some(amended) = self.rebase_read(AMEND)? {
+            let head = self.rebase_head()?;
+            if head == amended {
+                let tree = self.tree_write_from_index()?;
+                let commit = self.commit_read(&head)?;
+                if tree != commit.tree {
+                    self.commit_tree(&tree, commit.parents, commit.author, &commit.message)?;
+                }
+            } else {
+                // The user committed on their own.
+                self.merge_check_index()?;
+            }
+            self.rebase_remove(AMEND)?;
+        } else {
+            self.merge_check_index()?;
+        }
+        self.rebase_run(hooks)
+    }
+
+    /// Drops the changes of the stopped line and runs the rest of the todo list.
+    /// Unstaged changes to paths the line didn't change are kept.
+    pub fn rebase_skip(&self, hooks: &mut RebaseHooks) -> Result<RebaseOutcome, RepositoryError> {
+        if !self.rebase_in_progress() {
+            return Err(RepositoryError::NoRebaseInProgress);
+        }
+        self.checkout_reset_merge(&self.head_files()?)?;
+        for name in [STOPPED_SHA, MESSAGE, AMEND] {
+            self.rebase_remove(name)?;
+        }
+        self.rebase_run(hooks)
+    }
+
+    /// Gives up the rebase in progress: `HEAD`, the index and the working
+    /// directory go back to where the rebase started, keeping unstaged changes to
+    /// paths the rebase didn't change.
+    pub fn rebase_abort(&self) -> Result<(), RepositoryError> {
+        let (Some(head_name), Some(orig_head)) =
+            (self.rebase_read(HEAD_NAME)?, self.rebase_read(ORIG_HEAD)?)
+        else {
+            return Err(RepositoryError::NoRebaseInProgress);
+        };
+        self.checkout_reset_merge(&self.tree_files(&self.object_peel_to(&orig_head, "tree")?)?)?;
+        match head_name.as_str() {
+            DETACHED_HEAD => self.ref_detach("HEAD", &orig_head)?,
+            branch => self.ref_update_symbolic("HEAD", branch)?,
+        }
+        self.rebase_clear()?;
+        Ok(())
+    }
+
+    /// Lets the user change the lines left in the todo list of the rebase in
+    /// progress.
+    pub fn rebase_edit_todo(&self, edit_todo: &mut TextEditor) -> Result<(), RepositoryError> {
+        let Some(text) = self.rebase_read(TODO)? else {
+            return Err(RepositoryError::NoRebaseInProgress);
+        };
+        let todo = parse_todo(&text).map_err(RepositoryError::InvalidTodo)?;
+        let mut todo = self.rebase_todo_edit(&todo, edit_todo)?;
+        self.rebase_todo_expand(&mut todo)?;
+        self.rebase_write(TODO, &format_todo(&todo))
+    }
+
+    /// Lets the user edit `todo`, shown with abbreviated commit ids.
+    fn rebase_todo_edit(
+        &self,
+        todo: &[TodoItem],
+        edit_todo: &mut TextEditor,
+    ) -> Result<Vec<TodoItem>, RepositoryError> {
+        let mut shown = todo.to_vec();
+        for item in &mut shown {
+            if item.command != TodoCommand::Exec {
+                item.argument = self.object_abbrev(&item.argument, 7)?;
+            }
+        }
+        let text = edit_todo(&format!("{}{}", format_todo(&shown), TODO_HELP))?;
+        parse_todo(&text).map_err(RepositoryError::InvalidTodo)
+    }
+
+    /// Replaces the commit of every line of `todo` with its full id, failing
+    /// unless each names a commit, so that the list still resolves once the
+    /// rebase added objects.
+    fn rebase_todo_expand(&self, todo: &mut [TodoItem]) -> Result<(), RepositoryError> {
+        for item in todo {
+            if item.command != TodoCommand::Exec {
+                item.argument = self.object_peel_to(&self.rev_parse(&item.argument)?, "commit")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the todo list until it is empty or a line stops.
+    fn rebase_run(&self, hooks: &mut RebaseHooks) -> Result<RebaseOutcome, RepositoryError> {
+        loop {
+            let mut todo = parse_todo(&self.rebase_read(TODO)?.unwrap_or_default())
+                .map_err(RepositoryError::InvalidTodo)?;
+            if todo.is_empty() {
+                return Ok(RebaseOutcome::Done(self.rebase_finish()?));
+            }
+            let item = todo.remove(0);
+            self.rebase_write(TODO, &format_todo(&todo))?;
+            let mut done = parse_todo(&self.rebase_read(DONE)?.unwrap_or_default())
+                .map_err(RepositoryError::InvalidTodo)?;
+            done.push(item.clone());
+            self.rebase_write(DONE, &format_todo(&done))?;
+            if let Some(stop) = self.rebase_apply(&item, hooks)? {
+                return Ok(RebaseOutcome::Stopped(stop));
+            }
+        }
+    }
+
+    /// Runs one line of the todo list.
+    fn rebase_apply(
+        &self,
+        item: &TodoItem,
+        hooks: &mut RebaseHooks,
+    ) -> Result<Option<RebaseStop>, RepositoryError> {
+        match item.command {
+            TodoCommand::Drop => return Ok(None),
+            TodoCommand::Exec => {
+                return match (hooks.exec)(&item.argument)? {
+                    true => Ok(None),
+                    false => Ok(Some(RebaseStop::ExecFailed(item.argument.clone()))),
+                }
+            }
+            _ => {}
+        }
+        let hash = self.object_peel_to(&self.rev_parse(&item.argument)?, "commit")?;
+        let head = self.rebase_head()?;
+        let commit = self.commit_read(&hash)?;
+        let tree = |commit: &str| self.object_peel_to(commit, "tree");
+
+        // Commits already on top of `HEAD` are kept as they are.
+        if matches!(item.command, TodoCommand::Pick | TodoCommand::Edit)
+            && commit.parents == [head.clone()]
+        {
+            self.checkout_files(&self.tree_files(&commit.tree)?, false)?;
+            self.ref_update("HEAD", &hash, PreviousValue::MustBe(head))?;
+            if item.command == TodoCommand::Edit {
+                self.rebase_write(AMEND, &hash)?;
+                return Ok(Some(RebaseStop::Edit(item.clone())));
+            }
+            return Ok(None);
+        }
+
+        let subject = commit.message.lines().next().unwrap_or_default();
+        let commit_label = format!("{}... {}", &hash[..7], subject);
+        let parent_label = format!("parent of {}", commit_label);
+        let labels = MergeLabels {
+            base: &parent_label,
+            ours: "HEAD",
+            theirs: &commit_label,
+        };
+        let base = commit
+            .parents
+            .first()
+            .map(|parent| tree(parent))
+            .transpose()?;
+        let merge = self.merge_trees(
+            base.as_deref(),
+            &tree(&head)?,
+            &commit.tree,
+            labels,
+            self.conflict_style(None)?,
+            DiffAlgorithm::default(),
+        )?;
+        self.merge_apply(&merge)?;
+
+        let message = match item.command {
+            TodoCommand::Squash => format!(
+                "{}\n\n{}",
+                self.commit_read(&head)?.message.trim_end(),
+                commit.message
+            ),
+            TodoCommand::Fixup => self.commit_read(&head)?.message,
+            _ => commit.message.clone(),
+        };
+        if !merge.conflicts.is_empty() {
+            self.rebase_write(STOPPED_SHA, &hash)?;
+            self.rebase_write(MESSAGE, &message)?;
+            return Ok(Some(RebaseStop::Conflicts(item.clone(), merge.conflicts)));
+        }
+        self.rebase_commit(item, &hash, &message, hooks)
+    }
+
+    /// Records the index for the line `item` applying `hash`: a new commit with
+    /// the author of `hash`, or a replacement of `HEAD` for `squash` and `fixup`.
+    /// Changes that became empty are dropped.
+    fn rebase_commit(
+        &self,
+        item: &TodoItem,
+        hash: &str,
+        message: &str,
+        hooks: &mut RebaseHooks,
+    ) -> Result<Option<RebaseStop>, RepositoryError> {
+        let head = self.rebase_head()?;
+        let tree = self.tree_write_from_index()?;
+        let edit_message = |hooks: &mut RebaseHooks| -> Result<String, RepositoryError> {
+            let message = clean_message(&(hooks.edit_message)(message)?);
+            match message.is_empty() {
+                true => Err(RepositoryError::EmptyMessage),
+                false => Ok(message),
+            }
+        };
+        match item.command {
+            TodoCommand::Squash | TodoCommand::Fixup => {
+                let message = match item.command {
+                    TodoCommand::Squash => edit_message(hooks)?,
+                    _ => message.to_string(),
+                };
+                let squashed = self.commit_read(&head)?;
+                self.commit_tree(&tree, squashed.parents, squashed.author, &message)?;
+            }
+            _ => {
+                if tree == self.commit_read(&head)?.tree {
+                    return Ok(None);
+                }
+                let message = match item.command {
+                    TodoCommand::Reword => edit_message(hooks)?,
+                    _ => message.to_string(),
+                };
+                let author = self.commit_read(hash)?.author;
+                let hash = self.commit_tree(&tree, vec![head], author, &message)?;
+                if item.command == TodoCommand::Edit {
+                    self.rebase_write(AMEND, &hash)?;
+                    return Ok(Some(RebaseStop::Edit(item.clone())));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Points the rebased branch at the result and back `HEAD` at the branch,
+    /// then forgets the rebase. Returns the new tip.
+    fn rebase_finish(&self) -> Result<String, RepositoryError> {
+        let head = self.rebase_head()?;
+        let head_name = self.rebase_read(HEAD_NAME)?.unwrap_or_default();
+        if head_name != DETACHED_HEAD {
+            let orig_head = self.rebase_read(ORIG_HEAD)?.unwrap_or_default();
+            self.ref_update(&head_name, &head, PreviousValue::MustBe(orig_head))?;
+            self.ref_update_symbolic("HEAD", &head_name)?;
+        }
+        self.rebase_clear()?;
+        Ok(head)
+    }
+
+    /// Forgets the rebase in progress.
+    fn rebase_clear(&self) -> Result<(), RepositoryError> {
+        let path = self.gaal_path(&[REBASE_DIR]);
+        for name in self.directory().list_path(path.clone())? {
+            let mut entry = path.clone();
+            entry.push(name);
+            self.directory().remove_entry(entry)?;
+        }
+        self.directory().remove_path(path)?;
+        Ok(())
+    }
+
+    fn rebase_head(&self) -> Result<String, RepositoryError> {
+        self.ref_resolve("HEAD")?
+            .ok_or_else(|| RepositoryError::UnknownReference("HEAD".to_string()))
+    }
+
+    fn rebase_read(&self, name: &str) -> Result<Option<String>, RepositoryError> {
+        let path = self.gaal_path(&[REBASE_DIR, name]);
+        if !self.directory().is_entry(path.clone()) {
+            return Ok(None);
+        }
+        let data: Vec<u8> = self.directory().get_data(path, false)?.into();
+        Ok(Some(String::from_utf8_lossy(&data).trim_end().to_string()))
+    }
+
+    fn rebase_write(&self, name: &str, content: &str) -> Result<(), RepositoryError> {
+        let content = match content.is_empty() {
+            true => String::new(),
+            false => format!("{}\n", content.trim_end()),
+        };
+        self.directory().save_data(
+            self.gaal_path(&[REBASE_DIR, name]),
+            content.into_bytes().into(),
+            false,
+        )?;
+        Ok(())
+    }
+
+    fn rebase_remove(&self, name: &str) -> Result<(), RepositoryError> {
+        let path = self.gaal_path(&[REBASE_DIR, name]);
+        if self.directory().is_entry(path.clone()) {
+            self.directory().remove_entry(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_rebase {
+    use super::{
+        clean_message, format_todo, parse_todo, RebaseHooks, RebaseOptions, RebaseOutcome,
+        RebaseStop, TodoCommand, TODO,
+    };
+    use crate::gaal_core::provider::memory::{read, repository, write, MemoryCoreDirectory};
+
+    #[test]
+    fn rebase_skip_and_abort_keep_unrelated_changes() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        write("work/b.txt", "b\n");
+        repository.index_add(&["a.txt", "b.txt"]).unwrap();
+        let base = repository.commit_index("Base", false).unwrap();
+        write("work/a.txt", "upstream\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let upstream = repository.commit_index("Upstream", false).unwrap();
+
+        repository.ref_create("refs/heads/topic", &base).unwrap();
+        repository.checkout("topic", false).unwrap();
+        write("work/a.txt", "topic\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let topic = repository.commit_index("Topic", false).unwrap();
+        write("work/c.txt", "c\n");
+        repository.index_add(&["c.txt"]).unwrap();
+        repository.commit_index("More", false).unwrap();
+
+        let mut hooks = RebaseHooks {
+            edit_todo: Box::new(|text: &str| Ok(text.to_string())),
+            edit_message: Box::new(|text: &str| Ok(text.to_string())),
+            exec: Box::new(|_: &str| Ok(true)),
+        };
+        let stopped = |outcome| {
+            matches!(
+                outcome,
+                RebaseOutcome::Stopped(RebaseStop::Conflicts(item, _)) if item.argument == topic
+            )
+        };
+        let options = RebaseOptions::default();
+        write("work/b.txt", "unstaged\n");
+        let outcome = repository.rebase(Some(&upstream), &options, &mut hooks);
+        assert!(stopped(outcome.unwrap()));
+        repository.rebase_abort().unwrap();
+        assert_eq!(repository.head_branch().unwrap().as_deref(), Some("topic"));
+        assert_eq!(read("work/a.txt").as_deref(), Some("topic\n"));
+        assert_eq!(read("work/b.txt").as_deref(), Some("unstaged\n"));
+        assert_eq!(read("work/c.txt").as_deref(), Some("c\n"));
+
+        let outcome = repository.rebase(Some(&upstream), &options, &mut hooks);
+        assert!(stopped(outcome.unwrap()));
+        let outcome = repository.rebase_skip(&mut hooks).unwrap();
+        assert!(matches!(outcome, RebaseOutcome::Done(_)));
+        assert_eq!(read("work/a.txt").as_deref(), Some("upstream\n"));
+        assert_eq!(read("work/b.txt").as_deref(), Some("unstaged\n"));
+        assert_eq!(read("work/c.txt").as_deref(), Some("c\n"));
+        let status = repository.status().unwrap();
+        let paths = status.entries.iter().map(|entry| entry.path.as_str());
+        assert_eq!(paths.collect::<Vec<_>>(), vec!["b.txt"]);
+    }
+
+    #[test]
+    fn todo_keeps_full_ids() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let base = repository.commit_index("Base", false).unwrap();
+        let mut commits = Vec::new();
+        for name in ["b.txt", "c.txt"] {
+            write(&format!("work/{}", name), "change\n");
+            repository.index_add(&[name]).unwrap();
+            commits.push(repository.commit_index(name, false).unwrap());
+        }
+
+        let mut shown = String::new();
+        let mut hooks = RebaseHooks {
+            edit_todo: Box::new(|text: &str| {
+                shown = text.to_string();
+                Ok(text.replacen("pick", "edit", 1))
+            }),
+            edit_message: Box::new(|text: &str| Ok(text.to_string())),
+            exec: Box::new(|_: &str| Ok(true)),
+        };
+        let options = RebaseOptions {
+            onto: None,
+            interactive: true,
+        };
+        let outcome = repository
+            .rebase(Some(&base), &options, &mut hooks)
+            .unwrap();
+        drop(hooks);
+        assert!(matches!(
+            outcome,
+            RebaseOutcome::Stopped(RebaseStop::Edit(item)) if item.argument == commits[0]
+        ));
+        // The editor gets abbreviated ids, the todo file full ones.
+        assert!(shown.starts_with(&format!("pick {} b.txt\n", &commits[0][..7])));
+        let todo = parse_todo(&repository.rebase_read(TODO).unwrap().unwrap()).unwrap();
+        assert_eq!(todo[0].argument, commits[1]);
+
+        let mut edited = String::new();
+        repository
+            .rebase_edit_todo(&mut |text: &str| {
+                edited = text.to_string();
+                Ok(text.to_string())
+            })
+            .unwrap();
+        assert!(edited.starts_with(&format!("pick {} c.txt\n", &commits[1][..7])));
+        let todo = parse_todo(&repository.rebase_read(TODO).unwrap().unwrap()).unwrap();
+        assert_eq!(todo[0].argument, commits[1]);
+    }
+
+    #[test]
+    fn todo_parsing() {
+        let todo = parse_todo(
+            "# comment\npick abc1234 First change\n\nf def5678 Fixup\nx cargo test --all\ndrop 0123456\n",
+        )
+        .unwrap();
+        let commands = todo.iter().map(|item| item.command).collect::<Vec<_>>();
+        assert_eq!(
+            commands,
+            [
+                TodoCommand::Pick,
+                TodoCommand::Fixup,
+                TodoCommand::Exec,
+                TodoCommand::Drop
+            ]
+        );
+        assert_eq!(todo[0].subject, "First change");
+        assert_eq!(todo[2].argument, "cargo test --all");
+        assert_eq!(
+            format_todo(&todo),
+            "pick abc1234 First change\nfixup def5678 Fixup\nexec cargo test --all\ndrop 0123456\n"
+        );
+
+        assert!(parse_todo("jump abc1234").is_err());
+        assert!(parse_todo("pick").is_err());
+    }
+
+    #[test]
+    fn message_cleanup() {
+        assert_eq!(
+            clean_message("\n# Please enter a message\nSubject\n\nBody\n\n"),
+            "Subject\n\nBody"
+        );
+        assert_eq!(clean_message("# only comments\n"), "");
+    }
+}
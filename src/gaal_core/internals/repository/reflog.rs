@@ -0,0 +1,164 @@
+use std::fmt;
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    object::{commit::Signature, ObjectError},
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+/// One update of a reference, as logged under `logs/`:
+/// `<old> <new> <committer>\t<message>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReflogEntry {
+    /// Previous value, all zeros when the reference was created.
+    pub old: String,
+    pub new: String,
+    pub committer: Signature,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    pub fn parse(line: &str) -> Result<Self, ObjectError> {
+        let invalid = || ObjectError::InvalidData(format!("Invalid reflog entry `{}`", line));
+        let (header, message) = line.split_once('\t').unwrap_or((line, ""));
+        let mut parts = header.splitn(3, ' ');
+        let (Some(old), Some(new), Some(committer)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            old: old.to_string(),
+            new: new.to_string(),
+            committer: Signature::parse(committer)?,
+            message: message.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ReflogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}\t{}",
+            self.old, self.new, self.committer, self.message
+        )
+    }
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    fn reflog_path(&self, name: &str) -> Vec<GCDA::PathItem> {
+        let mut items = vec!["logs"];
+        items.extend(name.split('/'));
+        self.gaal_path(&items)
+    }
+
+    /// Logged updates of the reference `name`, oldest first. Empty when nothing
+    /// was logged.
+    pub fn reflog_read(&self, name: &str) -> Result<Vec<ReflogEntry>, RepositoryError> {
+        let path = self.reflog_path(name);
+        if !self.directory().is_entry(path.clone()) {
+            return Ok(Vec::new());
+        }
+        let data: Vec<u8> = self.directory().get_data(path, false)?.into();
+        Ok(String::from_utf8_lossy(&data)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(ReflogEntry::parse)
+            .collect::<Result<Vec<ReflogEntry>, ObjectError>>()?)
+    }
+
+    /// Names of every logged reference, `HEAD` and the stash included, sorted.
+    pub fn reflog_list(&self) -> Result<Vec<String>, RepositoryError> {
+        let mut names = Vec::new();
+        let path = self.gaal_path(&["logs"]);
+        if self.directory().is_path(path.clone()) {
+            self.reflog_list_at(path, "", &mut names)?;
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn reflog_list_at(
+        &self,
+        path: Vec<GCDA::PathItem>,
+        prefix: &str,
+        names: &mut Vec<String>,
+    ) -> Result<(), RepositoryError> {
+        for entry in self.directory().list_path(path.clone())? {
+            let mut entry_path = path.clone();
+            entry_path.push(entry.clone());
+            let name = format!("{}{}", prefix, entry.into());
+
+            if self.directory().is_path(entry_path.clone()) {
+                self.reflog_list_at(entry_path, &format!("{}/", name), names)?;
+            } else {
+                names.push(name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the log of the reference `name` with `entries`, removing it when
+    /// there are none.
+    pub fn reflog_write(&self, name: &str, entries: &[ReflogEntry]) -> Result<(), RepositoryError> {
+        let path = self.reflog_path(name);
+        if entries.is_empty() {
+            if self.directory().is_entry(path.clone()) {
+                self.directory().remove_entry(path)?;
+            }
+            return Ok(());
+        }
+        self.directory()
+            .make_path(path[0..path.len() - 1].to_vec())?;
+        let data: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+        self.directory()
+            .save_data(path, data.into_bytes().into(), false)?;
+        Ok(())
+    }
+
+    /// Logs that the reference `name` moved from `old` (`None` when it was
+    /// created) to `new`, with the configured committer.
+    pub fn reflog_append(
+        &self,
+        name: &str,
+        old: Option<&str>,
+        new: &str,
+        message: &str,
+    ) -> Result<(), RepositoryError> {
+        let mut entries = self.reflog_read(name)?;
+        entries.push(ReflogEntry {
+            old: match old {
+                Some(old) => old.to_string(),
+                None => "0".repeat(self.object_format.hex_len()),
+            },
+            new: new.to_string(),
+            committer: self.committer_signature()?,
+            message: message.lines().next().unwrap_or_default().to_string(),
+        });
+        self.reflog_write(name, &entries)
+    }
+}
+
+#[cfg(test)]
+mod test_reflog {
+    use super::ReflogEntry;
+
+    #[test]
+    fn reflog_entry_roundtrip() {
+        let line = "0000 abcd Alice <alice@example.com> 1700000000 +0100\tWIP on main: abcd Fix";
+        let entry = ReflogEntry::parse(line).unwrap();
+        assert_eq!(entry.old, "0000");
+        assert_eq!(entry.new, "abcd");
+        assert_eq!(entry.committer.name, "Alice");
+        assert_eq!(entry.message, "WIP on main: abcd Fix");
+        assert_eq!(entry.to_string(), line);
+
+        assert!(ReflogEntry::parse("abcd Alice").is_err());
+    }
+}
@@ -0,0 +1,403 @@
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    object::ObjectError,
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+const LOCK_SUFFIX: &str = ".lock";
+
+/// Content of a single ref file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RefValue {
+    /// `ref: <name>`, pointing at another ref.
+    Symbolic(String),
+    /// An object id.
+    Direct(String),
+}
+
+/// What a ref must hold for an update or a delete to go through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PreviousValue {
+    Any,
+    MustNotExist,
+    MustBe(String),
+}
+
+/// Whether `name` can be stored as a ref: `HEAD`-like names or slash separated
+/// components without `..`, control characters, `~^:?*[\` or spaces, none of them
+/// empty, starting with `.` or ending with `.lock`.
+pub fn is_valid_ref_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains("..")
+        && !name.contains("@{")
+        && !name
+            .chars()
+            .any(|c| c.is_control() || " ~^:?*[\\".contains(c))
+        && name.split('/').all(|component| {
+            !component.is_empty()
+                && !component.starts_with('.')
+                && !component.ends_with(LOCK_SUFFIX)
+        })
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    fn ref_path(&self, name: &str) -> Vec<GCDA::PathItem> {
+        self.gaal_path(&name.split('/').collect::<Vec<&str>>())
+    }
+
+    fn ref_lock_path(&self, name: &str) -> Vec<GCDA::PathItem> {
+        self.ref_path(&format!("{}{}", name, LOCK_SUFFIX))
+    }
+
+    fn ref_check_name(name: &str) -> Result<(), RepositoryError> {
+        if is_valid_ref_name(name) {
+            Ok(())
+        } else {
+            Err(RepositoryError::InvalidReference(name.to_string()))
+        }
+    }
+
+    /// Reads `name` without following it. Returns `None` when the ref doesn't exist.
+    pub fn ref_read(&self, name: &str) -> Result<Option<RefValue>, RepositoryError> {
+        let path = self.ref_path(name);
+        if !self.directory().is_entry(path.clone()) {
+            return Ok(None);
+        }
+
+        let data: Vec<u8> = self.directory().get_data(path, false)?.into();
+        let data = String::from_utf8_lossy(&data);
+        let data = data.trim();
+
+        Ok(Some(match data.strip_prefix("ref: ") {
+            Some(target) => RefValue::Symbolic(target.to_string()),
+            None => RefValue::Direct(data.to_string()),
+        }))
+    }
+
+    /// Follows symbolic refs from `name` and returns the name of the last ref in the
+    /// chain, which is the one holding (or that would hold) an object id.
+    pub fn ref_target_name(&self, name: &str) -> Result<String, RepositoryError> {
+        let mut name = name.to_string();
+        let mut seen = Vec::new();
+        while let Some(RefValue::Symbolic(target)) = self.ref_read(&name)? {
+            seen.push(name);
+            if seen.contains(&target) {
+                return Err(RepositoryError::SymbolicRefLoop(target));
+            }
+            name = target;
+        }
+        Ok(name)
+    }
+
+    /// Follows `name` (e.g. `HEAD` or `refs/heads/master`) through symbolic refs
+    /// until an object id is found. Returns `None` for refs that don't exist yet,
+    /// such as `HEAD` before the first commit.
+    pub fn ref_resolve(&self, name: &str) -> Result<Option<String>, RepositoryError> {
+        let target = self.ref_target_name(name)?;
+        match self.ref_read(&target)? {
+            Some(RefValue::Direct(hash)) => Ok(Some(hash)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Name of the branch `HEAD` points to, without `refs/heads/`. `None` when
+    /// `HEAD` is detached.
+    pub fn head_branch(&self) -> Result<Option<String>, RepositoryError> {
+        match self.ref_read("HEAD")? {
+            Some(RefValue::Symbolic(target)) => Ok(Some(
+                target
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(&target)
+                    .to_string(),
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Lists every ref below `prefix` (e.g. `refs` or `refs/tags`) together with the
+    /// object id it resolves to, sorted by name.
+    pub fn ref_list(&self, prefix: &str) -> Result<Vec<(String, String)>, RepositoryError> {
+        let mut refs = Vec::new();
+        let path = self.ref_path(prefix);
+        if !self.directory().is_path(path.clone()) {
+            return Ok(refs);
+        }
+
+        let mut entries = self
+            .directory()
+            .list_path(path)?
+            .into_iter()
+            .map(|entry| entry.into())
+            .filter(|entry: &String| !entry.ends_with(LOCK_SUFFIX))
+            .collect::<Vec<String>>();
+        entries.sort();
+
+        for entry in entries {
+            let name = format!("{}/{}", prefix, entry);
+            if self.directory().is_path(self.ref_path(&name)) {
+                refs.extend(self.ref_list(&name)?);
+            } else if let Some(hash) = self.ref_resolve(&name)? {
+                refs.push((name, hash));
+            }
+        }
+
+        Ok(refs)
+    }
+
+    /// Points `name` directly at `hash`, creating any missing directories.
+    pub fn ref_create(&self, name: &str, hash: &str) -> Result<(), RepositoryError> {
+        self.ref_update(name, hash, PreviousValue::Any)
+    }
+
+    /// Points the ref `name` resolves to at `hash`, so updating `HEAD` moves the
+    /// current branch. The new value is written next to the ref under a `.lock`
+    /// entry, checked against `previous`, then moved in place; a concurrent writer
+    /// holding the lock makes the update fail instead of waiting.
+    pub fn ref_update(
+        &self,
+        name: &str,
+        hash: &str,
+        previous: PreviousValue,
+    ) -> Result<(), RepositoryError> {
+        if !self.object_format.is_object_id(hash) {
+            return Err(ObjectError::InvalidData(format!(
+                "`{}` is not a valid {} object id",
+                hash, self.object_format
+            ))
+            .into());
+        }
+
+        let target = self.ref_target_name(name)?;
+        self.ref_write(&target, &format!("{}\n", hash), previous)
+    }
+
+    /// Points `name` itself at `hash`, replacing it if it is a symbolic ref. Used to
+    /// detach `HEAD`.
+    pub fn ref_detach(&self, name: &str, hash: &str) -> Result<(), RepositoryError> {
+        if !self.object_format.is_object_id(hash) {
+            return Err(ObjectError::InvalidData(format!(
+                "`{}` is not a valid {} object id",
+                hash, self.object_format
+            ))
+            .into());
+        }
+        self.ref_write(name, &format!("{}\n", hash), PreviousValue::Any)
+    }
+
+    /// Makes `name` a symbolic ref to `target`, e.g. `HEAD` to `refs/heads/master`.
+    /// `name` itself is replaced, not the ref it currently points at.
+    pub fn ref_update_symbolic(&self, name: &str, target: &str) -> Result<(), RepositoryError> {
+        Self::ref_check_name(target)?;
+        self.ref_write(name, &format!("ref: {}\n", target), PreviousValue::Any)
+    }
+
+    /// Removes the ref `name` itself, without following it, once it matches
+    /// `previous`.
+    pub fn ref_delete(&self, name: &str, previous: PreviousValue) -> Result<(), RepositoryError> {
+        Self::ref_check_name(name)?;
+        let lock = self.ref_lock(name)?;
+        let result = self.ref_check_previous(name, &previous).and_then(|_| {
+            if self.directory().is_entry(self.ref_path(name)) {
+                self.directory().remove_entry(self.ref_path(name))?;
+            }
+            Ok(())
+        });
+        self.directory().remove_entry(lock)?;
+        result
+    }
+
+    fn ref_write(
+        &self,
+        name: &str,
+        content: &str,
+        previous: PreviousValue,
+    ) -> Result<(), RepositoryError> {
+        Self::ref_check_name(name)?;
+        let path = self.ref_path(name);
+        self.directory()
+            .make_path(path[0..path.len() - 1].to_vec())?;
+
+        let lock = self.ref_lock(name)?;
+        let result = self.ref_check_previous(name, &previous).and_then(|_| {
+            self.directory()
+                .save_data(lock.clone(), content.as_bytes().to_vec().into(), false)?;
+            self.directory().rename_entry(lock.clone(), path)?;
+            Ok(())
+        });
+        if result.is_err() && self.directory().is_entry(lock.clone()) {
+            self.directory().remove_entry(lock)?;
+        }
+        result
+    }
+
+    fn ref_lock(&self, name: &str) -> Result<Vec<GCDA::PathItem>, RepositoryError> {
+        let lock = self.ref_lock_path(name);
+        match self
+            .directory()
+            .create_data(lock.clone(), Vec::new().into())
+        {
+            Ok(()) => Ok(lock),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                Err(RepositoryError::LockedReference(name.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn ref_check_previous(
+        &self,
+        name: &str,
+        previous: &PreviousValue,
+    ) -> Result<(), RepositoryError> {
+        let current = match self.ref_read(name)? {
+            Some(RefValue::Direct(hash)) => Some(hash),
+            Some(RefValue::Symbolic(target)) => Some(format!("ref: {}", target)),
+            None => None,
+        };
+        let matches = match previous {
+            PreviousValue::Any => true,
+            PreviousValue::MustNotExist => current.is_none(),
+            PreviousValue::MustBe(expected) => current.as_deref() == Some(expected.as_str()),
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(RepositoryError::ReferenceMismatch {
+                name: name.to_string(),
+                actual: current,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_ref_name {
+    use super::is_valid_ref_name;
+
+    #[test]
+    fn valid_ref_names() {
+        for name in [
+            "HEAD",
+            "refs/heads/master",
+            "refs/tags/v1.0",
+            "refs/heads/a-b_c",
+        ] {
+            assert!(is_valid_ref_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn invalid_ref_names() {
+        for name in [
+            "",
+            "refs//heads",
+            "refs/heads/",
+            "refs/heads/.hidden",
+            "refs/heads/a..b",
+            "refs/heads/master.lock",
+            "refs/heads/a b",
+            "refs/heads/a~1",
+            "refs/heads/a^",
+            "refs/heads/a:b",
+            "refs/heads/a@{1}",
+        ] {
+            assert!(!is_valid_ref_name(name), "{}", name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_ref_update {
+    use super::{PreviousValue, RefValue};
+    use crate::gaal_core::{
+        internals::repository::RepositoryError,
+        provider::memory::{read, remove, repository, write, MemoryCoreDirectory},
+    };
+
+    #[test]
+    fn ref_update_compare_and_swap() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        let hash = |data: &[u8]| repository.object_format.hash(data);
+        let (a, b, c) = (hash(b"a"), hash(b"b"), hash(b"c"));
+
+        repository
+            .ref_update("refs/heads/topic", &a, PreviousValue::MustNotExist)
+            .unwrap();
+        assert!(matches!(
+            repository.ref_update("refs/heads/topic", &b, PreviousValue::MustNotExist),
+            Err(RepositoryError::ReferenceMismatch { actual: Some(actual), .. }) if actual == a
+        ));
+        assert!(matches!(
+            repository.ref_update("refs/heads/topic", &b, PreviousValue::MustBe(c.clone())),
+            Err(RepositoryError::ReferenceMismatch { actual: Some(actual), .. }) if actual == a
+        ));
+        // A failed update leaves the ref and no lock behind.
+        assert_eq!(read("work/.gal/refs/heads/topic.lock"), None);
+        assert_eq!(
+            repository.ref_resolve("refs/heads/topic").unwrap(),
+            Some(a.clone())
+        );
+        repository
+            .ref_update("refs/heads/topic", &b, PreviousValue::MustBe(a.clone()))
+            .unwrap();
+        assert_eq!(
+            repository.ref_resolve("refs/heads/topic").unwrap(),
+            Some(b.clone())
+        );
+
+        // Updating `HEAD` moves the branch it points to.
+        repository
+            .ref_update_symbolic("HEAD", "refs/heads/topic")
+            .unwrap();
+        repository
+            .ref_update("HEAD", &c, PreviousValue::MustBe(b.clone()))
+            .unwrap();
+        assert_eq!(
+            repository.ref_read("HEAD").unwrap(),
+            Some(RefValue::Symbolic("refs/heads/topic".to_string()))
+        );
+        assert_eq!(
+            repository.ref_resolve("refs/heads/topic").unwrap(),
+            Some(c.clone())
+        );
+        assert!(matches!(
+            repository.ref_delete("refs/heads/topic", PreviousValue::MustBe(a)),
+            Err(RepositoryError::ReferenceMismatch { .. })
+        ));
+        assert_eq!(read("work/.gal/refs/heads/topic.lock"), None);
+    }
+
+    #[test]
+    fn ref_update_leftover_lock() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        let hash = repository.object_format.hash(b"a");
+        repository.ref_create("refs/heads/topic", &hash).unwrap();
+
+        write("work/.gal/refs/heads/topic.lock", "");
+        assert!(matches!(
+            repository.ref_update("refs/heads/topic", &hash, PreviousValue::Any),
+            Err(RepositoryError::LockedReference(name)) if name == "refs/heads/topic"
+        ));
+        assert!(matches!(
+            repository.ref_delete("refs/heads/topic", PreviousValue::Any),
+            Err(RepositoryError::LockedReference(_))
+        ));
+        // Someone else's lock is neither removed nor listed as a ref.
+        assert_eq!(read("work/.gal/refs/heads/topic.lock").as_deref(), Some(""));
+        let refs = repository.ref_list("refs/heads").unwrap();
+        assert_eq!(refs, vec![("refs/heads/topic".to_string(), hash.clone())]);
+
+        remove("work/.gal/refs/heads/topic.lock");
+        repository
+            .ref_delete("refs/heads/topic", PreviousValue::MustBe(hash))
+            .unwrap();
+        assert_eq!(repository.ref_resolve("refs/heads/topic").unwrap(), None);
+    }
+}
@@ -0,0 +1,476 @@
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    object::{tree::Tree, ObjectError},
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+/// Shortest abbreviated object id that is looked up in the object database.
+pub const MIN_ABBREV_LEN: usize = 4;
+
+/// Where a revision expression starts from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RevisionBase {
+    /// `HEAD`, a ref, a branch or tag name, or a full or abbreviated object id.
+    Name(String),
+    /// `<branch>@{upstream}` (or `@{u}`); `None` stands for the current branch.
+    Upstream(Option<String>),
+    /// Nothing before `:path`, which then names a file of the index.
+    Index,
+}
+
+/// A suffix applied to a revision, left to right.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RevisionStep {
+    /// `~n`: the n-th first-parent ancestor.
+    Ancestor(usize),
+    /// `^n`: the n-th parent, `^0` being the commit itself.
+    Parent(usize),
+    /// `^{type}`: peels until an object of that type, `^{}` peels tags only.
+    Peel(Option<String>),
+}
+
+/// A parsed revision expression such as `v1.0^{commit}~2:src/main.rs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Revision {
+    pub base: RevisionBase,
+    pub steps: Vec<RevisionStep>,
+    /// `rev:path` selects the object at `path` in the tree of the revision.
+    pub path: Option<String>,
+}
+
+impl Revision {
+    pub fn parse(expression: &str) -> Result<Self, RepositoryError> {
+        let invalid = || RepositoryError::InvalidRevision(expression.to_string());
+
+        let (revision, path) = match expression.split_once(':') {
+            Some((revision, path)) => (revision, Some(path.trim_matches('/').to_string())),
+            None => (expression, None),
+        };
+
+        if revision.is_empty() && path.as_deref().is_some_and(|path| !path.is_empty()) {
+            return Ok(Self {
+                base: RevisionBase::Index,
+                steps: Vec::new(),
+                path,
+            });
+        }
+
+        let steps_start = revision.find(['~', '^']).unwrap_or(revision.len());
+        let (base, mut rest) = revision.split_at(steps_start);
+        if base.is_empty() {
+            return Err(invalid());
+        }
+
+        let base = match base.split_once("@{") {
+            Some((branch, suffix)) => match suffix {
+                "u}" | "upstream}" => {
+                    RevisionBase::Upstream(Some(branch.to_string()).filter(|b| !b.is_empty()))
+                }
+                _ => return Err(invalid()),
+            },
+            None if base == "@" => RevisionBase::Name("HEAD".to_string()),
+            None => RevisionBase::Name(base.to_string()),
+        };
+
+        let mut steps = Vec::new();
+        while let Some(operator) = rest.chars().next() {
+            rest = &rest[1..];
+            if operator == '^' && rest.starts_with('{') {
+                let end = rest.find('}').ok_or_else(invalid)?;
+                let fmt = &rest[1..end];
+                if !["", "commit", "tree", "blob", "tag"].contains(&fmt) {
+                    return Err(invalid());
+                }
+                steps.push(RevisionStep::Peel(
+                    Some(fmt.to_string()).filter(|f| !f.is_empty()),
+                ));
+                rest = &rest[end + 1..];
+                continue;
+            }
+
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let count = match digits {
+                0 => 1,
+                _ => rest[..digits].parse::<usize>().map_err(|_| invalid())?,
+            };
+            rest = &rest[digits..];
+            steps.push(match operator {
+                '~' => RevisionStep::Ancestor(count),
+                '^' => RevisionStep::Parent(count),
+                _ => return Err(invalid()),
+            });
+        }
+
+        Ok(Self { base, steps, path })
+    }
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Resolves a revision expression to an object id, see [`Revision`] for the
+    /// supported syntax.
+    pub fn rev_parse(&self, expression: &str) -> Result<String, RepositoryError> {
+        let revision = Revision::parse(expression)?;
+        let unknown = || RepositoryError::UnknownReference(expression.to_string());
+
+        let mut hash = match &revision.base {
+            RevisionBase::Name(name) => self.name_resolve(name)?,
+            RevisionBase::Upstream(branch) => {
+                let upstream = self.branch_upstream_ref(branch.as_deref())?;
+                self.ref_resolve(&upstream)?.ok_or_else(unknown)?
+            }
+            RevisionBase::Index => {
+                let path = revision.path.as_deref().unwrap_or_default();
+                return self
+                    .index_read()?
+                    .get(path)
+                    .map(|entry| entry.id.clone())
+                    .ok_or_else(|| RepositoryError::UnknownPath {
+                        path: path.to_string(),
+                        revision: "index".to_string(),
+                    });
+            }
+        };
+
+        for step in &revision.steps {
+            hash = match step {
+                RevisionStep::Ancestor(count) => {
+                    let mut hash = self.object_peel_to(&hash, "commit")?;
+                    for _ in 0..*count {
+                        hash = self
+                            .commit_read(&hash)?
+                            .parents
+                            .into_iter()
+                            .next()
+                            .ok_or_else(unknown)?;
+                    }
+                    hash
+                }
+                RevisionStep::Parent(0) => self.object_peel_to(&hash, "commit")?,
+                RevisionStep::Parent(index) => {
+                    let commit = self.commit_read(&self.object_peel_to(&hash, "commit")?)?;
+                    commit
+                        .parents
+                        .into_iter()
+                        .nth(index - 1)
+                        .ok_or_else(unknown)?
+                }
+                RevisionStep::Peel(Some(fmt)) => self.object_peel_to(&hash, fmt)?,
+                RevisionStep::Peel(None) => self.object_peel(&hash)?.0,
+            };
+        }
+
+        match &revision.path {
+            Some(path) => self.tree_lookup(&self.object_peel_to(&hash, "tree")?, path, expression),
+            None => Ok(hash),
+        }
+    }
+
+    /// Resolves a name to a single object id. Refs win over abbreviated object ids;
+    /// names matching several refs or several objects are ambiguous.
+    fn name_resolve(&self, name: &str) -> Result<String, RepositoryError> {
+        let mut candidates = self.ref_candidates(name)?;
+        if candidates.is_empty() {
+            candidates = self.object_candidates(name)?;
+        }
+
+        match candidates.len() {
+            0 => Err(RepositoryError::UnknownReference(name.to_string())),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(RepositoryError::AmbiguousReference(
+                name.to_string(),
+                candidates,
+            )),
+        }
+    }
+
+    fn ref_candidates(&self, name: &str) -> Result<Vec<String>, RepositoryError> {
+        if name == "HEAD" {
+            return Ok(self.ref_resolve("HEAD")?.into_iter().collect());
+        }
+
+        if self.object_format.is_object_id(name) {
+            return Ok(vec![name.to_lowercase()]);
+        }
+
+        let mut candidates = Vec::new();
+        for prefix in ["", "refs/tags/", "refs/heads/", "refs/remotes/"] {
+            let ref_name = format!("{}{}", prefix, name);
+            if prefix.is_empty() && !ref_name.starts_with("refs/") {
+                continue;
+            }
+            if let Some(hash) = self.ref_resolve(&ref_name)? {
+                if !candidates.contains(&hash) {
+                    candidates.push(hash);
+                }
+            }
+        }
+        Ok(candidates)
+    }
+
+    fn object_candidates(&self, prefix: &str) -> Result<Vec<String>, RepositoryError> {
+        let is_abbreviation = prefix.len() >= MIN_ABBREV_LEN
+            && prefix.len() < self.object_format.hex_len()
+            && prefix.chars().all(|c| c.is_ascii_hexdigit());
+        if !is_abbreviation {
+            return Ok(Vec::new());
+        }
+
+        let prefix = prefix.to_lowercase();
+        Ok(self
+            .object_list()?
+            .into_iter()
+            .filter(|hash| hash.starts_with(&prefix))
+            .collect())
+    }
+
+    /// Shortest prefix of `hash`, `len` digits at least, that resolves back to it
+    /// rather than to another object or to a ref.
+    pub fn object_abbrev(&self, hash: &str, len: usize) -> Result<String, RepositoryError> {
+        let objects = self.object_list()?;
+        for len in len.max(MIN_ABBREV_LEN)..hash.len() {
+            let prefix = &hash[..len];
+            let matches = objects.iter().filter(|hash| hash.starts_with(prefix));
+            if matches.count() == 1 && self.ref_candidates(prefix)?.is_empty() {
+                return Ok(prefix.to_string());
+            }
+        }
+        Ok(hash.to_string())
+    }
+
+    /// Name of the ref `branch` (or the current branch) tracks, read from the
+    /// `branch.<name>.remote` and `branch.<name>.merge` config entries.
+    pub fn branch_upstream_ref(&self, branch: Option<&str>) -> Result<String, RepositoryError> {
+        let branch = match branch {
+            Some(branch) => branch.to_string(),
+            None => self
+                .head_branch()?
+                .ok_or_else(|| RepositoryError::NoUpstream("HEAD".to_string()))?,
+        };
+
+        let no_upstream = || RepositoryError::NoUpstream(branch.clone());
+        let remote = self
+            .config_get(&["branch", &branch, "remote"])
+            .ok_or_else(no_upstream)?;
+        let merge = self
+            .config_get(&["branch", &branch, "merge"])
+            .ok_or_else(no_upstream)?;
+
+        Ok(match remote.as_str() {
+            "." => merge,
+            _ => format!(
+                "refs/remotes/{}/{}",
+                remote,
+                merge.strip_prefix("refs/heads/").unwrap_or(&merge)
+            ),
+        })
+    }
+
+    /// Walks `path` down from the tree `hash`.
+    fn tree_lookup(
+        &self,
+        hash: &str,
+        path: &str,
+        expression: &str,
+    ) -> Result<String, RepositoryError> {
+        let mut hash = hash.to_string();
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            let object = self.object_read(hash.clone())?;
+            if object.fmt()? != "tree" {
+                return Err(ObjectError::InvalidData(format!(
+                    "`{}` is not a directory in `{}`",
+                    component, expression
+                ))
+                .into());
+            }
+            hash = Tree::parse(&object.serialize()?, self.object_format)?
+                .leafs
+                .into_iter()
+                .find(|leaf| leaf.path == component)
+                .map(|leaf| leaf.sha)
+                .ok_or_else(|| RepositoryError::UnknownPath {
+                    path: path.to_string(),
+                    revision: expression.to_string(),
+                })?;
+        }
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod test_revision_parse {
+    use super::{Revision, RevisionBase, RevisionStep};
+
+    #[test]
+    fn revision_parse_steps() {
+        let revision = Revision::parse("HEAD~3^2^{tree}^{}").unwrap();
+        assert_eq!(revision.base, RevisionBase::Name("HEAD".to_string()));
+        assert_eq!(
+            revision.steps,
+            vec![
+                RevisionStep::Ancestor(3),
+                RevisionStep::Parent(2),
+                RevisionStep::Peel(Some("tree".to_string())),
+                RevisionStep::Peel(None),
+            ]
+        );
+        assert_eq!(revision.path, None);
+
+        let revision = Revision::parse("master^~").unwrap();
+        assert_eq!(
+            revision.steps,
+            vec![RevisionStep::Parent(1), RevisionStep::Ancestor(1)]
+        );
+    }
+
+    #[test]
+    fn revision_parse_path_and_upstream() {
+        let revision = Revision::parse("v1.0:src/main.rs").unwrap();
+        assert_eq!(revision.base, RevisionBase::Name("v1.0".to_string()));
+        assert_eq!(revision.path, Some("src/main.rs".to_string()));
+
+        let revision = Revision::parse("topic@{upstream}~1").unwrap();
+        assert_eq!(
+            revision.base,
+            RevisionBase::Upstream(Some("topic".to_string()))
+        );
+        assert_eq!(revision.steps, vec![RevisionStep::Ancestor(1)]);
+
+        let revision = Revision::parse("@{u}").unwrap();
+        assert_eq!(revision.base, RevisionBase::Upstream(None));
+
+        let revision = Revision::parse("@^").unwrap();
+        assert_eq!(revision.base, RevisionBase::Name("HEAD".to_string()));
+
+        let revision = Revision::parse(":src/main.rs").unwrap();
+        assert_eq!(revision.base, RevisionBase::Index);
+        assert_eq!(revision.path, Some("src/main.rs".to_string()));
+    }
+
+    #[test]
+    fn revision_parse_invalid() {
+        for expression in [
+            "",
+            ":",
+            "~1",
+            "HEAD^{thing}",
+            "HEAD^{tree",
+            "HEAD@{1}",
+            "HEAD~x",
+        ] {
+            assert!(Revision::parse(expression).is_err(), "{}", expression);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_rev_parse {
+    use crate::gaal_core::{
+        internals::repository::RepositoryError,
+        provider::memory::{repository, write, MemoryCoreDirectory},
+    };
+
+    #[test]
+    fn rev_parse_names() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/src/a.txt", "a\n");
+        repository.index_add(&["src"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+        write("work/src/a.txt", "changed\n");
+        repository.index_add(&["src"]).unwrap();
+        let second = repository.commit_index("Second", false).unwrap();
+
+        assert_eq!(repository.rev_parse(&first[..7]).unwrap(), first);
+        assert_eq!(
+            repository.rev_parse(&second.to_uppercase()).unwrap(),
+            second
+        );
+        assert!(matches!(
+            repository.rev_parse(&first[..3]),
+            Err(RepositoryError::UnknownReference(_))
+        ));
+        assert_eq!(repository.rev_parse("HEAD~1").unwrap(), first);
+        assert_eq!(repository.rev_parse("@^").unwrap(), first);
+
+        // `rev:path` reads the tree of the revision, `:path` the index.
+        let blob = |data: &str| {
+            repository
+                .object_format
+                .hash(format!("blob\0{}\0{}", data.len(), data).as_bytes())
+        };
+        assert_eq!(
+            repository.rev_parse("HEAD~:src/a.txt").unwrap(),
+            blob("a\n")
+        );
+        write("work/src/a.txt", "staged\n");
+        repository.index_add(&["src"]).unwrap();
+        assert_eq!(
+            repository.rev_parse(":src/a.txt").unwrap(),
+            blob("staged\n")
+        );
+        assert_eq!(
+            repository.rev_parse("HEAD:src/a.txt").unwrap(),
+            blob("changed\n")
+        );
+        assert!(matches!(
+            repository.rev_parse(":src/b.txt"),
+            Err(RepositoryError::UnknownPath { .. })
+        ));
+        assert!(matches!(
+            repository.rev_parse("HEAD:src/b.txt"),
+            Err(RepositoryError::UnknownPath { .. })
+        ));
+    }
+
+    #[test]
+    fn rev_parse_upstream_and_ambiguous_names() {
+        let directory = MemoryCoreDirectory::default();
+        let mut repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+        write("work/a.txt", "b\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        let second = repository.commit_index("Second", false).unwrap();
+
+        assert!(matches!(
+            repository.rev_parse("@{u}"),
+            Err(RepositoryError::NoUpstream(branch)) if branch == "master"
+        ));
+        repository.ref_create("refs/heads/base", &first).unwrap();
+        repository
+            .config_set(&["branch", "master", "remote"], ".")
+            .unwrap();
+        repository
+            .config_set(&["branch", "master", "merge"], "refs/heads/base")
+            .unwrap();
+        assert_eq!(repository.rev_parse("@{u}").unwrap(), first);
+        assert_eq!(repository.rev_parse("master@{upstream}").unwrap(), first);
+        repository
+            .config_set(&["branch", "master", "remote"], "origin")
+            .unwrap();
+        repository
+            .ref_create("refs/remotes/origin/base", &second)
+            .unwrap();
+        assert_eq!(repository.rev_parse("@{u}").unwrap(), second);
+
+        // A tag and a branch of the same name only conflict when they differ.
+        repository.tag_create("base", &first, None).unwrap();
+        assert_eq!(repository.rev_parse("base").unwrap(), first);
+        repository.ref_create("refs/tags/topic", &first).unwrap();
+        repository.ref_create("refs/heads/topic", &second).unwrap();
+        assert!(matches!(
+            repository.rev_parse("topic"),
+            Err(RepositoryError::AmbiguousReference(name, candidates))
+                if name == "topic" && candidates.len() == 2
+        ));
+        assert_eq!(repository.rev_parse("refs/heads/topic").unwrap(), second);
+        assert_eq!(repository.rev_parse("refs/tags/topic").unwrap(), first);
+    }
+}
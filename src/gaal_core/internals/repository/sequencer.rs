@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+
+use crate::gaal_core::{
+    internals::{
+        text_diff::DiffAlgorithm,
+        text_merge::{ConflictStyle, MergeLabels},
+    },
+    provider::{
+        directory::GaalCoreDirectoryActions,
+        directory_object::GaalCoreDirectoryObjectsActions,
+        object::{commit::Signature, tree::Tree},
+    },
+};
+
+use super::{default::GaalRepository, merge::MergeConflict, refs::PreviousValue, RepositoryError};
+
+const CHERRY_PICK_HEAD: &str = "CHERRY_PICK_HEAD";
+const REVERT_HEAD: &str = "REVERT_HEAD";
+
+/// Which way the changes of a commit are applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickKind {
+    /// Apply the changes the commit made.
+    CherryPick,
+    /// Apply the opposite of the changes the commit made.
+    Revert,
+}
+
+impl PickKind {
+    /// Reference holding the commit being applied while conflicts are resolved.
+    fn head(self) -> &'static str {
+        match self {
+            PickKind::CherryPick => CHERRY_PICK_HEAD,
+            PickKind::Revert => REVERT_HEAD,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PickKind::CherryPick => "cherry-pick",
+            PickKind::Revert => "revert",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PickOptions {
+    /// Parent number, from 1, the changes of a merge commit are taken against.
+    pub mainline: Option<usize>,
+    /// `merge.conflictstyle`, or `merge`, when `None`.
+    pub style: Option<ConflictStyle>,
+    pub algorithm: DiffAlgorithm,
+}
+
+/// How a cherry-pick or a revert ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PickOutcome {
+    /// This commit was recorded on top of `HEAD`.
+    Committed(String),
+    /// These paths were left with conflicts; continuing once they are added
+    /// records the commit.
+    Conflicts(BTreeMap<String, MergeConflict>),
+}
+
+/// The parent whose changes to a commit with `parents` are applied: the only
+/// one, or the `mainline`th of a merge. `None` for a root commit.
+fn mainline_parent(parents: &[String], mainline: Option<usize>) -> Result<Option<String>, String> {
+    match (parents, mainline) {
+        ([], None) => Ok(None),
+        ([parent], None) => Ok(Some(parent.clone())),
+        (_, None) => Err("it is a merge but no mainline was given".to_string()),
+        ([] | [_], Some(_)) => Err("it is not a merge".to_string()),
+        (parents, Some(mainline)) => match mainline.checked_sub(1).and_then(|i| parents.get(i)) {
+            Some(parent) => Ok(Some(parent.clone())),
+            None => Err(format!("it has no parent {}", mainline)),
+        },
+    }
+}
+
+/// Message of the commit reverting `hash`, naming the `mainline` parent it goes
+/// back to for a merge.
+fn revert_message(subject: &str, hash: &str, mainline: Option<&str>) -> String {
+    match mainline {
+        Some(parent) => format!(
+            "Revert \"{}\"\n\nThis reverts commit {}, reversing\nchanges made to {}.",
+            subject, hash, parent
+        ),
+        None => format!("Revert \"{}\"\n\nThis reverts commit {}.", subject, hash),
+    }
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Applies the changes `revision` made to its parent onto `HEAD` and records
+    /// them with the original author and message. The index must match `HEAD`.
+    pub fn cherry_pick(
+        &self,
+        revision: &str,
+        options: &PickOptions,
+    ) -> Result<PickOutcome, RepositoryError> {
+        self.pick(PickKind::CherryPick, revision, options)
+    }
+
+    /// Records a commit undoing the changes `revision` made to its parent on top
+    /// of `HEAD`. The index must match `HEAD`.
+    pub fn revert(
+        &self,
+        revision: &str,
+        options: &PickOptions,
+    ) -> Result<PickOutcome, RepositoryError> {
+        self.pick(PickKind::Revert, revision, options)
+    }
+
+    /// Merges the changes between `revision` and its parent, in the direction of
+    /// `kind`, into `HEAD`. On conflicts, the commit being applied and the
+    /// message are kept under the repository directory until the operation is
+    /// continued or aborted.
+    fn pick(
+        &self,
+        kind: PickKind,
+        revision: &str,
+        options: &PickOptions,
+    ) -> Result<PickOutcome, RepositoryError> {
+        let hash = self.object_peel_to(&self.rev_parse(revision)?, "commit")?;
+        let head = self
+            .ref_resolve("HEAD")?
+            .ok_or_else(|| RepositoryError::UnknownReference("HEAD".to_string()))?;
+        self.operation_check()?;
+        self.merge_check_index()?;
+
+        let commit = self.commit_read(&hash)?;
+        let parent = mainline_parent(&commit.parents, options.mainline)
+            .map_err(|reason| RepositoryError::InvalidMainline(hash.clone(), reason))?;
+
+        let subject = commit.message.lines().next().unwrap_or_default();
+        let commit_label = format!("{}... {}", &hash[..7], subject);
+        let parent_label = format!("parent of {}", commit_label);
+        let tree = |commit: &str| self.object_peel_to(commit, "tree");
+        let commit_tree = tree(&hash)?;
+        let parent_tree = parent.as_deref().map(tree).transpose()?;
+        let (base, theirs, labels) = match kind {
+            PickKind::CherryPick => (
+                parent_tree,
+                commit_tree,
+                MergeLabels {
+                    base: &parent_label,
+                    ours: "HEAD",
+                    theirs: &commit_label,
+                },
+            ),
+            // Reverting the root commit empties the tree.
+            PickKind::Revert => (
+                Some(commit_tree),
+                match parent_tree {
+                    Some(tree) => tree,
+                    None => self.tree_write(Tree::default())?,
+                },
+                MergeLabels {
+                    base: &commit_label,
+                    ours: "HEAD",
+                    theirs: &parent_label,
+                },
+            ),
+        };
+        let merge = self.merge_trees(
+            base.as_deref(),
+            &tree(&head)?,
+            &theirs,
+            labels,
+            self.conflict_style(options.style)?,
+            options.algorithm,
+        )?;
+        self.merge_apply(&merge)?;
+
+        let (author, message) = match kind {
+            PickKind::CherryPick => (commit.author.clone(), commit.message.clone()),
+            PickKind::Revert => (
+                self.author_signature()?,
+                revert_message(subject, &hash, options.mainline.and(parent.as_deref())),
+            ),
+        };
+        if !merge.conflicts.is_empty() {
+            self.ref_detach(kind.head(), &hash)?;
+            self.merge_message_write(&message)?;
+            return Ok(PickOutcome::Conflicts(merge.conflicts));
+        }
+
+        let tree = self.tree_write_from_index()?;
+        if tree == self.commit_read(&head)?.tree {
+            return Err(RepositoryError::NothingToCommit);
+        }
+        let hash = self.commit_tree(&tree, vec![head], author, &message)?;
+        Ok(PickOutcome::Committed(hash))
+    }
+
+    /// The cherry-pick or revert waiting for its conflicts to be resolved, with
+    /// the commit it applies.
+    pub fn pick_in_progress(&self) -> Result<Option<(PickKind, String)>, RepositoryError> {
+        for kind in [PickKind::CherryPick, PickKind::Revert] {
+            if let Some(hash) = self.ref_resolve(kind.head())? {
+                return Ok(Some((kind, hash)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fails unless a pick of `kind` is in progress.
+    fn pick_check(&self, kind: PickKind) -> Result<(), RepositoryError> {
+        match self.pick_in_progress()? {
+            Some((current, _)) if current == kind => Ok(()),
+            Some((current, _)) => Err(RepositoryError::PickInProgress(current.name().to_string())),
+            None => Err(RepositoryError::NoPickInProgress),
+        }
+    }
+
+    /// Records the resolved index as the conclusion of the pick of `kind` in
+    /// progress, with the message it prepared.
+    pub fn pick_continue(&self, kind: PickKind) -> Result<String, RepositoryError> {
+        self.pick_check(kind)?;
+        let message = self.merge_message()?.unwrap_or_default();
+        self.commit_index(&message, false)
+    }
+
+    /// Gives up the pick of `kind` in progress: the paths it changed go back to
+    /// `HEAD` in the index and the working directory, unstaged changes to the
+    /// others are kept.
+    pub fn pick_abort(&self, kind: PickKind) -> Result<(), RepositoryError> {
+        self.pick_check(kind)?;
+        self.checkout_reset_merge(&self.head_files()?)?;
+        self.merge_state_clear()
+    }
+
+    /// Author of the commit concluding a cherry-pick: the one of the picked
+    /// commit. `None` when no cherry-pick is in progress.
+    pub(super) fn pick_author(&self) -> Result<Option<Signature>, RepositoryError> {
+        match self.ref_resolve(CHERRY_PICK_HEAD)? {
+            Some(hash) => Ok(Some(self.commit_read(&hash)?.author)),
+            None => Ok(None),
+        }
+    }
+
+    /// Forgets the cherry-pick or revert in progress, if any.
+    pub(super) fn pick_state_clear(&self) -> Result<(), RepositoryError> {
+        if let Some((kind, _)) = self.pick_in_progress()? {
+            self.ref_delete(kind.head(), PreviousValue::Any)?;
+        }
+        Ok(())
+    }
+
+    /// Fails while a merge, a cherry-pick or a revert waits for its conflicts to
+    /// be resolved, or while a rebase is in progress.
+    pub(super) fn operation_check(&self) -> Result<(), RepositoryError> {
+        if self.rebase_in_progress() {
+            return Err(RepositoryError::RebaseInProgress);
+        }
+        if self.merge_head()?.is_some() {
+            return Err(RepositoryError::MergeInProgress);
+        }
+        if let Some((kind, _)) = self.pick_in_progress()? {
+            return Err(RepositoryError::PickInProgress(kind.name().to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_sequencer {
+    use super::{mainline_parent, revert_message, PickKind, PickOptions, PickOutcome};
+    use crate::gaal_core::{
+        internals::repository::RepositoryError,
+        provider::memory::{read, repository, write, MemoryCoreDirectory},
+    };
+
+    #[test]
+    fn pick_abort_keeps_unrelated_changes() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        write("work/b.txt", "b\n");
+        repository.index_add(&["a.txt", "b.txt"]).unwrap();
+        let first = repository.commit_index("First", false).unwrap();
+        repository.ref_create("refs/heads/side", &first).unwrap();
+        repository.checkout("side", false).unwrap();
+        write("work/a.txt", "side\n");
+        write("work/c.txt", "c\n");
+        repository.index_add(&["a.txt", "c.txt"]).unwrap();
+        repository.commit_index("Side", false).unwrap();
+        repository.checkout("master", false).unwrap();
+        write("work/a.txt", "master\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        repository.commit_index("Master", false).unwrap();
+
+        write("work/b.txt", "unstaged\n");
+        let outcome = repository
+            .cherry_pick("side", &PickOptions::default())
+            .unwrap();
+        assert!(matches!(outcome, PickOutcome::Conflicts(_)));
+        assert_eq!(read("work/c.txt").as_deref(), Some("c\n"));
+
+        for result in [
+            repository.pick_continue(PickKind::Revert).map(|_| ()),
+            repository.pick_abort(PickKind::Revert),
+        ] {
+            assert!(matches!(
+                result,
+                Err(RepositoryError::PickInProgress(name)) if name == "cherry-pick"
+            ));
+        }
+        repository.pick_abort(PickKind::CherryPick).unwrap();
+        assert_eq!(repository.pick_in_progress().unwrap(), None);
+        assert_eq!(read("work/a.txt").as_deref(), Some("master\n"));
+        assert_eq!(read("work/b.txt").as_deref(), Some("unstaged\n"));
+        assert_eq!(read("work/c.txt"), None);
+        let status = repository.status().unwrap();
+        let paths = status.entries.iter().map(|entry| entry.path.as_str());
+        assert_eq!(paths.collect::<Vec<_>>(), vec!["b.txt"]);
+        assert!(matches!(
+            repository.pick_abort(PickKind::CherryPick),
+            Err(RepositoryError::NoPickInProgress)
+        ));
+    }
+
+    #[test]
+    fn mainline_selection() {
+        let parents = ["p1", "p2"].map(String::from);
+        assert_eq!(mainline_parent(&[], None), Ok(None));
+        assert_eq!(mainline_parent(&parents[..1], None), Ok(Some("p1".into())));
+        assert_eq!(mainline_parent(&parents, Some(2)), Ok(Some("p2".into())));
+        assert!(mainline_parent(&parents, None).is_err());
+        assert!(mainline_parent(&parents, Some(0)).is_err());
+        assert!(mainline_parent(&parents, Some(3)).is_err());
+        assert!(mainline_parent(&parents[..1], Some(1)).is_err());
+    }
+
+    #[test]
+    fn revert_messages() {
+        assert_eq!(
+            revert_message("Fix", "abc", None),
+            "Revert \"Fix\"\n\nThis reverts commit abc."
+        );
+        assert_eq!(
+            revert_message("Merge", "abc", Some("p1")),
+            "Revert \"Merge\"\n\nThis reverts commit abc, reversing\nchanges made to p1."
+        );
+    }
+}
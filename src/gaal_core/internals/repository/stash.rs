@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+
+use crate::gaal_core::{
+    internals::{text_diff::DiffAlgorithm, text_merge::MergeLabels},
+    provider::{
+        directory::GaalCoreDirectoryActions,
+        directory_object::GaalCoreDirectoryObjectsActions,
+        index::{IndexEntry, MODE_TREE},
+        object::commit::Commit,
+    },
+};
+
+use super::{default::GaalRepository, merge::MergeConflict, refs::PreviousValue, RepositoryError};
+
+/// Newest stash, the older ones being in its reflog.
+const STASH_REF: &str = "refs/stash";
+
+/// A stash of the stack, the newest first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StashEntry {
+    /// Commit of the working directory, with `HEAD` and the commit of the index
+    /// as parents.
+    pub commit: String,
+    pub message: String,
+}
+
+/// Position in the stack of `stash@{<n>}` or `<n>`, `None` as well for
+/// positions no stack reaches.
+pub fn parse_stash_index(name: &str) -> Option<usize> {
+    let index = match name.strip_prefix("stash@{") {
+        Some(rest) => rest.strip_suffix('}')?,
+        None => name,
+    };
+    usize::try_from(index.parse::<u32>().ok()?).ok()
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Records the index and the tracked files of the working directory as a
+    /// stash on top of the stack, then resets both to `HEAD`. Untracked files
+    /// are left alone. Returns the stash commit.
+    pub fn stash_push(&self, message: Option<&str>) -> Result<String, RepositoryError> {
+        self.operation_check()?;
+        let head = self
+            .ref_resolve("HEAD")?
+            .ok_or_else(|| RepositoryError::UnknownReference("HEAD".to_string()))?;
+        let index_tree = self.tree_write_from_index()?;
+        let mut files = Vec::new();
+        for entry in self.index_read()?.entries {
+            let Some(metadata) = self.work_metadata(&entry.path)? else {
+                continue;
+            };
+            if metadata.mode == MODE_TREE {
+                continue;
+            }
+            match self.work_is_modified(&entry.path, &entry)? {
+                true => {
+                    let id = self.work_blob(&entry.path, &metadata, true)?;
+                    files.push((entry.path, metadata.mode, id));
+                }
+                false => files.push((entry.path, entry.mode, entry.id)),
+            }
+        }
+        let work_tree = self.tree_write_files(&files)?;
+        let head_commit = self.commit_read(&head)?;
+        if index_tree == head_commit.tree && work_tree == index_tree {
+            return Err(RepositoryError::NoLocalChanges);
+        }
+
+        let branch = self
+            .head_branch()?
+            .unwrap_or_else(|| "(no branch)".to_string());
+        let head_subject = head_commit.message.lines().next().unwrap_or_default();
+        let head_summary = format!("{} {}", &head[..7], head_subject);
+        let signature = self.committer_signature()?;
+        let index_commit = self.commit_write(
+            Commit::builder()
+                .tree(&index_tree)
+                .parent(&head)
+                .author(signature.clone())
+                .committer(signature.clone())
+                .message(&format!("index on {}: {}\n", branch, head_summary))
+                .build()?,
+        )?;
+        let message = match message {
+            Some(message) => format!("On {}: {}", branch, message),
+            None => format!("WIP on {}: {}", branch, head_summary),
+        };
+        let stash = self.commit_write(
+            Commit::builder()
+                .tree(&work_tree)
+                .parents(&[head, index_commit])
+                .author(signature.clone())
+                .committer(signature)
+                .message(&format!("{}\n", message))
+                .build()?,
+        )?;
+
+        let previous = self.ref_resolve(STASH_REF)?;
+        let expected = match &previous {
+            Some(previous) => PreviousValue::MustBe(previous.clone()),
+            None => PreviousValue::MustNotExist,
+        };
+        self.ref_update(STASH_REF, &stash, expected)?;
+        self.reflog_append(STASH_REF, previous.as_deref(), &stash, &message)?;
+        self.checkout_reset(&self.head_files()?)?;
+        Ok(stash)
+    }
+
+    /// Stashes of the stack, the newest first.
+    pub fn stash_list(&self) -> Result<Vec<StashEntry>, RepositoryError> {
+        Ok(self
+            .reflog_read(STASH_REF)?
+            .into_iter()
+            .rev()
+            .map(|entry| StashEntry {
+                commit: entry.new,
+                message: entry.message,
+            })
+            .collect())
+    }
+
+    /// Commit of the stash at `index` in the stack.
+    pub fn stash_get(&self, index: usize) -> Result<String, RepositoryError> {
+        self.stash_list()?
+            .into_iter()
+            .nth(index)
+            .map(|entry| entry.commit)
+            .ok_or_else(|| RepositoryError::NoSuchStash(format!("stash@{{{}}}", index)))
+    }
+
+    /// Merges the changes of the stash at `index` into `HEAD`, from the commit
+    /// it was made on. Once merged cleanly, the changes are left unstaged except
+    /// for new files. Returns the conflicts, the stash being kept in any case.
+    /// The index must match `HEAD`.
+    pub fn stash_apply(
+        &self,
+        index: usize,
+    ) -> Result<BTreeMap<String, MergeConflict>, RepositoryError> {
+        self.operation_check()?;
+        let head = self
+            .ref_resolve("HEAD")?
+            .ok_or_else(|| RepositoryError::UnknownReference("HEAD".to_string()))?;
+        self.merge_check_index()?;
+        let stash = self.commit_read(&self.stash_get(index)?)?;
+        let base = stash
+            .parents
+            .first()
+            .ok_or_else(|| RepositoryError::NoSuchStash(format!("stash@{{{}}}", index)))?;
+
+        let tree = |commit: &str| self.object_peel_to(commit, "tree");
+        let labels = MergeLabels {
+            base: "Stash base",
+            ours: "Updated upstream",
+            theirs: "Stashed changes",
+        };
+        let merge = self.merge_trees(
+            Some(&tree(base)?),
+            &tree(&head)?,
+            &stash.tree,
+            labels,
+            self.conflict_style(None)?,
+            DiffAlgorithm::default(),
+        )?;
+        self.merge_apply(&merge)?;
+        if !merge.conflicts.is_empty() {
+            return Ok(merge.conflicts);
+        }
+
+        let head = self.head_files()?;
+        self.index_update(|index| {
+            for (path, leaf) in head {
+                let staged = index
+                    .get(&path)
+                    .is_some_and(|entry| entry.id == leaf.sha && entry.mode_string() == leaf.mode);
+                if !staged {
+                    index.insert(IndexEntry {
+                        path,
+                        mode: leaf.mode_value()?,
+                        id: leaf.sha,
+                        ..Default::default()
+                    });
+                }
+            }
+            Ok(())
+        })?;
+        Ok(BTreeMap::new())
+    }
+
+    /// Removes the stash at `index` from the stack and returns its commit.
+    pub fn stash_drop(&self, index: usize) -> Result<String, RepositoryError> {
+        let mut entries = self.reflog_read(STASH_REF)?;
+        if index >= entries.len() {
+            return Err(RepositoryError::NoSuchStash(format!("stash@{{{}}}", index)));
+        }
+        let dropped = entries.remove(entries.len() - 1 - index);
+        match entries.last() {
+            Some(newest) => self.ref_update(STASH_REF, &newest.new, PreviousValue::Any)?,
+            None => self.ref_delete(STASH_REF, PreviousValue::Any)?,
+        }
+        self.reflog_write(STASH_REF, &entries)?;
+        Ok(dropped.new)
+    }
+
+    /// Applies the stash at `index`, then drops it unless it left conflicts.
+    pub fn stash_pop(
+        &self,
+        index: usize,
+    ) -> Result<BTreeMap<String, MergeConflict>, RepositoryError> {
+        let conflicts = self.stash_apply(index)?;
+        if conflicts.is_empty() {
+            self.stash_drop(index)?;
+        }
+        Ok(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod test_stash {
+    use super::parse_stash_index;
+    use crate::gaal_core::{
+        internals::repository::RepositoryError,
+        provider::memory::{read, repository, write, MemoryCoreDirectory},
+    };
+
+    #[test]
+    fn stash_round_trip() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a\n");
+        repository.index_add(&["a.txt"]).unwrap();
+        repository.commit_index("First", false).unwrap();
+        assert!(matches!(
+            repository.stash_push(None),
+            Err(RepositoryError::NoLocalChanges)
+        ));
+
+        write("work/a.txt", "first\n");
+        let first = repository.stash_push(Some("first")).unwrap();
+        assert_eq!(read("work/a.txt").as_deref(), Some("a\n"));
+        write("work/a.txt", "second\n");
+        let second = repository.stash_push(None).unwrap();
+        assert_eq!(read("work/a.txt").as_deref(), Some("a\n"));
+
+        let list = repository.stash_list().unwrap();
+        let commits = list.iter().map(|entry| &entry.commit).collect::<Vec<_>>();
+        assert_eq!(commits, vec![&second, &first]);
+        assert_eq!(list[1].message, "On master: first");
+        assert!(list[0].message.starts_with("WIP on master: "));
+
+        // Applying keeps the stash, popping drops it once applied cleanly.
+        assert!(repository.stash_apply(1).unwrap().is_empty());
+        assert_eq!(read("work/a.txt").as_deref(), Some("first\n"));
+        assert_eq!(repository.stash_list().unwrap().len(), 2);
+        let status = repository.status().unwrap();
+        assert!(status.entries.iter().all(|entry| entry.staged.is_none()));
+        write("work/a.txt", "a\n");
+        assert!(repository.stash_pop(0).unwrap().is_empty());
+        assert_eq!(read("work/a.txt").as_deref(), Some("second\n"));
+        assert_eq!(repository.stash_get(0).unwrap(), first);
+
+        for index in [1, usize::MAX] {
+            assert!(matches!(
+                repository.stash_drop(index),
+                Err(RepositoryError::NoSuchStash(_))
+            ));
+        }
+        assert_eq!(repository.stash_drop(0).unwrap(), first);
+        assert!(repository.stash_list().unwrap().is_empty());
+        assert_eq!(repository.ref_resolve("refs/stash").unwrap(), None);
+    }
+
+    #[test]
+    fn stash_names() {
+        assert_eq!(parse_stash_index("stash@{2}"), Some(2));
+        assert_eq!(parse_stash_index("1"), Some(1));
+        assert_eq!(parse_stash_index("stash@{x}"), None);
+        assert_eq!(parse_stash_index("stash@{1"), None);
+        assert_eq!(parse_stash_index("topic"), None);
+        assert_eq!(parse_stash_index("18446744073709551615"), None);
+        assert_eq!(parse_stash_index("stash@{99999999999999999999}"), None);
+    }
+}
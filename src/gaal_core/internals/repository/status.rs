@@ -0,0 +1,299 @@
+use std::collections::BTreeSet;
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+/// How a path differs between two of HEAD, the index and the working directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StatusChange {
+    Added,
+    Modified,
+    Deleted,
+    /// Same content as the path it holds, which was deleted.
+    Renamed(String),
+    /// Conflicted, with several stages in the index.
+    Unmerged,
+}
+
+impl StatusChange {
+    /// Letter used by the short format.
+    pub fn code(&self) -> char {
+        match self {
+            StatusChange::Added => 'A',
+            StatusChange::Modified => 'M',
+            StatusChange::Deleted => 'D',
+            StatusChange::Renamed(_) => 'R',
+            StatusChange::Unmerged => 'U',
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusEntry {
+    pub path: String,
+    /// Difference between HEAD and the index.
+    pub staged: Option<StatusChange>,
+    /// Difference between the index and the working directory.
+    pub unstaged: Option<StatusChange>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    /// Branch `HEAD` points to, `None` when detached.
+    pub branch: Option<String>,
+    /// Changed tracked paths, sorted.
+    pub entries: Vec<StatusEntry>,
+    /// Files not in the index, sorted. Directories holding only untracked files
+    /// are listed once, with a trailing `/`.
+    pub untracked: Vec<String>,
+}
+
+impl Status {
+    pub fn is_clean(&self) -> bool {
+        self.entries.is_empty() && self.untracked.is_empty()
+    }
+}
+
+/// The entry for `path`, added to `entries` if needed.
+fn status_entry<'a>(entries: &'a mut Vec<StatusEntry>, path: &str) -> &'a mut StatusEntry {
+    let position = match entries.iter().position(|entry| entry.path == path) {
+        Some(position) => position,
+        None => {
+            entries.push(StatusEntry {
+                path: path.to_string(),
+                staged: None,
+                unstaged: None,
+            });
+            entries.len() - 1
+        }
+    };
+    &mut entries[position]
+}
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Compares HEAD, the index and the working directory. Files whose stat data
+    /// matches the index aren't read; files that only changed stat data get their
+    /// index entry refreshed so the next run doesn't read them either.
+    pub fn status(&self) -> Result<Status, RepositoryError> {
+        let head = self.head_files()?;
+        let mut index = self.index_read()?;
+        let mut entries = Vec::new();
+
+        // HEAD against the index.
+        let conflicted = index
+            .entries
+            .iter()
+            .filter(|entry| entry.stage() != 0)
+            .map(|entry| entry.path.clone())
+            .collect::<BTreeSet<String>>();
+        let mut added = Vec::new();
+        let mut deleted = Vec::new();
+        for entry in index.entries.iter().filter(|entry| entry.stage() == 0) {
+            match head.get(&entry.path) {
+                None => added.push(entry.clone()),
+                Some(leaf) if leaf.sha != entry.id || leaf.mode != entry.mode_string() => {
+                    status_entry(&mut entries, &entry.path).staged = Some(StatusChange::Modified);
+                }
+                Some(_) => {}
+            }
+        }
+        for (path, leaf) in &head {
+            if index.get(path).is_none() && !conflicted.contains(path) {
+                deleted.push(leaf.clone());
+            }
+        }
+        for entry in added {
+            // A deleted file with the same content is taken as renamed.
+            let staged = match deleted.iter().position(|leaf| leaf.sha == entry.id) {
+                Some(position) => StatusChange::Renamed(deleted.remove(position).path),
+                None => StatusChange::Added,
+            };
+            status_entry(&mut entries, &entry.path).staged = Some(staged);
+        }
+        for leaf in deleted {
+            status_entry(&mut entries, &leaf.path).staged = Some(StatusChange::Deleted);
+        }
+        for path in &conflicted {
+            let status = status_entry(&mut entries, path);
+            status.staged = Some(StatusChange::Unmerged);
+            status.unstaged = Some(StatusChange::Unmerged);
+        }
+
+        // The index against the working directory.
+        let mut refreshed = false;
+        for entry in index.entries.iter_mut().filter(|entry| entry.stage() == 0) {
+            let unstaged = match self.work_metadata(&entry.path)? {
+                None => Some(StatusChange::Deleted),
+                Some(metadata) if entry.is_up_to_date(&metadata) => None,
+                Some(metadata) => {
+                    let id = self.work_blob(&entry.path, &metadata, false)?;
+                    if id == entry.id && metadata.mode == entry.mode {
+                        entry.mtime = metadata.mtime;
+                        entry.ctime = metadata.ctime;
+                        entry.size = metadata.size as u32;
+                        refreshed = true;
+                        None
+                    } else {
+                        Some(StatusChange::Modified)
+                    }
+                }
+            };
+            if unstaged.is_some() {
+                status_entry(&mut entries, &entry.path).unstaged = unstaged;
+            }
+        }
+        if refreshed {
+            // Refreshing is only an optimisation, another process may hold the lock.
+            match self.index_write(&index) {
+                Ok(()) | Err(RepositoryError::LockedIndex) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // Files the index doesn't know about.
+        let tracked_dirs = index
+            .entries
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .path
+                    .match_indices('/')
+                    .map(|(i, _)| entry.path[..i].to_string())
+                    .collect::<Vec<String>>()
+            })
+            .collect::<BTreeSet<String>>();
+        let tracked = index
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<BTreeSet<&str>>();
+        let mut untracked = BTreeSet::new();
+        for file in self.work_files("")? {
+            if tracked.contains(file.as_str()) {
+                continue;
+            }
+            // Report the outermost directory no tracked file lives in.
+            let untracked_dir = file
+                .match_indices('/')
+                .map(|(i, _)| &file[..i])
+                .find(|dir| !tracked_dirs.contains(*dir));
+            untracked.insert(match untracked_dir {
+                Some(dir) => format!("{}/", dir),
+                None => file,
+            });
+        }
+
+        Ok(Status {
+            branch: self.head_branch()?,
+            entries,
+            untracked: untracked.into_iter().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_status {
+    use super::{StatusChange, StatusEntry};
+    use crate::gaal_core::provider::memory::{remove, repository, write, MemoryCoreDirectory};
+
+    fn entry(
+        path: &str,
+        staged: Option<StatusChange>,
+        unstaged: Option<StatusChange>,
+    ) -> StatusEntry {
+        StatusEntry {
+            path: path.to_string(),
+            staged,
+            unstaged,
+        }
+    }
+
+    #[test]
+    fn status_classification() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        for file in ["a.txt", "b.txt", "c.txt", "d/e.txt"] {
+            write(&format!("work/{}", file), file);
+        }
+        repository.index_add(&["."]).unwrap();
+        assert_eq!(
+            repository.status().unwrap().untracked,
+            vec![] as Vec<String>
+        );
+        repository.commit_index("First", false).unwrap();
+        let status = repository.status().unwrap();
+        assert!(status.is_clean());
+        assert_eq!(status.branch.as_deref(), Some("master"));
+
+        write("work/a.txt", "changed");
+        remove("work/b.txt");
+        write("work/c.txt", "staged");
+        repository.index_add(&["c.txt"]).unwrap();
+        write("work/c.txt", "staged then changed");
+        write("work/new.txt", "new");
+        repository.index_add(&["new.txt"]).unwrap();
+        write("work/d/untracked.txt", "u");
+        write("work/x/y.txt", "u");
+        // Rewriting the same content only changes the stat data.
+        write("work/d/e.txt", "d/e.txt");
+
+        let status = repository.status().unwrap();
+        assert_eq!(
+            status.entries,
+            vec![
+                entry("a.txt", None, Some(StatusChange::Modified)),
+                entry("b.txt", None, Some(StatusChange::Deleted)),
+                entry(
+                    "c.txt",
+                    Some(StatusChange::Modified),
+                    Some(StatusChange::Modified)
+                ),
+                entry("new.txt", Some(StatusChange::Added), None),
+            ]
+        );
+        assert_eq!(status.untracked, vec!["d/untracked.txt", "x/"]);
+
+        // The refreshed stat data is saved, so the file isn't read again.
+        let index = repository.index_read().unwrap();
+        let metadata = repository.work_metadata("d/e.txt").unwrap().unwrap();
+        assert!(index.get("d/e.txt").unwrap().is_up_to_date(&metadata));
+    }
+
+    #[test]
+    fn status_renames() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/old.txt", "content");
+        write("work/other.txt", "other");
+        repository.index_add(&["."]).unwrap();
+        repository.commit_index("First", false).unwrap();
+
+        remove("work/old.txt");
+        write("work/new.txt", "content");
+        remove("work/other.txt");
+        write("work/changed.txt", "changed");
+        repository.index_add(&["."]).unwrap();
+
+        let status = repository.status().unwrap();
+        assert_eq!(
+            status.entries,
+            vec![
+                entry("changed.txt", Some(StatusChange::Added), None),
+                entry(
+                    "new.txt",
+                    Some(StatusChange::Renamed("old.txt".to_string())),
+                    None
+                ),
+                entry("other.txt", Some(StatusChange::Deleted), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    object::{commit::Signature, kvlm::Kvlm, tag::Tag, ObjectError},
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Reads `hash` as an annotated tag.
+    pub fn tag_read(&self, hash: &str) -> Result<Tag, ObjectError> {
+        let object = self.object_read(hash.to_string())?;
+        let fmt = object.fmt()?;
+        if fmt != "tag" {
+            return Err(ObjectError::InvalidData(format!(
+                "`{}` is a {}, not a tag",
+                hash, fmt
+            )));
+        }
+        Tag::try_from(Kvlm::try_from(object.serialize()?)?)
+    }
+
+    pub fn tag_write(&self, tag: Tag) -> Result<String, ObjectError> {
+        let data: Vec<u8> = Kvlm::try_from(tag)?.into();
+        self.object_write(GCDOA::new_object(
+            "tag".to_string(),
+            data,
+            self.object_format,
+        )?)
+    }
+
+    /// Creates `refs/tags/<name>` pointing at `object`. Without `annotation` the tag
+    /// is lightweight, otherwise a tag object carrying the tagger and message is
+    /// written first and the ref points at it. Returns the id the ref points at.
+    pub fn tag_create(
+        &self,
+        name: &str,
+        object: &str,
+        annotation: Option<(Signature, &str)>,
+    ) -> Result<String, RepositoryError> {
+        let ref_name = format!("refs/tags/{}", name);
+        if self.ref_resolve(&ref_name)?.is_some() {
+            return Err(RepositoryError::ReferenceExists(ref_name));
+        }
+
+        let hash = match annotation {
+            None => object.to_string(),
+            Some((tagger, message)) => {
+                let kind = self.object_read(object.to_string())?.fmt()?;
+                self.tag_write(Tag::new(object, &kind, name, tagger, message))?
+            }
+        };
+        self.ref_create(&ref_name, &hash)?;
+        Ok(hash)
+    }
+
+    /// Follows annotated tags from `hash` until an object that isn't a tag is
+    /// reached, returning its id and type.
+    pub fn object_peel(&self, hash: &str) -> Result<(String, String), ObjectError> {
+        let mut hash = hash.to_string();
+        loop {
+            let object = self.object_read(hash.clone())?;
+            let fmt = object.fmt()?;
+            if fmt != "tag" {
+                return Ok((hash, fmt));
+            }
+            hash = Tag::try_from(Kvlm::try_from(object.serialize()?)?)?.object;
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions,
+    directory_object::GaalCoreDirectoryObjectsActions,
+    index::MODE_TREE,
+    object::{
+        tree::{Tree, TreeLeaf},
+        ObjectError,
+    },
+};
+
+use super::{default::GaalRepository, index::normalize_work_path, RepositoryError};
+
+impl<
+        GCDA: GaalCoreDirectoryActions + Clone,
+        GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+    > GaalRepository<'_, GCDA, GCDOA>
+{
+    /// Reads `hash` as a tree.
+    pub fn tree_read(&self, hash: &str) -> Result<Tree, ObjectError> {
+        let object = self.object_read(hash.to_string())?;
+        let fmt = object.fmt()?;
+        if fmt != "tree" {
+            return Err(ObjectError::InvalidData(format!(
+                "`{}` is a {}, not a tree",
+                hash, fmt
+            )));
+        }
+        Tree::parse(&object.serialize()?, self.object_format)
+    }
+
+    /// Every non-tree entry reachable from the tree `hash`, keyed by its slash
+    /// separated path. The leafs' `path` holds the full path too.
+    pub fn tree_files(&self, hash: &str) -> Result<BTreeMap<String, TreeLeaf>, ObjectError> {
+        let mut files = BTreeMap::new();
+        self.tree_files_at(hash, "", &mut files)?;
+        Ok(files)
+    }
+
+    fn tree_files_at(
+        &self,
+        hash: &str,
+        prefix: &str,
+        files: &mut BTreeMap<String, TreeLeaf>,
+    ) -> Result<(), ObjectError> {
+        for leaf in self.tree_read(hash)?.leafs {
+            let path = match prefix {
+                "" => leaf.path.clone(),
+                _ => format!("{}/{}", prefix, leaf.path),
+            };
+            if leaf.is_tree() {
+                self.tree_files_at(&leaf.sha, &path, files)?;
+            } else {
+                files.insert(path.clone(), TreeLeaf { path, ..leaf });
+            }
+        }
+        Ok(())
+    }
+
+    /// Id of the entry at the slash separated `path` below the tree `hash`, the
+    /// tree itself for an empty path. `None` when nothing is there.
+    pub fn tree_entry(&self, hash: &str, path: &str) -> Result<Option<String>, ObjectError> {
+        let components = path
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<&str>>();
+        let mut hash = hash.to_string();
+        for (i, component) in components.iter().enumerate() {
+            let entry = self
+                .tree_read(&hash)?
+                .leafs
+                .into_iter()
+                .find(|leaf| leaf.path == *component);
+            match entry {
+                // Only the last component may be a file.
+                Some(leaf) if leaf.is_tree() || i == components.len() - 1 => hash = leaf.sha,
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(hash))
+    }
+
+    /// Files of the tree `HEAD` points to, empty before the first commit.
+    pub fn head_files(&self) -> Result<BTreeMap<String, TreeLeaf>, RepositoryError> {
+        match self.ref_resolve("HEAD")? {
+            Some(head) => Ok(self.tree_files(&self.object_peel_to(&head, "tree")?)?),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    pub fn tree_write(&self, tree: Tree) -> Result<String, ObjectError> {
+        let data: Vec<u8> = tree.try_into()?;
+        self.object_write(GCDOA::new_object(
+            "tree".to_string(),
+            data,
+            self.object_format,
+        )?)
+    }
+
+    /// Writes the nested trees holding `files`, given as `(path, mode, id)` with
+    /// slash separated paths, and returns the id of the root tree.
+    pub fn tree_write_files(&self, files: &[(String, u32, String)]) -> Result<String, ObjectError> {
+        let mut leafs = Vec::new();
+        let mut subtrees: BTreeMap<&str, Vec<(String, u32, String)>> = BTreeMap::new();
+        for (path, mode, id) in files {
+            match path.split_once('/') {
+                Some((dir, rest)) => {
+                    subtrees
+                        .entry(dir)
+                        .or_default()
+                        .push((rest.to_string(), *mode, id.clone()))
+                }
+                None => leafs.push(TreeLeaf {
+                    mode: format!("{:06o}", mode),
+                    path: path.clone(),
+                    sha: id.clone(),
+                }),
+            }
+        }
+        for (dir, files) in subtrees {
+            leafs.push(TreeLeaf {
+                mode: format!("{:06o}", MODE_TREE),
+                path: dir.to_string(),
+                sha: self.tree_write_files(&files)?,
+            });
+        }
+        self.tree_write(Tree { leafs })
+    }
+
+    /// Writes the staged files as trees and returns the root tree id. Fails while
+    /// the index holds conflicts.
+    pub fn tree_write_from_index(&self) -> Result<String, RepositoryError> {
+        let index = self.index_read()?;
+        let mut conflicts = index
+            .entries
+            .iter()
+            .filter(|entry| entry.stage() != 0)
+            .map(|entry| entry.path.clone())
+            .collect::<Vec<String>>();
+        if !conflicts.is_empty() {
+            conflicts.dedup();
+            return Err(RepositoryError::UnmergedPaths(conflicts));
+        }
+
+        let files = index
+            .entries
+            .into_iter()
+            .map(|entry| (entry.path, entry.mode, entry.id))
+            .collect::<Vec<_>>();
+        Ok(self.tree_write_files(&files)?)
+    }
+
+    /// Writes every file at or below `path` in the working directory as blobs and
+    /// trees, ignoring the index, and returns the id of the tree for `path`.
+    /// Empty directories are left out.
+    pub fn tree_write_from_work_dir(&self, path: &str) -> Result<String, RepositoryError> {
+        let path = normalize_work_path(path)?;
+        if self.work_metadata(&path)?.map(|metadata| metadata.mode) != Some(MODE_TREE) {
+            return Err(RepositoryError::InvalidPath(path));
+        }
+        let mut files = Vec::new();
+        for file in self.work_files(&path)? {
+            let Some(metadata) = self.work_metadata(&file)? else {
+                continue;
+            };
+            let id = self.work_blob(&file, &metadata, true)?;
+            let relative = match path.as_str() {
+                "" => file,
+                _ => file[path.len() + 1..].to_string(),
+            };
+            files.push((relative, metadata.mode, id));
+        }
+        Ok(self.tree_write_files(&files)?)
+    }
+}
+
+#[cfg(test)]
+mod test_tree_write {
+    use crate::gaal_core::internals::repository::RepositoryError;
+    use crate::gaal_core::provider::{
+        directory::GaalCoreDirectoryActionsType,
+        index::{MODE_EXECUTABLE, MODE_FILE, MODE_TREE},
+        memory::{repository, write, MemoryCoreDirectory, MemoryDirectory},
+    };
+
+    #[test]
+    fn tree_write_nested_index() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        let files = [
+            "a.txt",
+            "src.txt",
+            "src/main.rs",
+            "src/lib/mod.rs",
+            "src/lib/util.rs",
+            "run.sh",
+        ];
+        for file in files {
+            write(&format!("work/{}", file), file);
+        }
+        MemoryDirectory::set_executable(vec!["work/run.sh".to_string()], true).unwrap();
+        repository.index_add(&["."]).unwrap();
+
+        let root = repository.tree_write_from_index().unwrap();
+        let leafs = repository
+            .tree_read(&root)
+            .unwrap()
+            .leafs
+            .into_iter()
+            .map(|leaf| (leaf.path, leaf.mode))
+            .collect::<Vec<_>>();
+        let mode = |mode: u32| format!("{:06o}", mode);
+        assert_eq!(
+            leafs,
+            vec![
+                ("a.txt".to_string(), mode(MODE_FILE)),
+                ("run.sh".to_string(), mode(MODE_EXECUTABLE)),
+                // Trees sort as if their name ended with `/`.
+                ("src.txt".to_string(), mode(MODE_FILE)),
+                ("src".to_string(), mode(MODE_TREE)),
+            ]
+        );
+
+        let written = repository.tree_files(&root).unwrap();
+        let index = repository.index_read().unwrap();
+        assert_eq!(written.len(), files.len());
+        for entry in &index.entries {
+            let leaf = &written[&entry.path];
+            assert_eq!((&leaf.sha, &leaf.mode), (&entry.id, &entry.mode_string()));
+        }
+        assert_eq!(
+            repository.tree_entry(&root, "src/lib/util.rs").unwrap(),
+            Some(index.get("src/lib/util.rs").unwrap().id.clone())
+        );
+
+        // The same files give the same trees, whichever way they're read.
+        assert_eq!(repository.tree_write_from_work_dir("").unwrap(), root);
+        assert_eq!(
+            repository.tree_write_from_work_dir("src").unwrap(),
+            repository.tree_entry(&root, "src").unwrap().unwrap()
+        );
+    }
+
+    #[test]
+    fn tree_write_conflicted_index() {
+        let directory = MemoryCoreDirectory::default();
+        let repository = repository(&directory);
+        write("work/a.txt", "a");
+        repository.index_add(&["a.txt"]).unwrap();
+        let mut index = repository.index_read().unwrap();
+        let mut theirs = index.entries[0].clone();
+        theirs.set_stage(3);
+        index.remove("a.txt");
+        index.insert_stage(theirs);
+        repository.index_write(&index).unwrap();
+
+        assert!(matches!(
+            repository.tree_write_from_index(),
+            Err(RepositoryError::UnmergedPaths(paths)) if paths == vec!["a.txt"]
+        ));
+    }
+}
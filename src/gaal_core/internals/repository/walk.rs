@@ -0,0 +1,589 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+use crate::gaal_core::provider::{
+    directory::GaalCoreDirectoryActions, directory_object::GaalCoreDirectoryObjectsActions,
+    object::commit::Commit,
+};
+
+use super::{default::GaalRepository, RepositoryError};
+
+/// Order in which a walk returns commits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Newest commit date first.
+    #[default]
+    Date,
+    /// Children before their parents, keeping lines of history together.
+    Topological,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WalkOptions {
+    pub order: WalkOrder,
+    /// Oldest first, applied after the other options.
+    pub reverse: bool,
+    /// Only follow the first parent of merges.
+    pub first_parent: bool,
+    pub max_count: Option<usize>,
+    /// Only commits made at or after this Unix time.
+    pub since: Option<i64>,
+    /// Only commits made at or before this Unix time.
+    pub until: Option<i64>,
+    /// Only commits whose author `name <email>` contains this.
+    pub author: Option<String>,
+    /// Only commits changing something at or below one of these paths.
+    pub paths: Vec<String>,
+}
+
+/// One revision argument of a walk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RevisionRange<'s> {
+    /// `A`: `A` and its ancestors.
+    Include(&'s str),
+    /// `^A`: nothing reachable from `A`.
+    Exclude(&'s str),
+    /// `A..B`: reachable from `B` but not from `A`.
+    Between(&'s str, &'s str),
+    /// `A...B`: reachable from either but not from both.
+    Symmetric(&'s str, &'s str),
+}
+
+impl<'s> RevisionRange<'s> {
+    /// Parses a range, an empty side standing for `HEAD`.
+    pub fn parse(spec: &'s str) -> Self {
+        let or_head = |name: &'s str| if name.is_empty() { "HEAD" } else { name };
+        if let Some((a, b)) = spec.split_once("...") {
+            RevisionRange::Symmetric(or_head(a), or_head(b))
+        } else if let Some((a, b)) = spec.split_once("..") {
+            RevisionRange::Between(or_head(a), or_head(b))
+        } else if let Some(name) = spec.strip_prefix('^') {
+            RevisionRange::Exclude(name)
+        } else {
+            RevisionRange::Include(spec)
+        }
+    }
+}
+
+/// Reads a commit.
+pub type CommitReader<'r> = dyn FnMut(&str) -> Result<Commit, RepositoryError> + 'r;
+
+/// Reads the ids found at each of `paths` below a tree, `None` where nothing is.
+pub type TreeEntriesReader<'r> =
+    dyn FnMut(&str, &[String]) -> Result<Vec<Option<String>>, RepositoryError> + 'r;
+
+/// Every commit reachable from `tips`, tips included.
+pub fn commit_ancestors(
+    tips: &[String],
+    read: &mut CommitReader,
+) -> Result<HashSet<String>, RepositoryError> {
+    let mut seen = HashSet::new();
+    let mut pending = tips.to_vec();
+    while let Some(hash) = pending.pop() {
+        if seen.insert(hash.clone()) {
+            pending.extend(read(&hash)?.parents);
+        }
+    }
+    Ok(seen)
+}
+
+/// Selection and ordering of the commits of a walk. Commits and trees are read
+/// through the readers passed to each call, so the walk doesn't depend on where
+/// they are stored.
+pub struct CommitWalk {
+    options: WalkOptions,
+    excluded: HashSet<String>,
+    /// Commits waiting to be returned, newest first then in discovery order.
+    queue: BinaryHeap<(i64, Reverse<usize>, String)>,
+    pending: HashMap<String, Commit>,
+    seen: HashSet<String>,
+    discovered: usize,
+    returned: usize,
+    /// Whole result, computed up front for orders needing every commit.
+    sorted: Option<VecDeque<(String, Commit)>>,
+}
+
+impl CommitWalk {
+    /// Walks from the `included` commits, leaving out those reachable from the
+    /// `excluded` ones and, for each `symmetric` pair, those reachable from both.
+    pub fn new(
+        included: Vec<String>,
+        excluded: &[String],
+        symmetric: &[(String, String)],
+        options: WalkOptions,
+        read: &mut CommitReader,
+    ) -> Result<Self, RepositoryError> {
+        let mut hidden = commit_ancestors(excluded, read)?;
+        for (a, b) in symmetric {
+            let from_a = commit_ancestors(std::slice::from_ref(a), read)?;
+            hidden.extend(
+                commit_ancestors(std::slice::from_ref(b), read)?
+                    .into_iter()
+                    .filter(|hash| from_a.contains(hash)),
+            );
+        }
+
+        let mut walk = Self {
+            options,
+            excluded: hidden,
+            queue: BinaryHeap::new(),
+            pending: HashMap::new(),
+            seen: HashSet::new(),
+            discovered: 0,
+            returned: 0,
+            sorted: None,
+        };
+        for hash in included {
+            walk.discover(hash, read)?;
+        }
+        Ok(walk)
+    }
+
+    fn parents<'c>(&self, commit: &'c Commit) -> &'c [String] {
+        match self.options.first_parent {
+            true => &commit.parents[..commit.parents.len().min(1)],
+            false => &commit.parents,
+        }
+    }
+
+    fn discover(&mut self, hash: String, read: &mut CommitReader) -> Result<(), RepositoryError> {
+        if self.excluded.contains(&hash) || !self.seen.insert(hash.clone()) {
+            return Ok(());
+        }
+        let commit = read(&hash)?;
+        self.queue.push((
+            commit.committer.time,
+            Reverse(self.discovered),
+            hash.clone(),
+        ));
+        self.pending.insert(hash, commit);
+        self.discovered += 1;
+        Ok(())
+    }
+
+    /// Next commit by date, before filtering.
+    fn next_by_date(
+        &mut self,
+        read: &mut CommitReader,
+    ) -> Option<Result<(String, Commit), RepositoryError>> {
+        let (_, _, hash) = self.queue.pop()?;
+        let commit = self.pending.remove(&hash)?;
+        for parent in self.parents(&commit).to_vec() {
+            if let Err(e) = self.discover(parent, read) {
+                return Some(Err(e));
+            }
+        }
+        Some(Ok((hash, commit)))
+    }
+
+    /// Whether `commit` passes the date, author and path filters.
+    fn accepts(
+        &self,
+        commit: &Commit,
+        read: &mut CommitReader,
+        entries: &mut TreeEntriesReader,
+    ) -> Result<bool, RepositoryError> {
+        let options = &self.options;
+        let time = commit.committer.time;
+        if options.since.is_some_and(|since| time < since)
+            || options.until.is_some_and(|until| time > until)
+        {
+            return Ok(false);
+        }
+        if let Some(author) = &options.author {
+            let signature = format!("{} <{}>", commit.author.name, commit.author.email);
+            if !signature.contains(author.as_str()) {
+                return Ok(false);
+            }
+        }
+        if options.paths.is_empty() {
+            return Ok(true);
+        }
+
+        // Kept when the paths differ from every parent followed.
+        let own = entries(&commit.tree, &options.paths)?;
+        let parents = self.parents(commit);
+        if parents.is_empty() {
+            return Ok(own.iter().any(Option::is_some));
+        }
+        for parent in parents {
+            if entries(&read(parent)?.tree, &options.paths)? == own {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Computes the whole result when the order or `reverse` requires it.
+    fn sort(
+        &mut self,
+        read: &mut CommitReader,
+        entries: &mut TreeEntriesReader,
+    ) -> Result<VecDeque<(String, Commit)>, RepositoryError> {
+        let mut commits = Vec::new();
+        while let Some(commit) = self.next_by_date(read) {
+            commits.push(commit?);
+        }
+        if self.options.order == WalkOrder::Topological {
+            commits = self.topological(commits);
+        }
+
+        let mut sorted = VecDeque::new();
+        for (hash, commit) in commits {
+            if self
+                .options
+                .max_count
+                .is_some_and(|max| sorted.len() >= max)
+            {
+                break;
+            }
+            if self.accepts(&commit, read, entries)? {
+                sorted.push_back((hash, commit));
+            }
+        }
+        if self.options.reverse {
+            sorted.make_contiguous().reverse();
+        }
+        Ok(sorted)
+    }
+
+    /// Reorders `commits`, given newest first, so that no commit comes before
+    /// one of its children, following each parent line as far as possible.
+    fn topological(&self, commits: Vec<(String, Commit)>) -> Vec<(String, Commit)> {
+        let mut children = HashMap::new();
+        for (_, commit) in &commits {
+            for parent in self.parents(commit) {
+                *children.entry(parent.clone()).or_insert(0) += 1;
+            }
+        }
+        let mut by_hash = commits
+            .iter()
+            .map(|(hash, commit)| (hash.clone(), commit.clone()))
+            .collect::<HashMap<String, Commit>>();
+
+        let mut ready = commits
+            .iter()
+            .rev()
+            .filter(|(hash, _)| !children.contains_key(hash))
+            .map(|(hash, _)| hash.clone())
+            .collect::<Vec<String>>();
+        let mut sorted = Vec::with_capacity(commits.len());
+        while let Some(hash) = ready.pop() {
+            let Some(commit) = by_hash.remove(&hash) else {
+                continue;
+            };
+            for parent in self.parents(&commit).iter().rev() {
+                let Some(count) = children.get_mut(parent) else {
+                    continue;
+                };
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(parent.clone());
+                }
+            }
+            sorted.push((hash, commit));
+        }
+        sorted
+    }
+
+    /// Next selected commit, with its id.
+    pub fn next(
+        &mut self,
+        read: &mut CommitReader,
+        entries: &mut TreeEntriesReader,
+    ) -> Option<Result<(String, Commit), RepositoryError>> {
+        if self.options.order == WalkOrder::Topological || self.options.reverse {
+            if self.sorted.is_none() {
+                match self.sort(read, entries) {
+                    Ok(sorted) => self.sorted = Some(sorted),
+                    Err(e) => {
+                        self.sorted = Some(VecDeque::new());
+                        return Some(Err(e));
+                    }
+                }
+            }
+            return self.sorted.as_mut()?.pop_front().map(Ok);
+        }
+
+        if self
+            .options
+            .max_count
+            .is_some_and(|max| self.returned >= max)
+        {
+            return None;
+        }
+        loop {
+            let (hash, commit) = match self.next_by_date(read)? {
+                Ok(next) => next,
+                Err(e) => return Some(Err(e)),
+            };
+            match self.accepts(&commit, read, entries) {
+                Ok(true) => {
+                    self.returned += 1;
+                    return Some(Ok((hash, commit)));
+                }
+                Ok(false) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Iterator over the commits selected by `GaalRepository::rev_walk`, yielding
+/// their id and content.
+pub struct RevWalk<
+    'r,
+    'a,
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+> {
+    repository: &'r GaalRepository<'a, GCDA, GCDOA>,
+    walk: CommitWalk,
+}
+
+impl<'a, GCDA, GCDOA> GaalRepository<'a, GCDA, GCDOA>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    /// Walks the commits selected by `revisions` (see `RevisionRange`), `HEAD`
+    /// when empty.
+    pub fn rev_walk(
+        &self,
+        revisions: &[&str],
+        options: WalkOptions,
+    ) -> Result<RevWalk<'_, 'a, GCDA, GCDOA>, RepositoryError> {
+        let mut included = Vec::new();
+        let mut excluded = Vec::new();
+        let mut symmetric = Vec::new();
+        for revision in revisions {
+            match RevisionRange::parse(revision) {
+                RevisionRange::Include(name) => included.push(self.walk_tip(name)?),
+                RevisionRange::Exclude(name) => excluded.push(self.walk_tip(name)?),
+                RevisionRange::Between(a, b) => {
+                    excluded.push(self.walk_tip(a)?);
+                    included.push(self.walk_tip(b)?);
+                }
+                RevisionRange::Symmetric(a, b) => {
+                    let (a, b) = (self.walk_tip(a)?, self.walk_tip(b)?);
+                    included.push(a.clone());
+                    included.push(b.clone());
+                    symmetric.push((a, b));
+                }
+            }
+        }
+        if revisions.is_empty() {
+            included.extend(self.ref_resolve("HEAD")?);
+        }
+
+        let walk = CommitWalk::new(included, &excluded, &symmetric, options, &mut |hash| {
+            Ok(self.commit_read(hash)?)
+        })?;
+        Ok(RevWalk {
+            repository: self,
+            walk,
+        })
+    }
+
+    fn walk_tip(&self, name: &str) -> Result<String, RepositoryError> {
+        Ok(self.object_peel_to(&self.rev_parse(name)?, "commit")?)
+    }
+
+    /// Every commit reachable from `tips`, tips included.
+    pub fn commit_ancestors(&self, tips: &[String]) -> Result<HashSet<String>, RepositoryError> {
+        commit_ancestors(tips, &mut |hash| Ok(self.commit_read(hash)?))
+    }
+}
+
+impl<GCDA, GCDOA> Iterator for RevWalk<'_, '_, GCDA, GCDOA>
+where
+    GCDA: GaalCoreDirectoryActions + Clone,
+    GCDOA: GaalCoreDirectoryObjectsActions<GCDA> + Clone,
+{
+    type Item = Result<(String, Commit), RepositoryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let repository = self.repository;
+        self.walk.next(
+            &mut |hash| Ok(repository.commit_read(hash)?),
+            &mut |tree, paths| {
+                paths
+                    .iter()
+                    .map(|path| Ok(repository.tree_entry(tree, path)?))
+                    .collect()
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_revision_range {
+    use super::RevisionRange;
+
+    #[test]
+    fn revision_range_parse() {
+        assert_eq!(
+            RevisionRange::parse("master"),
+            RevisionRange::Include("master")
+        );
+        assert_eq!(
+            RevisionRange::parse("^v1.0"),
+            RevisionRange::Exclude("v1.0")
+        );
+        assert_eq!(
+            RevisionRange::parse("a..b"),
+            RevisionRange::Between("a", "b")
+        );
+        assert_eq!(
+            RevisionRange::parse("..topic"),
+            RevisionRange::Between("HEAD", "topic")
+        );
+        assert_eq!(
+            RevisionRange::parse("a..."),
+            RevisionRange::Symmetric("a", "HEAD")
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_commit_walk {
+    use std::collections::HashMap;
+
+    use super::{CommitWalk, WalkOptions, WalkOrder};
+    use crate::gaal_core::provider::object::{
+        commit::{Commit, Signature},
+        hash::GaalHashAlgorithm,
+    };
+
+    fn id(name: &str) -> String {
+        GaalHashAlgorithm::Sha1.hash(name.as_bytes())
+    }
+
+    /// ```text
+    /// r(1) - a(2) - b(4) ----- m(6)
+    ///          \              /
+    ///           c(3) ---- d(5)
+    /// ```
+    /// Commit dates in parentheses. `f` changes in `r` and `b`, `m` takes it
+    /// from `b`.
+    fn history() -> HashMap<String, (&'static str, Commit)> {
+        [
+            ("r", vec![], 1),
+            ("a", vec!["r"], 2),
+            ("b", vec!["a"], 4),
+            ("c", vec!["a"], 3),
+            ("d", vec!["c"], 5),
+            ("m", vec!["b", "d"], 6),
+        ]
+        .into_iter()
+        .map(|(name, parents, time)| {
+            let parents = parents.into_iter().map(id).collect::<Vec<String>>();
+            let commit = Commit::builder()
+                .tree(&id(&format!("tree {}", name)))
+                .parents(&parents)
+                .author(Signature::new("Alice", "alice@example.com", time, 0))
+                .build()
+                .unwrap();
+            (id(name), (name, commit))
+        })
+        .collect()
+    }
+
+    /// Names of the commits a walk from `included` returns.
+    fn walk(
+        included: &[&str],
+        excluded: &[&str],
+        symmetric: Option<(&str, &str)>,
+        options: WalkOptions,
+    ) -> Vec<&'static str> {
+        let history = history();
+        let mut read = |hash: &str| Ok(history[hash].1.clone());
+        let mut entries = |tree: &str, paths: &[String]| {
+            let blob = match history.values().find(|(_, c)| c.tree == tree).unwrap().0 {
+                "r" | "a" | "c" | "d" => "1",
+                _ => "2",
+            };
+            Ok(paths
+                .iter()
+                .map(|path| (path == "f").then(|| id(blob)))
+                .collect())
+        };
+        let symmetric = symmetric
+            .map(|(a, b)| vec![(id(a), id(b))])
+            .unwrap_or_default();
+        let excluded = excluded.iter().map(|name| id(name)).collect::<Vec<_>>();
+        let included = included.iter().map(|name| id(name)).collect();
+        let mut walk =
+            CommitWalk::new(included, &excluded, &symmetric, options, &mut read).unwrap();
+        let mut names = Vec::new();
+        while let Some(next) = walk.next(&mut read, &mut entries) {
+            names.push(history[&next.unwrap().0].0);
+        }
+        names
+    }
+
+    #[test]
+    fn walk_orders() {
+        let options = WalkOptions::default();
+        assert_eq!(
+            walk(&["m"], &[], None, options.clone()),
+            ["m", "d", "b", "c", "a", "r"]
+        );
+        let topological = WalkOptions {
+            order: WalkOrder::Topological,
+            ..options.clone()
+        };
+        assert_eq!(
+            walk(&["m"], &[], None, topological),
+            ["m", "b", "d", "c", "a", "r"]
+        );
+        let first_parent = WalkOptions {
+            first_parent: true,
+            ..options
+        };
+        assert_eq!(walk(&["m"], &[], None, first_parent), ["m", "b", "a", "r"]);
+    }
+
+    #[test]
+    fn walk_reverse_with_max_count() {
+        let options = WalkOptions {
+            reverse: true,
+            max_count: Some(3),
+            ..WalkOptions::default()
+        };
+        // The count applies before reversing.
+        assert_eq!(walk(&["m"], &[], None, options), ["b", "d", "m"]);
+        let options = WalkOptions {
+            max_count: Some(2),
+            ..WalkOptions::default()
+        };
+        assert_eq!(walk(&["m"], &[], None, options), ["m", "d"]);
+    }
+
+    #[test]
+    fn walk_ranges() {
+        let options = WalkOptions::default();
+        assert_eq!(walk(&["m"], &["b"], None, options.clone()), ["m", "d", "c"]);
+        assert_eq!(
+            walk(&["b", "d"], &[], Some(("b", "d")), options),
+            ["d", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn walk_path_simplification() {
+        let options = WalkOptions {
+            paths: vec!["f".to_string()],
+            ..WalkOptions::default()
+        };
+        // `m` has the content of its first parent, so it isn't listed.
+        assert_eq!(walk(&["m"], &[], None, options.clone()), ["b", "r"]);
+        let since = WalkOptions {
+            since: Some(3),
+            ..options
+        };
+        assert_eq!(walk(&["m"], &[], None, since), ["b"]);
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+
+/// Content is taken as binary when a NUL byte shows up this early.
+const BINARY_CHECK_LEN: usize = 8000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiffAlgorithm {
+    /// Shortest edit script.
+    #[default]
+    Myers,
+    /// Anchors on lines that occur once on each side, which keeps unrelated
+    /// lines such as braces from being matched.
+    Patience,
+}
+
+impl std::str::FromStr for DiffAlgorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "myers" | "default" => Ok(DiffAlgorithm::Myers),
+            "patience" => Ok(DiffAlgorithm::Patience),
+            _ => Err(format!("Unknown diff algorithm `{}`", name)),
+        }
+    }
+}
+
+/// One step turning the old lines into the new ones, with 0-based line
+/// indexes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Changes with the unchanged lines around them. Starts are 1-based like in the
+/// `@@` header, and point at the line before for empty ranges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub edits: Vec<Edit>,
+}
+
+impl Hunk {
+    /// `@@ -old_start,old_len +new_start,new_len @@`, lengths of 1 left out.
+    pub fn header(&self) -> String {
+        let range = |start: usize, len: usize| match len {
+            1 => format!("{}", start),
+            _ => format!("{},{}", start, len),
+        };
+        format!(
+            "@@ -{} +{} @@",
+            range(self.old_start, self.old_len),
+            range(self.new_start, self.new_len)
+        )
+    }
+}
+
+pub fn is_binary(data: &[u8]) -> bool {
+    data[..data.len().min(BINARY_CHECK_LEN)].contains(&0)
+}
+
+/// Lines of `data`, each keeping its newline.
+pub fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    data.split_inclusive(|b| *b == b'\n').collect()
+}
+
+/// Edits turning `old` into `new`, in order.
+pub fn diff_lines(old: &[&[u8]], new: &[&[u8]], algorithm: DiffAlgorithm) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    match algorithm {
+        DiffAlgorithm::Myers => diff_myers(old, new, 0, 0, &mut edits),
+        DiffAlgorithm::Patience => diff_patience(old, new, 0, 0, &mut edits),
+    }
+    // Lists the deletions of each block of changes before its insertions.
+    let mut start = 0;
+    while start < edits.len() {
+        let len = edits[start..]
+            .iter()
+            .take_while(|edit| !matches!(edit, Edit::Equal(..)))
+            .count();
+        edits[start..start + len].sort_by_key(|edit| matches!(edit, Edit::Insert(_)));
+        start += len.max(1);
+    }
+    edits
+}
+
+/// Numbers of inserted and deleted lines.
+pub fn edit_counts(edits: &[Edit]) -> (usize, usize) {
+    edits
+        .iter()
+        .fold((0, 0), |(added, deleted), edit| match edit {
+            Edit::Insert(_) => (added + 1, deleted),
+            Edit::Delete(_) => (added, deleted + 1),
+            Edit::Equal(..) => (added, deleted),
+        })
+}
+
+/// Groups `edits` into hunks with up to `context` unchanged lines around each
+/// change, merging hunks whose context would touch.
+pub fn hunks(edits: &[Edit], context: usize) -> Vec<Hunk> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Equal(..)) {
+            continue;
+        }
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    // Lines of each side before every edit.
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for edit in edits {
+        positions.push((old_line, new_line));
+        match edit {
+            Edit::Equal(..) => (old_line, new_line) = (old_line + 1, new_line + 1),
+            Edit::Delete(_) => old_line += 1,
+            Edit::Insert(_) => new_line += 1,
+        }
+    }
+    positions.push((old_line, new_line));
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let (old_before, new_before) = positions[start];
+            let (old_after, new_after) = positions[end];
+            let (old_len, new_len) = (old_after - old_before, new_after - new_before);
+            Hunk {
+                old_start: old_before + usize::from(old_len > 0),
+                old_len,
+                new_start: new_before + usize::from(new_len > 0),
+                new_len,
+                edits: edits[start..end].to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// Lengths of the common prefix of `old` and `new` and of their common suffix
+/// after it.
+fn common_ends(old: &[&[u8]], new: &[&[u8]]) -> (usize, usize) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix, suffix)
+}
+
+fn push_equal(edits: &mut Vec<Edit>, old_offset: usize, new_offset: usize, len: usize) {
+    edits.extend((0..len).map(|i| Edit::Equal(old_offset + i, new_offset + i)));
+}
+
+fn diff_myers(
+    old: &[&[u8]],
+    new: &[&[u8]],
+    old_offset: usize,
+    new_offset: usize,
+    edits: &mut Vec<Edit>,
+) {
+    let (prefix, suffix) = common_ends(old, new);
+    push_equal(edits, old_offset, new_offset, prefix);
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let (a_offset, b_offset) = (old_offset + prefix, new_offset + prefix);
+
+    if a.is_empty() {
+        edits.extend((0..b.len()).map(|j| Edit::Insert(b_offset + j)));
+    } else if b.is_empty() {
+        edits.extend((0..a.len()).map(|i| Edit::Delete(a_offset + i)));
+    } else {
+        let (x, y) = middle_snake(a, b);
+        diff_myers(&a[..x], &b[..y], a_offset, b_offset, edits);
+        diff_myers(&a[x..], &b[y..], a_offset + x, b_offset + y, edits);
+    }
+
+    push_equal(edits, a_offset + a.len(), b_offset + b.len(), suffix);
+}
+
+/// A point on a shortest edit path between `a` and `b`, found by searching
+/// from both ends at once so that only the furthest reaching paths of the
+/// current step are kept. `a` and `b` can't be empty nor share their first or
+/// last line, which keeps the point away from both ends.
+fn middle_snake(a: &[&[u8]], b: &[&[u8]]) -> (usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2 + 1;
+    // Furthest x reached on each diagonal k = x - y, from the start in
+    // `forward` and from the end in `backward`, where x and y count from the
+    // last lines.
+    let mut forward = vec![0isize; 2 * max as usize + 1];
+    let mut backward = vec![0isize; 2 * max as usize + 1];
+    let index = |k: isize| (k + max) as usize;
+    let next = |v: &[isize], d: isize, k: isize| {
+        if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            v[index(k + 1)]
+        } else {
+            v[index(k - 1)] + 1
+        }
+    };
+
+    for d in 0..max {
+        for k in (-d..=d).rev().step_by(2) {
+            let (x0, y0) = {
+                let x = next(&forward, d, k);
+                (x, x - k)
+            };
+            let (mut x, mut y) = (x0, y0);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index(k)] = x;
+            if odd && (k - delta).abs() < d && x + backward[index(delta - k)] >= n {
+                return (x0 as usize, y0 as usize);
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = next(&backward, d, k);
+            let mut y = x - k;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index(k)] = x;
+            if !odd && (k - delta).abs() <= d && x + forward[index(delta - k)] >= n {
+                return ((n - x) as usize, (m - y) as usize);
+            }
+        }
+    }
+    unreachable!("the paths from both ends always meet")
+}
+
+fn diff_patience(
+    old: &[&[u8]],
+    new: &[&[u8]],
+    old_offset: usize,
+    new_offset: usize,
+    edits: &mut Vec<Edit>,
+) {
+    let (prefix, suffix) = common_ends(old, new);
+    push_equal(edits, old_offset, new_offset, prefix);
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let (a_offset, b_offset) = (old_offset + prefix, new_offset + prefix);
+
+    // Lines found exactly once on each side, in the order of `b`.
+    let mut counts: HashMap<&[u8], (usize, usize, usize)> = HashMap::new();
+    for (i, line) in a.iter().enumerate() {
+        let count = counts.entry(line).or_insert((0, 0, 0));
+        count.0 += 1;
+        count.2 = i;
+    }
+    for line in b {
+        if let Some(count) = counts.get_mut(line) {
+            count.1 += 1;
+        }
+    }
+    let unique = b
+        .iter()
+        .enumerate()
+        .filter_map(|(j, line)| match counts.get(line) {
+            Some((1, 1, i)) => Some((*i, j)),
+            _ => None,
+        })
+        .collect::<Vec<(usize, usize)>>();
+
+    let anchors = longest_increasing(&unique);
+    if anchors.is_empty() {
+        diff_myers(a, b, a_offset, b_offset, edits);
+    } else {
+        let (mut i, mut j) = (0, 0);
+        for (anchor_i, anchor_j) in anchors {
+            diff_patience(
+                &a[i..anchor_i],
+                &b[j..anchor_j],
+                a_offset + i,
+                b_offset + j,
+                edits,
+            );
+            edits.push(Edit::Equal(a_offset + anchor_i, b_offset + anchor_j));
+            (i, j) = (anchor_i + 1, anchor_j + 1);
+        }
+        diff_patience(&a[i..], &b[j..], a_offset + i, b_offset + j, edits);
+    }
+
+    push_equal(edits, a_offset + a.len(), b_offset + b.len(), suffix);
+}
+
+/// Longest run of `pairs`, sorted by their second item, whose first items also
+/// increase, found by patience sorting.
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // Index in `pairs` of the top of each pile, and of the card below each one.
+    let mut piles: Vec<usize> = Vec::new();
+    let mut below = vec![None; pairs.len()];
+    for (index, (i, _)) in pairs.iter().enumerate() {
+        let pile = piles.partition_point(|top| pairs[*top].0 < *i);
+        if pile > 0 {
+            below[index] = Some(piles[pile - 1]);
+        }
+        match piles.get_mut(pile) {
+            Some(top) => *top = index,
+            None => piles.push(index),
+        }
+    }
+
+    let mut run = Vec::new();
+    let mut card = piles.last().copied();
+    while let Some(index) = card {
+        run.push(pairs[index]);
+        card = below[index];
+    }
+    run.reverse();
+    run
+}
+
+#[cfg(test)]
+mod test_text_diff {
+    use super::{diff_lines, edit_counts, hunks, is_binary, split_lines, DiffAlgorithm, Edit};
+
+    /// Rebuilds the new side from the old one and the edits.
+    fn apply(old: &[&[u8]], new: &[&[u8]], edits: &[Edit]) -> Vec<u8> {
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for edit in edits {
+            match *edit {
+                Edit::Equal(o, n) => {
+                    assert_eq!((o, n), (i, j));
+                    assert_eq!(old[o], new[n]);
+                    result.extend_from_slice(old[o]);
+                    (i, j) = (i + 1, j + 1);
+                }
+                Edit::Delete(o) => {
+                    assert_eq!(o, i);
+                    i += 1;
+                }
+                Edit::Insert(n) => {
+                    assert_eq!(n, j);
+                    result.extend_from_slice(new[n]);
+                    j += 1;
+                }
+            }
+        }
+        assert_eq!((i, j), (old.len(), new.len()));
+        result
+    }
+
+    #[test]
+    fn line_diff() {
+        let old = b"a\nb\nc\na\nb\nb\na\n";
+        let new = b"c\nb\na\nb\na\nc\n";
+        let (old_lines, new_lines) = (split_lines(old), split_lines(new));
+        for algorithm in [DiffAlgorithm::Myers, DiffAlgorithm::Patience] {
+            let edits = diff_lines(&old_lines, &new_lines, algorithm);
+            assert_eq!(apply(&old_lines, &new_lines, &edits), new);
+        }
+        let edits = diff_lines(&old_lines, &new_lines, DiffAlgorithm::Myers);
+        assert_eq!(edit_counts(&edits), (2, 3));
+
+        let edits = diff_lines(&[], &split_lines(b"x\ny"), DiffAlgorithm::Myers);
+        assert_eq!(edits, vec![Edit::Insert(0), Edit::Insert(1)]);
+
+        let edits = diff_lines(
+            &split_lines(b"a\nb\n"),
+            &split_lines(b"c\nb\n"),
+            DiffAlgorithm::Myers,
+        );
+        assert_eq!(
+            edits,
+            vec![Edit::Delete(0), Edit::Insert(0), Edit::Equal(1, 1)]
+        );
+    }
+
+    #[test]
+    fn hunk_grouping() {
+        let old = (1..=20).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = (1..=20)
+            .filter(|i| *i != 18)
+            .map(|i| match i {
+                3 => "three\n".to_string(),
+                _ => format!("{}\n", i),
+            })
+            .collect::<String>();
+        let (old_lines, new_lines) = (split_lines(old.as_bytes()), split_lines(new.as_bytes()));
+        let edits = diff_lines(&old_lines, &new_lines, DiffAlgorithm::Myers);
+
+        let headers = hunks(&edits, 3)
+            .iter()
+            .map(|hunk| hunk.header())
+            .collect::<Vec<String>>();
+        assert_eq!(headers, vec!["@@ -1,6 +1,6 @@", "@@ -15,6 +15,5 @@"]);
+        assert_eq!(hunks(&edits, 10).len(), 1);
+        assert_eq!(hunks(&edits, 0)[1].header(), "@@ -18 +17,0 @@");
+    }
+
+    #[test]
+    fn binary_detection() {
+        assert!(is_binary(b"PNG\0\x01"));
+        assert!(!is_binary(b"plain text\n"));
+    }
+}
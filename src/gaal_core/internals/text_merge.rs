@@ -0,0 +1,296 @@
+use std::ops::Range;
+
+use super::text_diff::{diff_lines, split_lines, DiffAlgorithm, Edit};
+
+/// Length of the `<<<<<<<`, `|||||||`, `=======` and `>>>>>>>` markers.
+const MARKER_LEN: usize = 7;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictStyle {
+    /// Both sides of each conflict, without their common first and last lines.
+    #[default]
+    Merge,
+    /// Both sides of each conflict and the base they changed.
+    Diff3,
+}
+
+impl std::str::FromStr for ConflictStyle {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "merge" => Ok(ConflictStyle::Merge),
+            "diff3" => Ok(ConflictStyle::Diff3),
+            _ => Err(format!("Unknown conflict style `{}`", name)),
+        }
+    }
+}
+
+/// Names written after the conflict markers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergeLabels<'l> {
+    pub base: &'l str,
+    pub ours: &'l str,
+    pub theirs: &'l str,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeResult {
+    /// Merged content, with conflict markers around the conflicts.
+    pub content: Vec<u8>,
+    pub conflicts: usize,
+}
+
+/// Base lines replaced on one side, and the lines replacing them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Change {
+    base: Range<usize>,
+    side: Range<usize>,
+}
+
+impl Change {
+    /// Lines added minus lines removed.
+    fn delta(&self) -> isize {
+        self.side.len() as isize - self.base.len() as isize
+    }
+}
+
+/// Groups `edits` into blocks of consecutive changes.
+fn changes(edits: &[Edit]) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut current: Option<Change> = None;
+    let (mut base_line, mut side_line) = (0, 0);
+    for edit in edits {
+        match edit {
+            Edit::Equal(..) => {
+                changes.extend(current.take());
+                (base_line, side_line) = (base_line + 1, side_line + 1);
+            }
+            Edit::Delete(_) => {
+                let change = current.get_or_insert(Change {
+                    base: base_line..base_line,
+                    side: side_line..side_line,
+                });
+                change.base.end += 1;
+                base_line += 1;
+            }
+            Edit::Insert(_) => {
+                let change = current.get_or_insert(Change {
+                    base: base_line..base_line,
+                    side: side_line..side_line,
+                });
+                change.side.end += 1;
+                side_line += 1;
+            }
+        }
+    }
+    changes.extend(current);
+    changes
+}
+
+/// What a side with `changes` turned the base lines `base` into, `delta` being
+/// the lines it gained before them. Also returns the lines gained after them.
+fn side_lines<'l>(
+    lines: &[&'l [u8]],
+    base: Range<usize>,
+    changes: &[Change],
+    delta: isize,
+) -> (Vec<&'l [u8]>, isize) {
+    let delta_after = delta + changes.iter().map(Change::delta).sum::<isize>();
+    let from = (base.start as isize + delta) as usize;
+    let to = (base.end as isize + delta_after) as usize;
+    (lines[from..to].to_vec(), delta_after)
+}
+
+fn push_lines(content: &mut Vec<u8>, lines: &[&[u8]]) {
+    for line in lines {
+        content.extend_from_slice(line);
+    }
+}
+
+/// Pushes the lines of one side of a conflict, ending the last one so that the
+/// next marker starts a line.
+fn push_conflict_lines(content: &mut Vec<u8>, lines: &[&[u8]]) {
+    push_lines(content, lines);
+    if !content.is_empty() && !content.ends_with(b"\n") {
+        content.push(b'\n');
+    }
+}
+
+fn push_marker(content: &mut Vec<u8>, marker: u8, label: &str) {
+    content.extend(std::iter::repeat_n(marker, MARKER_LEN));
+    if !label.is_empty() {
+        content.push(b' ');
+        content.extend_from_slice(label.as_bytes());
+    }
+    content.push(b'\n');
+}
+
+/// Merges the changes `ours` and `theirs` made to `base` line by line. Changes
+/// to separate lines are both kept and identical changes are taken once; changes
+/// that overlap or touch are conflicts, written between markers in `style`.
+pub fn merge_lines(
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+    labels: MergeLabels,
+    style: ConflictStyle,
+    algorithm: DiffAlgorithm,
+) -> MergeResult {
+    let base = split_lines(base);
+    let ours = split_lines(ours);
+    let theirs = split_lines(theirs);
+    let our_changes = changes(&diff_lines(&base, &ours, algorithm));
+    let their_changes = changes(&diff_lines(&base, &theirs, algorithm));
+
+    let mut content = Vec::new();
+    let mut conflicts = 0;
+    let (mut i, mut j) = (0, 0);
+    // Lines each side gained before the current group.
+    let (mut our_delta, mut their_delta) = (0isize, 0isize);
+    let mut base_line = 0;
+    loop {
+        let start = match (our_changes.get(i), their_changes.get(j)) {
+            (None, None) => break,
+            (Some(a), Some(b)) => a.base.start.min(b.base.start),
+            (Some(change), None) | (None, Some(change)) => change.base.start,
+        };
+        push_lines(&mut content, &base[base_line..start]);
+
+        // Gathers the changes overlapping or touching the group.
+        let (first_ours, first_theirs) = (i, j);
+        let mut end = start;
+        loop {
+            if let Some(change) = our_changes.get(i).filter(|c| c.base.start <= end) {
+                end = end.max(change.base.end);
+                i += 1;
+            } else if let Some(change) = their_changes.get(j).filter(|c| c.base.start <= end) {
+                end = end.max(change.base.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        let (our_lines, next_our_delta) =
+            side_lines(&ours, start..end, &our_changes[first_ours..i], our_delta);
+        let (their_lines, next_their_delta) = side_lines(
+            &theirs,
+            start..end,
+            &their_changes[first_theirs..j],
+            their_delta,
+        );
+        (our_delta, their_delta) = (next_our_delta, next_their_delta);
+
+        if first_ours == i {
+            push_lines(&mut content, &their_lines);
+        } else if first_theirs == j || our_lines == their_lines {
+            push_lines(&mut content, &our_lines);
+        } else {
+            conflicts += 1;
+            let base_lines = &base[start..end];
+            let (prefix, suffix) = match style {
+                ConflictStyle::Merge => {
+                    let prefix = our_lines
+                        .iter()
+                        .zip(&their_lines)
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    let suffix = our_lines[prefix..]
+                        .iter()
+                        .rev()
+                        .zip(their_lines[prefix..].iter().rev())
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    (prefix, suffix)
+                }
+                ConflictStyle::Diff3 => (0, 0),
+            };
+            push_lines(&mut content, &our_lines[..prefix]);
+            push_conflict_lines(&mut content, &[]);
+            push_marker(&mut content, b'<', labels.ours);
+            push_conflict_lines(&mut content, &our_lines[prefix..our_lines.len() - suffix]);
+            if style == ConflictStyle::Diff3 {
+                push_marker(&mut content, b'|', labels.base);
+                push_conflict_lines(&mut content, base_lines);
+            }
+            push_marker(&mut content, b'=', "");
+            push_conflict_lines(
+                &mut content,
+                &their_lines[prefix..their_lines.len() - suffix],
+            );
+            push_marker(&mut content, b'>', labels.theirs);
+            push_lines(&mut content, &our_lines[our_lines.len() - suffix..]);
+        }
+        base_line = end;
+    }
+    push_lines(&mut content, &base[base_line..]);
+
+    MergeResult { content, conflicts }
+}
+
+#[cfg(test)]
+mod test_text_merge {
+    use super::{merge_lines, ConflictStyle, MergeLabels};
+    use crate::gaal_core::internals::text_diff::DiffAlgorithm;
+
+    const LABELS: MergeLabels = MergeLabels {
+        base: "base",
+        ours: "ours",
+        theirs: "theirs",
+    };
+
+    fn merge(base: &str, ours: &str, theirs: &str, style: ConflictStyle) -> (String, usize) {
+        let result = merge_lines(
+            base.as_bytes(),
+            ours.as_bytes(),
+            theirs.as_bytes(),
+            LABELS,
+            style,
+            DiffAlgorithm::Myers,
+        );
+        (String::from_utf8(result.content).unwrap(), result.conflicts)
+    }
+
+    #[test]
+    fn clean_merge() {
+        let base = "a\nb\nc\nd\ne\n";
+        let (merged, conflicts) = merge(
+            base,
+            "A\nb\nc\nd\ne\n",
+            "a\nb\nc\nd\nE\nf\n",
+            ConflictStyle::Merge,
+        );
+        assert_eq!((merged.as_str(), conflicts), ("A\nb\nc\nd\nE\nf\n", 0));
+
+        // The same change on both sides is taken once.
+        let (merged, conflicts) = merge(
+            base,
+            "a\nx\nc\nd\ne\n",
+            "a\nx\nc\nd\ne\n",
+            ConflictStyle::Merge,
+        );
+        assert_eq!((merged.as_str(), conflicts), ("a\nx\nc\nd\ne\n", 0));
+    }
+
+    #[test]
+    fn conflict_markers() {
+        let base = "a\nb\nc\n";
+        let (merged, conflicts) = merge(base, "a\nB\nx\nc\n", "a\nB\ny\nc\n", ConflictStyle::Merge);
+        assert_eq!(conflicts, 1);
+        assert_eq!(
+            merged,
+            "a\nB\n<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\nc\n"
+        );
+
+        let (merged, _) = merge(base, "a\nB\nx\nc\n", "a\nB\ny\nc\n", ConflictStyle::Diff3);
+        assert_eq!(
+            merged,
+            "a\n<<<<<<< ours\nB\nx\n||||||| base\nb\n=======\nB\ny\n>>>>>>> theirs\nc\n"
+        );
+
+        // Sides without a final newline still leave the markers on their own lines.
+        let (merged, _) = merge("", "x", "y", ConflictStyle::Merge);
+        assert_eq!(merged, "<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\n");
+    }
+}
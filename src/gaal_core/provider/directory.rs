@@ -5,11 +5,37 @@ use crate::gaal_core::internals::repository::{default::GaalRepository, Repositor
 use super::{
     directory_config::{GaalDirectoryConfigActions, GaalDirectoryConfigSectionActions},
     directory_object::GaalCoreDirectoryObjectsActions,
+    object::hash::GaalHashAlgorithm,
 };
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GaalTimestamp {
+    pub seconds: i64,
+    pub nanoseconds: u32,
+}
+
+impl GaalTimestamp {
+    pub fn new(seconds: i64, nanoseconds: u32) -> Self {
+        Self {
+            seconds,
+            nanoseconds,
+        }
+    }
+}
+
+/// Stat data of an entry of the working directory, as recorded in the index.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GaalEntryMetadata {
+    /// Git-style mode: `0o100644`, `0o100755`, `0o120000` or `0o040000`.
+    pub mode: u32,
+    pub size: u64,
+    pub mtime: GaalTimestamp,
+    pub ctime: GaalTimestamp,
+}
+
 pub trait GaalCoreDirectoryActionsType {
     type PathItem: Clone + std::fmt::Debug + Into<String> + From<String>;
-    type Data: Clone + std::fmt::Debug + Into<String> + From<String>;
+    type Data: Clone + std::fmt::Debug + Into<Vec<u8>> + From<Vec<u8>>;
 
     type ConfigSection: GaalDirectoryConfigSectionActions + Clone + Default;
     type Config: Clone + std::fmt::Debug + GaalDirectoryConfigActions<Self::ConfigSection> + Default;
@@ -17,13 +43,32 @@ pub trait GaalCoreDirectoryActionsType {
     fn make_entry(path: Vec<Self::PathItem>) -> Result<(), std::io::Error>;
     fn is_path(path: Vec<Self::PathItem>) -> bool;
     fn is_entry(path: Vec<Self::PathItem>) -> bool;
+    fn list_path(path: Vec<Self::PathItem>) -> Result<Vec<Self::PathItem>, std::io::Error>;
     fn get_path() -> Vec<Self::PathItem>;
     fn save_data(
         path: Vec<Self::PathItem>,
         data: Self::Data,
         compress: bool,
     ) -> Result<(), std::io::Error>;
+    /// Like `save_data` without compression, but fails with
+    /// `std::io::ErrorKind::AlreadyExists` when the entry exists. Used as a lock.
+    fn create_data(path: Vec<Self::PathItem>, data: Self::Data) -> Result<(), std::io::Error>;
+    /// Undecodable compressed data is reported as `std::io::ErrorKind::InvalidData`.
     fn get_data(path: Vec<Self::PathItem>, decompress: bool) -> Result<Self::Data, std::io::Error>;
+    /// Moves an entry, replacing `to` in a single step if it exists.
+    fn rename_entry(
+        from: Vec<Self::PathItem>,
+        to: Vec<Self::PathItem>,
+    ) -> Result<(), std::io::Error>;
+    fn remove_entry(path: Vec<Self::PathItem>) -> Result<(), std::io::Error>;
+    /// Symlinks are described, not followed.
+    fn metadata(path: Vec<Self::PathItem>) -> Result<GaalEntryMetadata, std::io::Error>;
+    fn read_link(path: Vec<Self::PathItem>) -> Result<String, std::io::Error>;
+    /// Creates a symlink at `path` pointing to `target`.
+    fn make_link(path: Vec<Self::PathItem>, target: &str) -> Result<(), std::io::Error>;
+    fn set_executable(path: Vec<Self::PathItem>, executable: bool) -> Result<(), std::io::Error>;
+    /// Removes a directory, failing unless it is empty.
+    fn remove_path(path: Vec<Self::PathItem>) -> Result<(), std::io::Error>;
     fn is_config(path: Vec<Self::PathItem>) -> bool;
     fn save_config(path: Vec<Self::PathItem>, config: Self::Config) -> Result<(), std::io::Error>;
     fn get_config(path: Vec<Self::PathItem>) -> Result<Self::Config, std::io::Error>;
@@ -64,14 +109,22 @@ impl<GCDA: GaalCoreDirectoryActions, GCDOA: GaalCoreDirectoryObjectsActions<GCDA
     pub fn init(
         &self,
         path: Vec<GCDA::PathItem>,
-    ) -> Result<GaalRepository<GCDA, GCDOA>, RepositoryError> {
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError> {
         GaalRepository::create(path, self)
     }
 
+    pub fn init_with_object_format(
+        &self,
+        path: Vec<GCDA::PathItem>,
+        object_format: GaalHashAlgorithm,
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError> {
+        GaalRepository::create_with_object_format(path, self, object_format)
+    }
+
     pub fn derive_from_path(
         &self,
         path: Vec<GCDA::PathItem>,
-    ) -> Result<GaalRepository<GCDA, GCDOA>, RepositoryError> {
+    ) -> Result<GaalRepository<'_, GCDA, GCDOA>, RepositoryError> {
         GaalRepository::derive_from_path(path, self)
     }
 
@@ -95,6 +148,13 @@ impl<GCDA: GaalCoreDirectoryActions, GCDOA: GaalCoreDirectoryObjectsActions<GCDA
         GCDA::is_entry(path)
     }
 
+    pub fn list_path(
+        &self,
+        path: Vec<GCDA::PathItem>,
+    ) -> Result<Vec<GCDA::PathItem>, std::io::Error> {
+        GCDA::list_path(path)
+    }
+
     pub fn save_data(
         &self,
         path: Vec<GCDA::PathItem>,
@@ -112,6 +172,50 @@ impl<GCDA: GaalCoreDirectoryActions, GCDOA: GaalCoreDirectoryObjectsActions<GCDA
         GCDA::get_data(path, decompress)
     }
 
+    pub fn create_data(
+        &self,
+        path: Vec<GCDA::PathItem>,
+        data: GCDA::Data,
+    ) -> Result<(), std::io::Error> {
+        GCDA::create_data(path, data)
+    }
+
+    pub fn rename_entry(
+        &self,
+        from: Vec<GCDA::PathItem>,
+        to: Vec<GCDA::PathItem>,
+    ) -> Result<(), std::io::Error> {
+        GCDA::rename_entry(from, to)
+    }
+
+    pub fn remove_entry(&self, path: Vec<GCDA::PathItem>) -> Result<(), std::io::Error> {
+        GCDA::remove_entry(path)
+    }
+
+    pub fn metadata(&self, path: Vec<GCDA::PathItem>) -> Result<GaalEntryMetadata, std::io::Error> {
+        GCDA::metadata(path)
+    }
+
+    pub fn read_link(&self, path: Vec<GCDA::PathItem>) -> Result<String, std::io::Error> {
+        GCDA::read_link(path)
+    }
+
+    pub fn make_link(&self, path: Vec<GCDA::PathItem>, target: &str) -> Result<(), std::io::Error> {
+        GCDA::make_link(path, target)
+    }
+
+    pub fn set_executable(
+        &self,
+        path: Vec<GCDA::PathItem>,
+        executable: bool,
+    ) -> Result<(), std::io::Error> {
+        GCDA::set_executable(path, executable)
+    }
+
+    pub fn remove_path(&self, path: Vec<GCDA::PathItem>) -> Result<(), std::io::Error> {
+        GCDA::remove_path(path)
+    }
+
     pub fn is_config(&self, path: Vec<GCDA::PathItem>) -> bool {
         GCDA::is_config(path)
     }
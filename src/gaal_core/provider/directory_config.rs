@@ -105,18 +105,18 @@ where
     }
 
     fn insert_section(&mut self, path: &[&str], section: Self, force: bool) {
-        if path.is_empty() {
-            eprintln!("Path cannot be empty!");
-        } else if path.len() == 1 {
-            self.sections.insert(path[0].to_string(), section);
-        } else {
-            let current_section = self.get_section_mut(&path[0..path.len() - 1]);
-            if let Some(current_section) = current_section {
-                current_section.insert_section(path, section, force);
-            } else if force {
-                let mut current_section = Self::new();
-                current_section.insert_section(&path[1..path.len()], section, force);
-                self.insert_section(&[path[0]], current_section, force);
+        match path {
+            [] => eprintln!("Path cannot be empty!"),
+            [name] => {
+                self.sections.insert(name.to_string(), section);
+            }
+            [first, rest @ ..] => {
+                if force && !self.sections.contains_key(*first) {
+                    self.sections.insert(first.to_string(), Self::new());
+                }
+                if let Some(current_section) = self.sections.get_mut(*first) {
+                    current_section.insert_section(rest, section, force);
+                }
             }
         }
     }
@@ -163,19 +163,19 @@ where
     }
 
     fn insert_item(&mut self, path: &[&str], item: Self::GCDSCI, force: bool) {
-        let section_path = &path[0..path.len() - 1];
-        let item_name = path[path.len() - 1];
-        if section_path.is_empty() {
-            self.items.insert(item_name.to_string(), item);
-            return;
-        }
-        let section = self.get_section_mut(section_path);
-        if let Some(section) = section {
-            section.items.insert(item_name.to_string(), item);
-        } else if force {
-            let mut section = Self::new();
-            section.insert_item(&[item_name], item, force);
-            self.insert_section(&path[0..path.len() - 1], section, force);
+        match path {
+            [] => eprintln!("Path cannot be empty!"),
+            [name] => {
+                self.items.insert(name.to_string(), item);
+            }
+            [first, rest @ ..] => {
+                if force && !self.sections.contains_key(*first) {
+                    self.sections.insert(first.to_string(), Self::new());
+                }
+                if let Some(section) = self.sections.get_mut(*first) {
+                    section.insert_item(rest, item, force);
+                }
+            }
         }
     }
 }
@@ -345,18 +345,18 @@ where
     }
 
     fn insert_section(&mut self, path: &[&str], section: GCDCS, force: bool) {
-        if path.is_empty() {
-            eprintln!("Path cannot be empty!");
-        } else if path.len() == 1 {
-            self.sections.insert(path[0].to_string(), section);
-        } else {
-            let current_section = self.get_section_mut(&path[0..path.len() - 1]);
-            if let Some(current_section) = current_section {
-                current_section.insert_section(path, section, force);
-            } else if force {
-                let mut current_section = GCDCS::new();
-                current_section.insert_section(&path[1..path.len()], section, force);
-                self.insert_section(&[path[0]], current_section, force);
+        match path {
+            [] => eprintln!("Path cannot be empty!"),
+            [name] => {
+                self.sections.insert(name.to_string(), section);
+            }
+            [first, rest @ ..] => {
+                if force && !self.sections.contains_key(*first) {
+                    self.sections.insert(first.to_string(), GCDCS::new());
+                }
+                if let Some(current_section) = self.sections.get_mut(*first) {
+                    current_section.insert_section(rest, section, force);
+                }
             }
         }
     }
@@ -384,15 +384,12 @@ where
     fn insert_item(&mut self, path: &[&str], item: GCDCS::GCDSCI, force: bool) {
         if path.len() <= 1 {
             panic!("Path must have at least 2 elements");
-        } else {
-            let current_section = self.get_section_mut(&path[0..path.len() - 1]);
-            if let Some(current_section) = current_section {
-                current_section.insert_item(&[path[path.len() - 1]], item, force);
-            } else if force {
-                let mut current_section = GCDCS::new();
-                current_section.insert_item(&path[1..path.len()], item, force);
-                self.insert_section(&[path[0]], current_section, force);
-            }
+        }
+        if force && !self.sections.contains_key(path[0]) {
+            self.sections.insert(path[0].to_string(), GCDCS::new());
+        }
+        if let Some(section) = self.sections.get_mut(path[0]) {
+            section.insert_item(&path[1..], item, force);
         }
     }
 }
@@ -410,6 +407,8 @@ impl<GCDCS: GaalDirectoryConfigSectionActions> Default for GaalDirectoryConfig<G
         config.insert_item(&["core", "filemode"], file_mode, true);
         let bare = GCDCS::GCDSCI::new(&"false".to_string().into());
         config.insert_item(&["core", "bare"], bare, true);
+        let verify_objects = GCDCS::GCDSCI::new(&"false".to_string().into());
+        config.insert_item(&["core", "verifyobjects"], verify_objects, true);
         config
     }
 }
@@ -518,6 +517,31 @@ mod config_tests {
         assert_eq!(item, None);
     }
 
+    #[test]
+    fn test_insert_sibling_item() {
+        let mut config = GaalDirectoryConfig::<
+            GaalDirectoryConfigSection<GaalDirectoryConfigSectionItem<String>>,
+        >::new("config1");
+        let item = |value: &str| GaalDirectoryConfigSectionItem {
+            value: value.to_string(),
+        };
+        config.insert_item(&["branch", "main", "remote"], item("origin"), true);
+        config.insert_item(&["branch", "dev", "remote"], item("upstream"), true);
+        config.insert_item(&["branch", "main", "merge"], item("refs/heads/main"), true);
+        assert_eq!(
+            config.get_item(&["branch", "main", "remote"]),
+            Some(&item("origin"))
+        );
+        assert_eq!(
+            config.get_item(&["branch", "dev", "remote"]),
+            Some(&item("upstream"))
+        );
+        assert_eq!(
+            config.get_item(&["branch", "main", "merge"]),
+            Some(&item("refs/heads/main"))
+        );
+    }
+
     #[test]
     fn test_insert_item() {
         let mut config = GaalDirectoryConfig::<
@@ -2,7 +2,9 @@ use std::marker::PhantomData;
 
 use super::{
     directory::GaalCoreDirectoryActions,
-    object::{default::GaalObjectAction, ObjectError},
+    object::{
+        default::GaalObjectAction, hash::GaalHashAlgorithm, header::object_parse, ObjectError,
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -21,6 +23,69 @@ where
     gaal_tree: Option<GCDOATr>,
 }
 
+pub type GaalCoreDirectoryObjectsType<GCDA, GCDOA> = GaalCoreDirectoryObjectType<
+    GCDA,
+    <GCDOA as GaalCoreDirectoryObjectsActionsType<GCDA>>::GaalBlob,
+    <GCDOA as GaalCoreDirectoryObjectsActionsType<GCDA>>::GaalCommit,
+    <GCDOA as GaalCoreDirectoryObjectsActionsType<GCDA>>::GaalTag,
+    <GCDOA as GaalCoreDirectoryObjectsActionsType<GCDA>>::GaalTree,
+>;
+
+impl<GCDA, GCDOAB, GCDOAC, GCDOAT, GCDOATr>
+    GaalCoreDirectoryObjectType<GCDA, GCDOAB, GCDOAC, GCDOAT, GCDOATr>
+where
+    GCDA: GaalCoreDirectoryActions,
+    GCDOAB: GaalObjectAction<GCDA>,
+    GCDOAC: GaalObjectAction<GCDA>,
+    GCDOAT: GaalObjectAction<GCDA>,
+    GCDOATr: GaalObjectAction<GCDA>,
+{
+    pub fn blob(&self) -> Option<&GCDOAB> {
+        self.gaal_blob.as_ref()
+    }
+
+    pub fn commit(&self) -> Option<&GCDOAC> {
+        self.gaal_commit.as_ref()
+    }
+
+    pub fn tag(&self) -> Option<&GCDOAT> {
+        self.gaal_tag.as_ref()
+    }
+
+    pub fn tree(&self) -> Option<&GCDOATr> {
+        self.gaal_tree.as_ref()
+    }
+
+    pub fn fmt(&self) -> Result<String, ObjectError> {
+        let fmt: Vec<u8> = if let Some(gaal_blob) = &self.gaal_blob {
+            gaal_blob.fmt()?.into()
+        } else if let Some(gaal_commit) = &self.gaal_commit {
+            gaal_commit.fmt()?.into()
+        } else if let Some(gaal_tag) = &self.gaal_tag {
+            gaal_tag.fmt()?.into()
+        } else if let Some(gaal_tree) = &self.gaal_tree {
+            gaal_tree.fmt()?.into()
+        } else {
+            return Err(ObjectError::Inexistent("No object found".to_string()));
+        };
+        Ok(String::from_utf8_lossy(&fmt).to_string())
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, ObjectError> {
+        if let Some(gaal_blob) = &self.gaal_blob {
+            Ok(gaal_blob.serialize()?.into())
+        } else if let Some(gaal_commit) = &self.gaal_commit {
+            Ok(gaal_commit.serialize()?.into())
+        } else if let Some(gaal_tag) = &self.gaal_tag {
+            Ok(gaal_tag.serialize()?.into())
+        } else if let Some(gaal_tree) = &self.gaal_tree {
+            Ok(gaal_tree.serialize()?.into())
+        } else {
+            Err(ObjectError::Inexistent("No object found".to_string()))
+        }
+    }
+}
+
 pub trait GaalCoreDirectoryObjectsActionsType<GCDA>
 where
     GCDA: GaalCoreDirectoryActions,
@@ -32,14 +97,9 @@ where
 
     fn new_object(
         fmt: String,
-        data: String,
-    ) -> GaalCoreDirectoryObjectType<
-        GCDA,
-        Self::GaalBlob,
-        Self::GaalCommit,
-        Self::GaalTag,
-        Self::GaalTree,
-    > {
+        data: Vec<u8>,
+        format: GaalHashAlgorithm,
+    ) -> Result<GaalCoreDirectoryObjectsType<GCDA, Self>, ObjectError> {
         let mut item = GaalCoreDirectoryObjectType {
             _gaal_core_directory_actions: None,
             gaal_blob: None,
@@ -50,87 +110,55 @@ where
 
         match fmt.as_str() {
             "blob" => {
-                item.gaal_blob = Some(Self::GaalBlob::from_data("blob", data.into()));
+                item.gaal_blob = Some(Self::GaalBlob::from_serialized(
+                    "blob",
+                    data.into(),
+                    format,
+                )?);
             }
             "commit" => {
-                item.gaal_commit = Some(Self::GaalCommit::from_data("commit", data.into()));
+                item.gaal_commit = Some(Self::GaalCommit::from_serialized(
+                    "commit",
+                    data.into(),
+                    format,
+                )?);
             }
             "tree" => {
-                item.gaal_tree = Some(Self::GaalTree::from_data("tree", data.into()));
+                item.gaal_tree = Some(Self::GaalTree::from_serialized(
+                    "tree",
+                    data.into(),
+                    format,
+                )?);
             }
             "tag" => {
-                item.gaal_tag = Some(Self::GaalTag::from_data("tag", data.into()));
+                item.gaal_tag = Some(Self::GaalTag::from_serialized("tag", data.into(), format)?);
             }
-            _ => {}
+            _ => return Err(ObjectError::UnknownType(fmt)),
         };
 
-        item
+        Ok(item)
     }
 
     fn from_hash(
-        hash: String,
-    ) -> Result<
-        GaalCoreDirectoryObjectType<
-            GCDA,
-            Self::GaalBlob,
-            Self::GaalCommit,
-            Self::GaalTag,
-            Self::GaalTree,
-        >,
-        ObjectError,
-    > {
-        let info = hash.split("\x00").collect::<Vec<&str>>();
-        let fmt = info[0].to_string();
-        let data_bytes = info[2].to_string();
-
-        let mut item = GaalCoreDirectoryObjectType {
-            _gaal_core_directory_actions: None,
-            gaal_blob: None,
-            gaal_commit: None,
-            gaal_tag: None,
-            gaal_tree: None,
-        };
-
-        match fmt.as_str() {
-            "blob" => {
-                item.gaal_blob = Some(Self::GaalBlob::from_data("blob", data_bytes.into()));
-            }
-            "commit" => {
-                item.gaal_commit = Some(Self::GaalCommit::from_data("commit", data_bytes.into()));
-            }
-            "tree" => {
-                item.gaal_tree = Some(Self::GaalTree::from_data("tree", data_bytes.into()));
-            }
-            "tag" => {
-                item.gaal_tag = Some(Self::GaalTag::from_data("tag", data_bytes.into()));
-            }
-            _ => {
-                return Err(ObjectError::Inexistent(format!(
-                    "Object format {} not supported",
-                    fmt
-                )))
-            }
-        };
-        Ok(item)
+        hash: Vec<u8>,
+        format: GaalHashAlgorithm,
+    ) -> Result<GaalCoreDirectoryObjectsType<GCDA, Self>, ObjectError> {
+        let (fmt, data_bytes) = object_parse(&hash)?;
+        Self::new_object(fmt.to_string(), data_bytes.to_vec(), format)
     }
 
     fn hash(
-        item: GaalCoreDirectoryObjectType<
-            GCDA,
-            Self::GaalBlob,
-            Self::GaalCommit,
-            Self::GaalTag,
-            Self::GaalTree,
-        >,
-    ) -> Result<(String, String), ObjectError> {
+        item: GaalCoreDirectoryObjectsType<GCDA, Self>,
+        format: GaalHashAlgorithm,
+    ) -> Result<(String, Vec<u8>), ObjectError> {
         if let Some(gaal_blob) = &item.gaal_blob {
-            gaal_blob.hash()
+            gaal_blob.hash(format)
         } else if let Some(gaal_commit) = &item.gaal_commit {
-            gaal_commit.hash()
+            gaal_commit.hash(format)
         } else if let Some(gaal_tag) = &item.gaal_tag {
-            gaal_tag.hash()
+            gaal_tag.hash(format)
         } else if let Some(gaal_tree) = &item.gaal_tree {
-            gaal_tree.hash()
+            gaal_tree.hash(format)
         } else {
             Err(ObjectError::Inexistent("No object found".to_string()))
         }
@@ -0,0 +1,357 @@
+use super::{
+    directory::{GaalEntryMetadata, GaalTimestamp},
+    object::{hash::GaalHashAlgorithm, ObjectError},
+};
+
+const INDEX_SIGNATURE: &[u8; 4] = b"GIDX";
+const INDEX_VERSION: u32 = 1;
+/// Fixed size part of an entry: four timestamps, mode, size and flags, without the
+/// digest.
+const ENTRY_FIXED_LEN: usize = 6 * 4 + 2;
+const FLAG_NAME_MASK: u16 = 0x0fff;
+const FLAG_STAGE_SHIFT: u16 = 12;
+
+pub const MODE_FILE: u32 = 0o100644;
+pub const MODE_EXECUTABLE: u32 = 0o100755;
+pub const MODE_SYMLINK: u32 = 0o120000;
+pub const MODE_TREE: u32 = 0o040000;
+
+/// A staged file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Slash separated path relative to the working directory.
+    pub path: String,
+    /// Octal git-style mode, one of the `MODE_*` constants.
+    pub mode: u32,
+    pub id: String,
+    pub size: u32,
+    pub mtime: GaalTimestamp,
+    pub ctime: GaalTimestamp,
+    /// Stage in bits 12-13 (0 unless the path is conflicted); the name length bits
+    /// are computed when the index is written.
+    pub flags: u16,
+}
+
+/// The staging area: entries sorted by path then stage.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Index {
+    pub entries: Vec<IndexEntry>,
+}
+
+// The index file is laid out as:
+// ```
+// "GIDX" <version: u32> <entry count: u32>
+// <ctime s> <ctime ns> <mtime s> <mtime ns> <mode> <size> <digest> <flags: u16> <path> <NUL padding>
+// ...
+// <digest of everything above>
+// ```
+// Integers are big endian, each entry is padded with 1 to 8 NULs to a multiple of
+// 8 bytes, and digests use the repository's hash algorithm.
+
+impl IndexEntry {
+    pub fn new(path: &str, id: &str, metadata: &GaalEntryMetadata) -> Self {
+        Self {
+            path: path.to_string(),
+            mode: metadata.mode,
+            id: id.to_string(),
+            size: metadata.size as u32,
+            mtime: metadata.mtime,
+            ctime: metadata.ctime,
+            flags: 0,
+        }
+    }
+
+    pub fn stage(&self) -> u8 {
+        ((self.flags >> FLAG_STAGE_SHIFT) & 0b11) as u8
+    }
+
+    pub fn set_stage(&mut self, stage: u8) {
+        self.flags = (self.flags & !(0b11 << FLAG_STAGE_SHIFT))
+            | ((stage as u16 & 0b11) << FLAG_STAGE_SHIFT);
+    }
+
+    /// Mode as written in trees, e.g. `100644`.
+    pub fn mode_string(&self) -> String {
+        format!("{:06o}", self.mode)
+    }
+
+    /// Whether the stat data recorded when the file was staged still matches, in
+    /// which case its content doesn't need to be hashed again.
+    pub fn is_up_to_date(&self, metadata: &GaalEntryMetadata) -> bool {
+        self.mode == metadata.mode
+            && self.size == metadata.size as u32
+            && self.mtime == metadata.mtime
+            && self.ctime == metadata.ctime
+    }
+}
+
+impl Index {
+    pub fn get(&self, path: &str) -> Option<&IndexEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.path == path && entry.stage() == 0)
+    }
+
+    /// Adds `entry`, replacing every stage recorded for its path.
+    pub fn insert(&mut self, entry: IndexEntry) {
+        self.entries.retain(|other| other.path != entry.path);
+        let position = self.entries.partition_point(|other| {
+            (other.path.as_str(), other.stage()) < (entry.path.as_str(), entry.stage())
+        });
+        self.entries.insert(position, entry);
+    }
+
+    /// Adds `entry` as one side of a conflict: replaces the same stage of its path
+    /// and the merged stage 0, keeping the other sides.
+    pub fn insert_stage(&mut self, entry: IndexEntry) {
+        self.entries.retain(|other| {
+            other.path != entry.path || (other.stage() != 0 && other.stage() != entry.stage())
+        });
+        let position = self.entries.partition_point(|other| {
+            (other.path.as_str(), other.stage()) < (entry.path.as_str(), entry.stage())
+        });
+        self.entries.insert(position, entry);
+    }
+
+    /// Removes every stage of `path`, returning whether anything was removed.
+    pub fn remove(&mut self, path: &str) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.path != path);
+        self.entries.len() != len
+    }
+
+    pub fn parse(raw: &[u8], format: GaalHashAlgorithm) -> Result<Index, ObjectError> {
+        let digest_len = format.digest_len();
+        let truncated = || ObjectError::InvalidData("Truncated index".to_string());
+
+        if raw.len() < 12 + digest_len {
+            return Err(truncated());
+        }
+        let (content, checksum) = raw.split_at(raw.len() - digest_len);
+        let actual = format.hash(content);
+        let expected = hex::encode(checksum);
+        if actual != expected {
+            return Err(ObjectError::HashMismatch { expected, actual });
+        }
+
+        if &content[0..4] != INDEX_SIGNATURE {
+            return Err(ObjectError::InvalidData("Not an index file".to_string()));
+        }
+        let version = read_u32(content, 4).ok_or_else(truncated)?;
+        if version != INDEX_VERSION {
+            return Err(ObjectError::InvalidData(format!(
+                "Unsupported index version {}",
+                version
+            )));
+        }
+        let count = read_u32(content, 8).ok_or_else(truncated)?;
+
+        // The count isn't trusted for the allocation, each entry takes at least
+        // `ENTRY_FIXED_LEN` bytes.
+        let mut entries = Vec::with_capacity((count as usize).min(content.len() / ENTRY_FIXED_LEN));
+        let mut i = 12;
+        for _ in 0..count {
+            let start = i;
+            let field = |n: usize| read_u32(content, start + n * 4).ok_or_else(truncated);
+            let ctime = GaalTimestamp::new(field(0)? as i64, field(1)?);
+            let mtime = GaalTimestamp::new(field(2)? as i64, field(3)?);
+            let mode = field(4)?;
+            let size = field(5)?;
+
+            let digest_start = start + 24;
+            let digest = content
+                .get(digest_start..digest_start + digest_len)
+                .ok_or_else(truncated)?;
+            let flags_start = digest_start + digest_len;
+            let flags = content
+                .get(flags_start..flags_start + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or_else(truncated)?;
+
+            let path_start = flags_start + 2;
+            let path_len = content[path_start..]
+                .iter()
+                .position(|b| *b == 0x00)
+                .ok_or_else(truncated)?;
+            let path = String::from_utf8(content[path_start..path_start + path_len].to_vec())
+                .map_err(|_| ObjectError::InvalidData("Index path is not UTF-8".to_string()))?;
+
+            entries.push(IndexEntry {
+                path,
+                mode,
+                id: hex::encode(digest),
+                size,
+                mtime,
+                ctime,
+                flags: flags & !FLAG_NAME_MASK,
+            });
+            i = start + padded_len(ENTRY_FIXED_LEN + digest_len + path_len);
+        }
+
+        if i != content.len() {
+            return Err(ObjectError::InvalidData(
+                "Trailing data after index entries".to_string(),
+            ));
+        }
+        Ok(Index { entries })
+    }
+
+    pub fn serialize(&self, format: GaalHashAlgorithm) -> Result<Vec<u8>, ObjectError> {
+        let mut raw = INDEX_SIGNATURE.to_vec();
+        raw.extend(INDEX_VERSION.to_be_bytes());
+        raw.extend((self.entries.len() as u32).to_be_bytes());
+
+        for entry in &self.entries {
+            let digest = hex::decode(&entry.id)
+                .ok()
+                .filter(|digest| digest.len() == format.digest_len())
+                .ok_or_else(|| {
+                    ObjectError::InvalidData(format!(
+                        "Invalid object id `{}` for `{}`",
+                        entry.id, entry.path
+                    ))
+                })?;
+            if entry.path.is_empty() || entry.path.contains('\0') {
+                return Err(ObjectError::InvalidData(format!(
+                    "Invalid index path `{}`",
+                    entry.path
+                )));
+            }
+
+            let start = raw.len();
+            for value in [
+                entry.ctime.seconds as u32,
+                entry.ctime.nanoseconds,
+                entry.mtime.seconds as u32,
+                entry.mtime.nanoseconds,
+                entry.mode,
+                entry.size,
+            ] {
+                raw.extend(value.to_be_bytes());
+            }
+            raw.extend(digest);
+            let name_len = entry.path.len().min(FLAG_NAME_MASK as usize) as u16;
+            raw.extend(((entry.flags & !FLAG_NAME_MASK) | name_len).to_be_bytes());
+            raw.extend(entry.path.as_bytes());
+            let len = raw.len() - start;
+            raw.resize(start + padded_len(len), 0x00);
+        }
+
+        let checksum = hex::decode(format.hash(&raw)).unwrap_or_default();
+        raw.extend(checksum);
+        Ok(raw)
+    }
+}
+
+fn read_u32(raw: &[u8], start: usize) -> Option<u32> {
+    raw.get(start..start + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Length of an entry once padded with at least one NUL to a multiple of 8.
+fn padded_len(len: usize) -> usize {
+    (len + 8) & !7
+}
+
+#[cfg(test)]
+mod test_index {
+    use super::{Index, IndexEntry, MODE_EXECUTABLE, MODE_FILE};
+    use crate::gaal_core::provider::{
+        directory::{GaalEntryMetadata, GaalTimestamp},
+        object::hash::GaalHashAlgorithm,
+    };
+
+    fn index(format: GaalHashAlgorithm) -> Index {
+        let metadata = GaalEntryMetadata {
+            mode: MODE_FILE,
+            size: 12,
+            mtime: GaalTimestamp::new(1700000000, 5),
+            ctime: GaalTimestamp::new(1700000001, 7),
+        };
+        let mut index = Index::default();
+        index.insert(IndexEntry::new(
+            "src/main.rs",
+            &"a".repeat(format.hex_len()),
+            &metadata,
+        ));
+        index.insert(IndexEntry::new(
+            "README.md",
+            &"b".repeat(format.hex_len()),
+            &metadata,
+        ));
+        let mut conflicted = IndexEntry::new("x", &"c".repeat(format.hex_len()), &metadata);
+        conflicted.mode = MODE_EXECUTABLE;
+        conflicted.set_stage(2);
+        index.insert(conflicted);
+        index
+    }
+
+    #[test]
+    fn index_round_trip() {
+        for format in [GaalHashAlgorithm::Sha1, GaalHashAlgorithm::Sha256] {
+            let index = index(format);
+            assert_eq!(
+                index
+                    .entries
+                    .iter()
+                    .map(|entry| entry.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["README.md", "src/main.rs", "x"]
+            );
+            assert_eq!(index.entries[2].stage(), 2);
+            assert_eq!(index.entries[2].mode_string(), "100755");
+
+            let raw = index.serialize(format).unwrap();
+            assert_eq!(Index::parse(&raw, format).unwrap(), index);
+        }
+    }
+
+    #[test]
+    fn index_checksum() {
+        let format = GaalHashAlgorithm::Sha256;
+        let mut raw = index(format).serialize(format).unwrap();
+        raw[20] ^= 0xff;
+        assert!(Index::parse(&raw, format).is_err());
+        assert!(Index::parse(&raw[..10], format).is_err());
+    }
+
+    #[test]
+    fn index_count_too_large() {
+        let format = GaalHashAlgorithm::Sha1;
+        let mut content = b"GIDX".to_vec();
+        content.extend(1u32.to_be_bytes());
+        content.extend(u32::MAX.to_be_bytes());
+        let mut raw = content.clone();
+        raw.extend(hex::decode(format.hash(&content)).unwrap());
+        assert!(Index::parse(&raw, format).is_err());
+    }
+
+    #[test]
+    fn index_insert_remove() {
+        let mut index = index(GaalHashAlgorithm::Sha1);
+        assert!(index.get("x").is_none());
+        assert!(index.get("README.md").is_some());
+        assert!(index.remove("README.md"));
+        assert!(!index.remove("README.md"));
+        assert_eq!(index.entries.len(), 2);
+    }
+
+    #[test]
+    fn index_insert_stage() {
+        let mut index = index(GaalHashAlgorithm::Sha1);
+        let mut base = index.entries[1].clone();
+        base.set_stage(1);
+        let mut theirs = base.clone();
+        theirs.set_stage(3);
+        index.insert_stage(theirs);
+        index.insert_stage(base);
+        let stages = index
+            .entries
+            .iter()
+            .filter(|entry| entry.path == "src/main.rs")
+            .map(|entry| entry.stage())
+            .collect::<Vec<u8>>();
+        assert_eq!(stages, vec![1, 3]);
+        assert!(index.get("src/main.rs").is_none());
+    }
+}
@@ -0,0 +1,377 @@
+//! A directory provider keeping everything in memory, for tests. Each thread sees
+//! its own tree, and errors follow what `std::fs` reports for the same calls.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Read, Write},
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use super::{
+    directory::{
+        GaalCoreDirectory, GaalCoreDirectoryActions, GaalCoreDirectoryActionsType,
+        GaalEntryMetadata, GaalTimestamp,
+    },
+    directory_config::{
+        GaalDirectoryConfig, GaalDirectoryConfigSection, GaalDirectoryConfigSectionItem,
+    },
+    directory_object::{GaalCoreDirectoryObjectsActions, GaalCoreDirectoryObjectsActionsType},
+    index::{MODE_EXECUTABLE, MODE_FILE, MODE_SYMLINK, MODE_TREE},
+    object::{default::GaalObject, kvlm::Kvlm, tree::Tree},
+};
+use crate::gaal_core::internals::repository::default::GaalRepository;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Dir,
+    File {
+        data: Vec<u8>,
+        executable: bool,
+        mtime: i64,
+    },
+    Link {
+        target: String,
+        mtime: i64,
+    },
+}
+
+thread_local! {
+    static NODES: RefCell<BTreeMap<Vec<String>, Node>> = const { RefCell::new(BTreeMap::new()) };
+    /// Every write gets a later modification time, so stat data always changes.
+    static CLOCK: Cell<i64> = const { Cell::new(1) };
+}
+
+fn split(path: &[String]) -> Vec<String> {
+    path.iter()
+        .flat_map(|item| item.split('/'))
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn tick() -> i64 {
+    CLOCK.with(|clock| {
+        clock.set(clock.get() + 1);
+        clock.get()
+    })
+}
+
+fn get(path: &[String]) -> Option<Node> {
+    NODES.with(|nodes| nodes.borrow().get(path).cloned())
+}
+
+fn set(path: Vec<String>, node: Node) {
+    NODES.with(|nodes| nodes.borrow_mut().insert(path, node));
+}
+
+/// Fails like a stat of `path` would when one of its parents is missing or isn't
+/// a directory.
+fn check_parents(path: &[String]) -> Result<(), Error> {
+    for end in 1..path.len() {
+        match get(&path[..end]) {
+            Some(Node::Dir) => {}
+            Some(_) => return Err(ErrorKind::NotADirectory.into()),
+            None => return Err(ErrorKind::NotFound.into()),
+        }
+    }
+    Ok(())
+}
+
+fn lookup(path: &[String]) -> Result<Node, Error> {
+    check_parents(path)?;
+    get(path).ok_or_else(|| ErrorKind::NotFound.into())
+}
+
+fn children(path: &[String]) -> Vec<String> {
+    NODES.with(|nodes| {
+        nodes
+            .borrow()
+            .keys()
+            .filter(|key| key.len() == path.len() + 1 && key.starts_with(path))
+            .map(|key| key[path.len()].clone())
+            .collect()
+    })
+}
+
+fn config_path(path: Vec<String>) -> Vec<String> {
+    let mut path = split(&path);
+    if let Some(last) = path.last_mut() {
+        last.push_str(".json");
+    }
+    path
+}
+
+#[derive(Clone, Debug)]
+pub struct MemoryDirectory;
+
+impl GaalCoreDirectoryActionsType for MemoryDirectory {
+    type PathItem = String;
+    type Data = Vec<u8>;
+    type ConfigSection = GaalDirectoryConfigSection<GaalDirectoryConfigSectionItem<String>>;
+    type Config = GaalDirectoryConfig<Self::ConfigSection>;
+
+    fn make_path(path: Vec<Self::PathItem>) -> Result<(), Error> {
+        let path = split(&path);
+        for end in 1..=path.len() {
+            match get(&path[..end]) {
+                Some(Node::Dir) => {}
+                Some(_) if end == path.len() => return Err(ErrorKind::AlreadyExists.into()),
+                Some(_) => return Err(ErrorKind::NotADirectory.into()),
+                None => set(path[..end].to_vec(), Node::Dir),
+            }
+        }
+        Ok(())
+    }
+
+    fn make_entry(path: Vec<Self::PathItem>) -> Result<(), Error> {
+        let path = split(&path);
+        Self::make_path(path[..path.len() - 1].to_vec())?;
+        Self::save_data(path, b"{}".to_vec(), false)
+    }
+
+    fn is_path(path: Vec<Self::PathItem>) -> bool {
+        matches!(get(&split(&path)), Some(Node::Dir))
+    }
+
+    fn is_entry(path: Vec<Self::PathItem>) -> bool {
+        matches!(get(&split(&path)), Some(Node::File { .. }))
+    }
+
+    fn list_path(path: Vec<Self::PathItem>) -> Result<Vec<Self::PathItem>, Error> {
+        let path = split(&path);
+        match lookup(&path)? {
+            Node::Dir => Ok(children(&path)),
+            _ => Err(ErrorKind::NotADirectory.into()),
+        }
+    }
+
+    fn get_path() -> Vec<Self::PathItem> {
+        Vec::new()
+    }
+
+    fn save_data(path: Vec<Self::PathItem>, data: Self::Data, compress: bool) -> Result<(), Error> {
+        let path = split(&path);
+        check_parents(&path)?;
+        let executable = match get(&path) {
+            Some(Node::Dir) => return Err(ErrorKind::IsADirectory.into()),
+            Some(Node::File { executable, .. }) => executable,
+            _ => false,
+        };
+        let data = match compress {
+            true => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&data)?;
+                encoder.finish()?
+            }
+            false => data,
+        };
+        set(
+            path,
+            Node::File {
+                data,
+                executable,
+                mtime: tick(),
+            },
+        );
+        Ok(())
+    }
+
+    fn create_data(path: Vec<Self::PathItem>, data: Self::Data) -> Result<(), Error> {
+        if get(&split(&path)).is_some() {
+            return Err(ErrorKind::AlreadyExists.into());
+        }
+        Self::save_data(path, data, false)
+    }
+
+    fn get_data(path: Vec<Self::PathItem>, decompress: bool) -> Result<Self::Data, Error> {
+        let Node::File { data, .. } = lookup(&split(&path))? else {
+            return Err(ErrorKind::IsADirectory.into());
+        };
+        if !decompress {
+            return Ok(data);
+        }
+        let mut contents = Vec::new();
+        ZlibDecoder::new(&data[..])
+            .read_to_end(&mut contents)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(contents)
+    }
+
+    fn rename_entry(from: Vec<Self::PathItem>, to: Vec<Self::PathItem>) -> Result<(), Error> {
+        let (from, to) = (split(&from), split(&to));
+        let node = lookup(&from)?;
+        check_parents(&to)?;
+        NODES.with(|nodes| nodes.borrow_mut().remove(&from));
+        set(to, node);
+        Ok(())
+    }
+
+    fn remove_entry(path: Vec<Self::PathItem>) -> Result<(), Error> {
+        let path = split(&path);
+        if let Node::Dir = lookup(&path)? {
+            return Err(ErrorKind::IsADirectory.into());
+        }
+        NODES.with(|nodes| nodes.borrow_mut().remove(&path));
+        Ok(())
+    }
+
+    fn metadata(path: Vec<Self::PathItem>) -> Result<GaalEntryMetadata, Error> {
+        let (mode, size, mtime) = match lookup(&split(&path))? {
+            Node::Dir => (MODE_TREE, 0, 0),
+            Node::File {
+                data,
+                executable,
+                mtime,
+            } => match executable {
+                true => (MODE_EXECUTABLE, data.len(), mtime),
+                false => (MODE_FILE, data.len(), mtime),
+            },
+            Node::Link { target, mtime } => (MODE_SYMLINK, target.len(), mtime),
+        };
+        Ok(GaalEntryMetadata {
+            mode,
+            size: size as u64,
+            mtime: GaalTimestamp::new(mtime, 0),
+            ctime: GaalTimestamp::new(mtime, 0),
+        })
+    }
+
+    fn read_link(path: Vec<Self::PathItem>) -> Result<String, Error> {
+        match lookup(&split(&path))? {
+            Node::Link { target, .. } => Ok(target),
+            _ => Err(ErrorKind::InvalidInput.into()),
+        }
+    }
+
+    fn make_link(path: Vec<Self::PathItem>, target: &str) -> Result<(), Error> {
+        let path = split(&path);
+        check_parents(&path)?;
+        if get(&path).is_some() {
+            return Err(ErrorKind::AlreadyExists.into());
+        }
+        set(
+            path,
+            Node::Link {
+                target: target.to_string(),
+                mtime: tick(),
+            },
+        );
+        Ok(())
+    }
+
+    fn set_executable(path: Vec<Self::PathItem>, executable: bool) -> Result<(), Error> {
+        let path = split(&path);
+        match lookup(&path)? {
+            Node::File { data, .. } => {
+                set(
+                    path,
+                    Node::File {
+                        data,
+                        executable,
+                        mtime: tick(),
+                    },
+                );
+                Ok(())
+            }
+            _ => Err(ErrorKind::InvalidInput.into()),
+        }
+    }
+
+    fn remove_path(path: Vec<Self::PathItem>) -> Result<(), Error> {
+        let path = split(&path);
+        match lookup(&path)? {
+            Node::Dir if children(&path).is_empty() => {
+                NODES.with(|nodes| nodes.borrow_mut().remove(&path));
+                Ok(())
+            }
+            Node::Dir => Err(ErrorKind::DirectoryNotEmpty.into()),
+            _ => Err(ErrorKind::NotADirectory.into()),
+        }
+    }
+
+    fn is_config(path: Vec<Self::PathItem>) -> bool {
+        Self::is_entry(config_path(path))
+    }
+
+    fn save_config(path: Vec<Self::PathItem>, config: Self::Config) -> Result<(), Error> {
+        Self::save_data(config_path(path), serde_json::to_vec(&config)?, false)
+    }
+
+    fn get_config(path: Vec<Self::PathItem>) -> Result<Self::Config, Error> {
+        Ok(serde_json::from_slice(&Self::get_data(
+            config_path(path),
+            false,
+        )?)?)
+    }
+
+    fn hash_object_to_path(hash: String) -> Vec<Self::PathItem> {
+        let (dir, file) = hash.split_at(2.min(hash.len()));
+        vec![dir.to_string(), file.to_string()]
+    }
+}
+
+impl GaalCoreDirectoryActions for MemoryDirectory {}
+
+#[derive(Clone)]
+pub struct MemoryObjects;
+
+impl GaalCoreDirectoryObjectsActionsType<MemoryDirectory> for MemoryObjects {
+    type GaalBlob = GaalObject<Vec<u8>>;
+    type GaalCommit = GaalObject<Kvlm>;
+    type GaalTag = GaalObject<Kvlm>;
+    type GaalTree = GaalObject<Tree>;
+}
+
+impl GaalCoreDirectoryObjectsActions<MemoryDirectory> for MemoryObjects {}
+
+pub type MemoryCoreDirectory = GaalCoreDirectory<MemoryDirectory, MemoryObjects>;
+
+pub type MemoryRepository<'a> = GaalRepository<'a, MemoryDirectory, MemoryObjects>;
+
+/// Root of the working directory of `repository`.
+pub const WORK_DIR: &str = "work";
+
+/// Creates a repository in `WORK_DIR` with an identity to commit with.
+pub fn repository(directory: &MemoryCoreDirectory) -> MemoryRepository<'_> {
+    let mut repository = directory.init(vec![WORK_DIR.to_string()]).unwrap();
+    repository.config_set(&["user", "name"], "Alice").unwrap();
+    repository
+        .config_set(&["user", "email"], "alice@example.com")
+        .unwrap();
+    repository
+}
+
+/// Stores an object as is, without the checks `object_write` makes when parsing
+/// it, and returns its id.
+pub fn store_object(repository: &MemoryRepository, fmt: &str, payload: &[u8]) -> String {
+    let mut raw = format!("{}\0{}\0", fmt, payload.len()).into_bytes();
+    raw.extend_from_slice(payload);
+    let hash = repository.object_format.hash(&raw);
+    let mut path = repository.gaal_path(&["objects"]);
+    path.extend(MemoryDirectory::hash_object_to_path(hash.clone()));
+    MemoryDirectory::make_path(path[..path.len() - 1].to_vec()).unwrap();
+    MemoryDirectory::save_data(path, raw, true).unwrap();
+    hash
+}
+
+/// Writes `data` to the slash separated `path`, creating its parents.
+pub fn write(path: &str, data: &str) {
+    let path = split(&[path.to_string()]);
+    MemoryDirectory::make_path(path[..path.len() - 1].to_vec()).unwrap();
+    MemoryDirectory::save_data(path, data.as_bytes().to_vec(), false).unwrap();
+}
+
+/// Content of the file at `path`, `None` when there's no file there.
+pub fn read(path: &str) -> Option<String> {
+    match get(&split(&[path.to_string()])) {
+        Some(Node::File { data, .. }) => Some(String::from_utf8(data).unwrap()),
+        _ => None,
+    }
+}
+
+/// Removes the file at `path`.
+pub fn remove(path: &str) {
+    MemoryDirectory::remove_entry(vec![path.to_string()]).unwrap();
+}
@@ -1,4 +1,7 @@
 pub mod directory;
 pub mod directory_config;
 pub mod directory_object;
+pub mod index;
+#[cfg(test)]
+pub mod memory;
 pub mod object;
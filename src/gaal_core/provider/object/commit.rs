@@ -0,0 +1,421 @@
+use std::fmt;
+
+use super::{hash::GaalHashAlgorithm, kvlm::Kvlm, ObjectError};
+
+/// Identity and time attached to a commit or tag, e.g.
+/// `Alice <alice@example.com> 1700000000 +0100`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    /// Seconds since the Unix epoch.
+    pub time: i64,
+    /// Offset from UTC in minutes.
+    pub offset: i32,
+}
+
+impl Signature {
+    pub fn new(name: &str, email: &str, time: i64, offset: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            email: email.to_string(),
+            time,
+            offset,
+        }
+    }
+
+    pub fn parse(data: &str) -> Result<Self, ObjectError> {
+        let invalid = || ObjectError::InvalidData(format!("Invalid signature `{}`", data));
+
+        let email_start = data.find('<').ok_or_else(invalid)?;
+        let email_end = data.rfind('>').ok_or_else(invalid)?;
+        if email_end < email_start {
+            return Err(invalid());
+        }
+
+        let name = data[..email_start].trim();
+        let email = &data[email_start + 1..email_end];
+        let rest = &data[email_end + 1..];
+
+        let (time, offset) = parse_date(rest).ok_or_else(invalid)?;
+
+        Ok(Self::new(name, email, time, offset))
+    }
+}
+
+/// Parses a `+HHMM`/`-HHMM` timezone into minutes.
+pub fn parse_offset(offset: &str) -> Option<i32> {
+    let (sign, digits) = match offset.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours = digits[..2].parse::<i32>().ok()?;
+    let minutes = digits[2..].parse::<i32>().ok()?;
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// Parses a `<seconds> <+HHMM>` date, as written in signatures.
+pub fn parse_date(date: &str) -> Option<(i64, i32)> {
+    let mut parts = date.split_whitespace();
+    let time = parts.next()?.parse::<i64>().ok()?;
+    let offset = parse_offset(parts.next()?)?;
+    match parts.next() {
+        Some(_) => None,
+        None => Some((time, offset)),
+    }
+}
+
+impl Signature {
+    /// Formats the offset as `+HHMM`/`-HHMM`.
+    pub fn timezone(&self) -> String {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.abs();
+        format!("{}{:02}{:02}", sign, offset / 60, offset % 60)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} <{}> {} {}",
+            self.name,
+            self.email,
+            self.time,
+            self.timezone()
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Commit {
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: Signature,
+    pub committer: Signature,
+    /// Headers this model doesn't know about (e.g. `encoding`), kept in order.
+    pub extra: Vec<(String, String)>,
+    pub gpgsig: Option<String>,
+    pub message: String,
+    /// Header keys in the order they were parsed, so re-serializing keeps the
+    /// object id. Empty for built commits, which use the canonical order.
+    pub header_order: Vec<String>,
+}
+
+/// Whether `id` is an object id of any supported hash algorithm.
+pub(super) fn is_object_id(id: &str) -> bool {
+    [GaalHashAlgorithm::Sha1, GaalHashAlgorithm::Sha256]
+        .iter()
+        .any(|format| format.is_object_id(id))
+}
+
+impl Commit {
+    pub fn builder() -> CommitBuilder {
+        CommitBuilder::default()
+    }
+
+    fn validate(&self) -> Result<(), ObjectError> {
+        for id in std::iter::once(&self.tree).chain(&self.parents) {
+            if !is_object_id(id) {
+                return Err(ObjectError::InvalidData(format!(
+                    "Invalid object id `{}` in commit",
+                    id
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<Kvlm> for Commit {
+    type Error = ObjectError;
+
+    fn try_from(mut kvlm: Kvlm) -> Result<Self, Self::Error> {
+        let header_order = kvlm.fields.iter().map(|(key, _)| key.clone()).collect();
+        let mut take = |key: &str| {
+            kvlm.remove(key)
+                .into_iter()
+                .next()
+                .ok_or_else(|| ObjectError::InvalidData(format!("Commit without `{}`", key)))
+        };
+
+        let tree = take("tree")?;
+        let author = Signature::parse(&take("author")?)?;
+        let committer = Signature::parse(&take("committer")?)?;
+        let gpgsig = take("gpgsig").ok();
+        let parents = kvlm.remove("parent");
+
+        let commit = Self {
+            tree,
+            parents,
+            author,
+            committer,
+            extra: kvlm.fields,
+            gpgsig,
+            message: kvlm.message,
+            header_order,
+        };
+        commit.validate()?;
+        Ok(commit)
+    }
+}
+
+impl TryFrom<Commit> for Kvlm {
+    type Error = ObjectError;
+
+    fn try_from(commit: Commit) -> Result<Self, Self::Error> {
+        let mut kvlm = Kvlm::default();
+        kvlm.push("tree", &commit.tree);
+        for parent in &commit.parents {
+            kvlm.push("parent", parent);
+        }
+        kvlm.push("author", &commit.author.to_string());
+        kvlm.push("committer", &commit.committer.to_string());
+        kvlm.fields.extend(commit.extra);
+        if let Some(gpgsig) = &commit.gpgsig {
+            kvlm.push("gpgsig", gpgsig);
+        }
+
+        // Parsed headers go back in their original order, as long as the same keys
+        // are still there. Otherwise the canonical order is used.
+        let mut keys = kvlm.fields.iter().map(|(k, _)| k).collect::<Vec<_>>();
+        let mut parsed = commit.header_order.iter().collect::<Vec<_>>();
+        keys.sort();
+        parsed.sort();
+        if keys == parsed {
+            let mut fields = Vec::with_capacity(kvlm.fields.len());
+            for key in &commit.header_order {
+                if let Some(position) = kvlm.fields.iter().position(|(k, _)| k == key) {
+                    fields.push(kvlm.fields.remove(position));
+                }
+            }
+            kvlm.fields = fields;
+        }
+        kvlm.message = commit.message;
+        Ok(kvlm)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CommitBuilder {
+    tree: Option<String>,
+    parents: Vec<String>,
+    author: Option<Signature>,
+    committer: Option<Signature>,
+    gpgsig: Option<String>,
+    message: String,
+}
+
+impl CommitBuilder {
+    pub fn tree(mut self, tree: &str) -> Self {
+        self.tree = Some(tree.to_string());
+        self
+    }
+
+    pub fn parent(mut self, parent: &str) -> Self {
+        self.parents.push(parent.to_string());
+        self
+    }
+
+    pub fn parents(mut self, parents: &[String]) -> Self {
+        self.parents.extend_from_slice(parents);
+        self
+    }
+
+    pub fn author(mut self, author: Signature) -> Self {
+        self.author = Some(author);
+        self
+    }
+
+    /// Defaults to the author when not set.
+    pub fn committer(mut self, committer: Signature) -> Self {
+        self.committer = Some(committer);
+        self
+    }
+
+    pub fn gpgsig(mut self, gpgsig: &str) -> Self {
+        self.gpgsig = Some(gpgsig.to_string());
+        self
+    }
+
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = message.to_string();
+        self
+    }
+
+    pub fn build(self) -> Result<Commit, ObjectError> {
+        let tree = self
+            .tree
+            .ok_or_else(|| ObjectError::InvalidData("Commit without tree".to_string()))?;
+        let author = self
+            .author
+            .ok_or_else(|| ObjectError::InvalidData("Commit without author".to_string()))?;
+        let committer = self.committer.unwrap_or_else(|| author.clone());
+
+        let commit = Commit {
+            tree,
+            parents: self.parents,
+            author,
+            committer,
+            extra: Vec::new(),
+            gpgsig: self.gpgsig,
+            message: self.message,
+            header_order: Vec::new(),
+        };
+        commit.validate()?;
+        Ok(commit)
+    }
+}
+
+#[cfg(test)]
+mod test_commit {
+    use super::{parse_date, Commit, Signature};
+    use crate::gaal_core::provider::object::kvlm::Kvlm;
+
+    const TREE: &str = "29ff16c9c14e2652b22f8b78bb08a5a07930c147";
+    const PARENT: &str = "206941306e8a8af65b66eaaaea388a7ae24d49a0";
+
+    #[test]
+    fn signature_round_trip() {
+        let data = "Thibault Polge <thibault@thb.lt> 1527025023 +0200";
+        let signature = Signature::parse(data).unwrap();
+        assert_eq!(signature.name, "Thibault Polge");
+        assert_eq!(signature.email, "thibault@thb.lt");
+        assert_eq!(signature.time, 1527025023);
+        assert_eq!(signature.offset, 120);
+        assert_eq!(signature.to_string(), data);
+
+        let negative = Signature::parse("A <a@b> 0 -0130").unwrap();
+        assert_eq!(negative.offset, -90);
+        assert_eq!(negative.to_string(), "A <a@b> 0 -0130");
+    }
+
+    #[test]
+    fn date_parse() {
+        assert_eq!(parse_date("1700000000 -0130"), Some((1700000000, -90)));
+        assert_eq!(parse_date("1700000000"), None);
+        assert_eq!(parse_date("1700000000 +0100 extra"), None);
+    }
+
+    #[test]
+    fn signature_invalid() {
+        assert!(Signature::parse("A a@b 0 +0000").is_err());
+        assert!(Signature::parse("A <a@b> now +0000").is_err());
+        assert!(Signature::parse("A <a@b> 0 0100").is_err());
+    }
+
+    #[test]
+    fn commit_from_kvlm() {
+        let data = format!(
+            "tree {}\n\
+            parent {}\n\
+            author Alice <alice@example.com> 1700000000 +0000\n\
+            committer Bob <bob@example.com> 1700000060 -0500\n\
+            \n\
+            Add a feature",
+            TREE, PARENT
+        );
+        let commit = Commit::try_from(Kvlm::from(data.clone())).unwrap();
+        assert_eq!(commit.tree, TREE);
+        assert_eq!(commit.parents, vec![PARENT.to_string()]);
+        assert_eq!(commit.author.name, "Alice");
+        assert_eq!(commit.committer.offset, -300);
+        assert_eq!(commit.message, "Add a feature");
+
+        let kvlm = Kvlm::try_from(commit).unwrap();
+        assert_eq!(String::from(kvlm), data);
+    }
+
+    #[test]
+    fn commit_from_kvlm_invalid() {
+        let missing_tree = "author A <a@b> 0 +0000\ncommitter A <a@b> 0 +0000\n\nmsg";
+        assert!(Commit::try_from(Kvlm::from(missing_tree.to_string())).is_err());
+
+        let bad_tree = "tree xyz\nauthor A <a@b> 0 +0000\ncommitter A <a@b> 0 +0000\n\nmsg";
+        assert!(Commit::try_from(Kvlm::from(bad_tree.to_string())).is_err());
+    }
+
+    #[test]
+    fn commit_builder() {
+        let author = Signature::new("Alice", "alice@example.com", 1700000000, 60);
+        let commit = Commit::builder()
+            .tree(TREE)
+            .parent(PARENT)
+            .author(author.clone())
+            .message("Initial commit\n")
+            .build()
+            .unwrap();
+        assert_eq!(commit.committer, author);
+        assert_eq!(commit.parents.len(), 1);
+
+        assert!(Commit::builder().author(author).build().is_err());
+    }
+
+    #[test]
+    fn merge_commit_round_trip() {
+        let other = "b".repeat(40);
+        let data = format!(
+            "tree {}\n\
+            parent {}\n\
+            parent {}\n\
+            author Alice <alice@example.com> 1700000000 +0000\n\
+            committer Alice <alice@example.com> 1700000000 +0000\n\
+            \n\
+            Merge branch 'topic'\n",
+            TREE, PARENT, other
+        );
+        let commit = Commit::try_from(Kvlm::from(data.clone())).unwrap();
+        assert_eq!(commit.parents, vec![PARENT.to_string(), other]);
+
+        let kvlm = Kvlm::try_from(commit).unwrap();
+        assert_eq!(String::from(kvlm), data);
+    }
+
+    #[test]
+    fn signed_commit_keeps_header_order() {
+        let data = format!(
+            "tree {}\n\
+            parent {}\n\
+            author Alice <alice@example.com> 1700000000 +0000\n\
+            committer Alice <alice@example.com> 1700000000 +0000\n\
+            gpgsig -----BEGIN PGP SIGNATURE-----\n \n abcd\n -----END PGP SIGNATURE-----\n\
+            encoding UTF-8\n\
+            mergetag object {}\n\
+            \n\
+            Signed\n",
+            TREE, PARENT, PARENT
+        );
+        let commit = Commit::try_from(Kvlm::from(data.clone())).unwrap();
+        assert!(commit.gpgsig.is_some());
+        assert_eq!(commit.extra.len(), 2);
+
+        let kvlm = Kvlm::try_from(commit.clone()).unwrap();
+        assert_eq!(String::from(kvlm), data);
+
+        let mut edited = commit;
+        edited.parents.push("c".repeat(40));
+        let kvlm = Kvlm::try_from(edited).unwrap();
+        let keys = kvlm
+            .fields
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "tree",
+                "parent",
+                "parent",
+                "author",
+                "committer",
+                "encoding",
+                "mergetag",
+                "gpgsig"
+            ]
+        );
+    }
+}
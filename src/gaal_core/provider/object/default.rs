@@ -1,27 +1,34 @@
 use crate::gaal_core::provider::directory::GaalCoreDirectoryActions;
-use sha2::{Digest, Sha256};
 
-use super::ObjectError;
+use super::{hash::GaalHashAlgorithm, ObjectError};
 
 pub trait GaalObjectAction<GCDA: GaalCoreDirectoryActions> {
-    type Data: Clone + std::fmt::Debug + Default + From<String> + Into<String>;
-    type Serialized: Clone + std::fmt::Debug + Default + ToString;
+    type Data: Clone + std::fmt::Debug + Default;
+    type Serialized: Clone + std::fmt::Debug + Default + From<Vec<u8>> + Into<Vec<u8>>;
 
     fn new(fmt: String) -> Self;
-    fn from_serialized(fmt: &str, data: Self::Serialized) -> Self;
+    fn from_serialized(
+        fmt: &str,
+        data: Self::Serialized,
+        format: GaalHashAlgorithm,
+    ) -> Result<Self, ObjectError>
+    where
+        Self: Sized;
     fn from_data(fmt: &str, data: Self::Data) -> Self;
     fn fmt(&self) -> Result<Self::Serialized, ObjectError>;
     fn serialize(&self) -> Result<Self::Serialized, ObjectError>;
     fn serialize_data(data: Self::Data) -> Result<Self::Serialized, ObjectError>;
     fn deserialize(&self) -> Result<Self::Data, ObjectError>;
-    fn deserialize_data(data: Self::Serialized) -> Result<Self::Data, ObjectError>;
+    fn deserialize_data(
+        data: Self::Serialized,
+        format: GaalHashAlgorithm,
+    ) -> Result<Self::Data, ObjectError>;
 
-    fn hash(&self) -> Result<(String, String), ObjectError> {
-        let data = self.serialize()?;
-        let data_bytes = data.to_string();
-        let data_len = data_bytes.len().to_string();
-        let fmt_bytes = self.fmt()?.to_string();
-        let separator = "\x00".to_string();
+    fn hash(&self, format: GaalHashAlgorithm) -> Result<(String, Vec<u8>), ObjectError> {
+        let data_bytes: Vec<u8> = self.serialize()?.into();
+        let data_len = data_bytes.len().to_string().into_bytes();
+        let fmt_bytes: Vec<u8> = self.fmt()?.into();
+        let separator = vec![0x00];
         let all = [
             fmt_bytes,
             separator.clone(),
@@ -30,10 +37,7 @@ pub trait GaalObjectAction<GCDA: GaalCoreDirectoryActions> {
             data_bytes,
         ]
         .concat();
-        let mut hasher = Sha256::new();
-        hasher.update(&all);
-        let hash = hasher.finalize();
-        Ok((format!("{:x}", hash), all))
+        Ok((format.hash(&all), all))
     }
 }
 
@@ -43,31 +47,35 @@ pub struct GaalObject<GaalObjectType> {
     pub data: GaalObjectType,
 }
 
-impl<GCDA> GaalObjectAction<GCDA> for GaalObject<String>
+impl<GCDA> GaalObjectAction<GCDA> for GaalObject<Vec<u8>>
 where
     GCDA: GaalCoreDirectoryActions,
 {
-    type Data = String;
-    type Serialized = String;
+    type Data = Vec<u8>;
+    type Serialized = Vec<u8>;
 
     fn new(fmt: String) -> Self {
         Self {
             fmt,
-            data: String::new(),
+            data: Vec::new(),
         }
     }
 
     fn fmt(&self) -> Result<Self::Serialized, ObjectError> {
-        Ok(self.fmt.clone())
+        Ok(self.fmt.clone().into_bytes())
     }
 
-    fn from_serialized(fmt: &str, data: Self::Serialized) -> Self {
+    fn from_serialized(
+        fmt: &str,
+        data: Self::Serialized,
+        format: GaalHashAlgorithm,
+    ) -> Result<Self, ObjectError> {
         let deserialized =
-            <GaalObject<String> as GaalObjectAction<GCDA>>::deserialize_data(data).unwrap();
-        Self {
+            <GaalObject<Vec<u8>> as GaalObjectAction<GCDA>>::deserialize_data(data, format)?;
+        Ok(Self {
             fmt: fmt.to_string(),
             data: deserialized,
-        }
+        })
     }
 
     fn from_data(fmt: &str, data: Self::Data) -> Self {
@@ -89,7 +97,10 @@ where
         Ok(self.data.clone())
     }
 
-    fn deserialize_data(data: Self::Serialized) -> Result<Self::Data, ObjectError> {
+    fn deserialize_data(
+        data: Self::Serialized,
+        _format: GaalHashAlgorithm,
+    ) -> Result<Self::Data, ObjectError> {
         Ok(data)
     }
 }
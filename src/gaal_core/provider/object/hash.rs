@@ -0,0 +1,105 @@
+use std::{fmt, str::FromStr};
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::ObjectError;
+
+/// Hash function used to name objects, selected per repository through the
+/// `extensions.objectformat` config entry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GaalHashAlgorithm {
+    Sha1,
+    #[default]
+    Sha256,
+}
+
+impl GaalHashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GaalHashAlgorithm::Sha1 => "sha1",
+            GaalHashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    /// Size of a raw digest in bytes, as stored in trees.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            GaalHashAlgorithm::Sha1 => 20,
+            GaalHashAlgorithm::Sha256 => 32,
+        }
+    }
+
+    /// Size of a digest written as hexadecimal, as used for object ids.
+    pub fn hex_len(&self) -> usize {
+        self.digest_len() * 2
+    }
+
+    pub fn is_object_id(&self, id: &str) -> bool {
+        id.len() == self.hex_len() && id.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    pub fn hash(&self, data: &[u8]) -> String {
+        match self {
+            GaalHashAlgorithm::Sha1 => hex::encode(Sha1::digest(data)),
+            GaalHashAlgorithm::Sha256 => hex::encode(Sha256::digest(data)),
+        }
+    }
+}
+
+impl FromStr for GaalHashAlgorithm {
+    type Err = ObjectError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "sha1" => Ok(GaalHashAlgorithm::Sha1),
+            "sha256" => Ok(GaalHashAlgorithm::Sha256),
+            _ => Err(ObjectError::InvalidData(format!(
+                "Unsupported object format `{}`",
+                name
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for GaalHashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod test_hash_algorithm {
+    use super::GaalHashAlgorithm;
+
+    #[test]
+    fn hash_sha1() {
+        let hash = GaalHashAlgorithm::Sha1.hash(b"blob 0\x00");
+        assert_eq!(hash, "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391");
+        assert!(GaalHashAlgorithm::Sha1.is_object_id(&hash));
+    }
+
+    #[test]
+    fn hash_sha256() {
+        let hash = GaalHashAlgorithm::Sha256.hash(b"blob 0\x00");
+        assert_eq!(
+            hash,
+            "473a0f4c3be8a93681a267e3b1e9a7dcda1185436fe141f7749120a303721813"
+        );
+        assert!(GaalHashAlgorithm::Sha256.is_object_id(&hash));
+        assert!(!GaalHashAlgorithm::Sha1.is_object_id(&hash));
+    }
+
+    #[test]
+    fn hash_algorithm_from_str() {
+        assert_eq!(
+            "sha1".parse::<GaalHashAlgorithm>().unwrap(),
+            GaalHashAlgorithm::Sha1
+        );
+        assert_eq!(
+            "SHA256".parse::<GaalHashAlgorithm>().unwrap(),
+            GaalHashAlgorithm::Sha256
+        );
+        assert!("md5".parse::<GaalHashAlgorithm>().is_err());
+    }
+}
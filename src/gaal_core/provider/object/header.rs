@@ -0,0 +1,103 @@
+use super::ObjectError;
+
+pub const OBJECT_TYPES: [&str; 4] = ["blob", "commit", "tag", "tree"];
+
+// Stored objects are prefixed with a header naming their type and payload length,
+// each terminated by a null byte:
+// ```
+// <fmt>\0<len>\0<payload>
+// ```
+
+/// Splits a decompressed object into its type and payload, validating the header.
+pub fn object_parse(raw: &[u8]) -> Result<(&str, &[u8]), ObjectError> {
+    let fmt_end = raw
+        .iter()
+        .position(|b| *b == 0x00)
+        .ok_or_else(|| ObjectError::TruncatedHeader("missing type terminator".to_string()))?;
+    let fmt = std::str::from_utf8(&raw[..fmt_end])
+        .map_err(|_| ObjectError::UnknownType(String::from_utf8_lossy(&raw[..fmt_end]).into()))?;
+    if !OBJECT_TYPES.contains(&fmt) {
+        return Err(ObjectError::UnknownType(fmt.to_string()));
+    }
+
+    let rest = &raw[fmt_end + 1..];
+    let len_end = rest
+        .iter()
+        .position(|b| *b == 0x00)
+        .ok_or_else(|| ObjectError::TruncatedHeader("missing length terminator".to_string()))?;
+    let len = &rest[..len_end];
+    let expected = std::str::from_utf8(len)
+        .ok()
+        .filter(|len| !len.is_empty() && len.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|len| len.parse::<usize>().ok())
+        .ok_or_else(|| {
+            ObjectError::TruncatedHeader(format!(
+                "invalid length `{}`",
+                String::from_utf8_lossy(len)
+            ))
+        })?;
+
+    let payload = &rest[len_end + 1..];
+    if payload.len() != expected {
+        return Err(ObjectError::LengthMismatch {
+            expected,
+            actual: payload.len(),
+        });
+    }
+
+    Ok((fmt, payload))
+}
+
+#[cfg(test)]
+mod test_object_parse {
+    use super::{object_parse, ObjectError};
+
+    #[test]
+    fn object_parse_keeps_null_bytes_in_payload() {
+        let raw = b"tree\x006\x00a\x00b\x00c\x00";
+        let (fmt, payload) = object_parse(raw).unwrap();
+        assert_eq!(fmt, "tree");
+        assert_eq!(payload, b"a\x00b\x00c\x00");
+    }
+
+    #[test]
+    fn object_parse_empty_payload() {
+        let (fmt, payload) = object_parse(b"blob\x000\x00").unwrap();
+        assert_eq!(fmt, "blob");
+        assert!(payload.is_empty());
+    }
+
+    #[test]
+    fn object_parse_unknown_type() {
+        let result = object_parse(b"bolb\x001\x00a");
+        assert!(matches!(result, Err(ObjectError::UnknownType(fmt)) if fmt == "bolb"));
+    }
+
+    #[test]
+    fn object_parse_length_mismatch() {
+        let result = object_parse(b"blob\x005\x00abc");
+        assert!(matches!(
+            result,
+            Err(ObjectError::LengthMismatch {
+                expected: 5,
+                actual: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn object_parse_truncated_header() {
+        assert!(matches!(
+            object_parse(b"blob"),
+            Err(ObjectError::TruncatedHeader(_))
+        ));
+        assert!(matches!(
+            object_parse(b"blob\x0012"),
+            Err(ObjectError::TruncatedHeader(_))
+        ));
+        assert!(matches!(
+            object_parse(b"blob\x00\x00"),
+            Err(ObjectError::TruncatedHeader(_))
+        ));
+    }
+}
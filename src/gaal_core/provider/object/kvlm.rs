@@ -1,15 +1,15 @@
-use indexmap::IndexMap;
-
 use crate::gaal_core::provider::directory::GaalCoreDirectoryActions;
 
 use super::{
     default::{GaalObject, GaalObjectAction},
+    hash::GaalHashAlgorithm,
     ObjectError,
 };
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Kvlm {
-    pub fields: IndexMap<String, String>,
+    /// Fields in the order they appear, a key may be repeated.
+    pub fields: Vec<(String, String)>,
     pub message: String,
 }
 
@@ -27,42 +27,88 @@ pub struct Kvlm {
 // body
 // ```
 
+impl Kvlm {
+    /// First value stored under `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value stored under `key`, in order.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Append a value, keeping the ones already stored under `key`.
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.fields.push((key.to_string(), value.to_string()));
+    }
+
+    /// Replace every value stored under `key` by a single one.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let mut found = false;
+        self.fields.retain_mut(|(k, v)| {
+            if k != key {
+                return true;
+            }
+            if found {
+                return false;
+            }
+            found = true;
+            *v = value.to_string();
+            true
+        });
+        if !found {
+            self.push(key, value);
+        }
+    }
+
+    /// Remove and return every value stored under `key`.
+    pub fn remove(&mut self, key: &str) -> Vec<String> {
+        let (removed, kept) = std::mem::take(&mut self.fields)
+            .into_iter()
+            .partition(|(k, _)| k == key);
+        self.fields = kept;
+        removed.into_iter().map(|(_, v)| v).collect()
+    }
+}
+
 impl From<String> for Kvlm {
     fn from(data: String) -> Self {
-        let mut fields = IndexMap::new();
+        let mut fields: Vec<(String, String)> = Vec::new();
         let mut message = String::new();
 
-        let mut current_key = String::new();
-        let mut is_message_body = false;
-        for line in data.lines() {
-            if is_message_body {
-                // Append remaining lines to the message body
-                message.push_str(line);
-                message.push('\n');
-                continue;
-            }
+        let mut rest = data.as_str();
+        while !rest.is_empty() {
+            let (line, next) = match rest.split_once('\n') {
+                Some((line, next)) => (line, next),
+                None => (rest, ""),
+            };
             if line.is_empty() {
-                // Empty line indicates the start of the message body
-                is_message_body = true;
-                continue;
+                // Empty line indicates the start of the message body, which is kept verbatim
+                message = next.to_string();
+                break;
             }
 
-            if let Some((key, value)) = line.split_once(' ') {
-                if !line.starts_with(' ') {
-                    current_key = key.to_string();
-                    fields.insert(current_key.clone(), value.to_string());
-                } else {
-                    // This is a continuation of the previous field
-                    if let Some(value) = fields.get_mut(&current_key) {
-                        value.push('\n');
-                        value.push_str(&line[1..]);
-                    }
+            if let Some(continuation) = line.strip_prefix(' ') {
+                // This is a continuation of the previous field
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push('\n');
+                    value.push_str(continuation);
                 }
+            } else {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                fields.push((key.to_string(), value.to_string()));
             }
+            rest = next;
         }
 
-        message = message.trim_end_matches('\n').to_string();
-
         Self { fields, message }
     }
 }
@@ -79,12 +125,30 @@ impl From<Kvlm> for String {
     }
 }
 
+/// Headers and messages are kept as text, so bytes that aren't UTF-8 are rejected
+/// rather than replaced, which would change the object id on re-serialization.
+impl TryFrom<Vec<u8>> for Kvlm {
+    type Error = ObjectError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        String::from_utf8(data)
+            .map(Kvlm::from)
+            .map_err(|e| ObjectError::InvalidData(format!("Object is not valid UTF-8: {}", e)))
+    }
+}
+
+impl From<Kvlm> for Vec<u8> {
+    fn from(kvlm: Kvlm) -> Self {
+        String::from(kvlm).into_bytes()
+    }
+}
+
 impl<GCDA> GaalObjectAction<GCDA> for GaalObject<Kvlm>
 where
     GCDA: GaalCoreDirectoryActions,
 {
     type Data = Kvlm;
-    type Serialized = String;
+    type Serialized = Vec<u8>;
 
     fn new(fmt: String) -> Self {
         Self {
@@ -94,16 +158,20 @@ where
     }
 
     fn fmt(&self) -> Result<Self::Serialized, ObjectError> {
-        Ok(self.fmt.clone())
+        Ok(self.fmt.clone().into_bytes())
     }
 
-    fn from_serialized(fmt: &str, data: Self::Serialized) -> Self {
+    fn from_serialized(
+        fmt: &str,
+        data: Self::Serialized,
+        format: GaalHashAlgorithm,
+    ) -> Result<Self, ObjectError> {
         let deserialized =
-            <GaalObject<String> as GaalObjectAction<GCDA>>::deserialize_data(data).unwrap();
-        Self {
+            <GaalObject<Kvlm> as GaalObjectAction<GCDA>>::deserialize_data(data, format)?;
+        Ok(Self {
             fmt: fmt.to_string(),
-            data: deserialized.into(),
-        }
+            data: deserialized,
+        })
     }
 
     fn from_data(fmt: &str, data: Self::Data) -> Self {
@@ -125,8 +193,11 @@ where
         Ok(self.data.clone())
     }
 
-    fn deserialize_data(data: Self::Serialized) -> Result<Self::Data, ObjectError> {
-        Ok(data.into())
+    fn deserialize_data(
+        data: Self::Serialized,
+        _format: GaalHashAlgorithm,
+    ) -> Result<Self::Data, ObjectError> {
+        Kvlm::try_from(data)
     }
 }
 
@@ -144,12 +215,12 @@ mod test_kvlm_from_string {
             This is the message \n\
             body";
         let kvlm = super::Kvlm::from(data.to_string());
-        assert_eq!(kvlm.fields["Subject"], "Hello");
-        assert_eq!(kvlm.fields["From"], "Alice");
-        assert_eq!(kvlm.fields["To"], "Bob");
+        assert_eq!(kvlm.get("Subject"), Some("Hello"));
+        assert_eq!(kvlm.get("From"), Some("Alice"));
+        assert_eq!(kvlm.get("To"), Some("Bob"));
         assert_eq!(
-            kvlm.fields["MultilineField"],
-            "This is a\nmult\\niline\nmessage"
+            kvlm.get("MultilineField"),
+            Some("This is a\nmult\\niline\nmessage")
         );
         assert_eq!(kvlm.message, "This is the message \nbody");
     }
@@ -175,4 +246,33 @@ mod test_kvlm_from_string {
         let result: String = kvlm.into();
         assert_eq!(result, data);
     }
+    #[test]
+    fn kvlm_repeated_keys() {
+        let data = "tree abc\n\
+            parent one\n\
+            author Alice\n\
+            parent two\n\
+            \n\
+            Merge\n\n";
+        let mut kvlm = super::Kvlm::from(data.to_string());
+        assert_eq!(kvlm.get("parent"), Some("one"));
+        assert_eq!(kvlm.get_all("parent"), vec!["one", "two"]);
+        assert_eq!(kvlm.message, "Merge\n\n");
+        assert_eq!(String::from(kvlm.clone()), data);
+
+        kvlm.set("parent", "three");
+        assert_eq!(kvlm.get_all("parent"), vec!["three"]);
+        assert_eq!(kvlm.fields[1], ("parent".to_string(), "three".to_string()));
+        assert_eq!(kvlm.remove("parent"), vec!["three"]);
+        assert_eq!(kvlm.get("parent"), None);
+    }
+    #[test]
+    fn kvlm_from_non_utf8_bytes() {
+        let data = b"tree abc\nencoding ISO-8859-1\n\nCaf\xe9\n".to_vec();
+        assert!(super::Kvlm::try_from(data).is_err());
+
+        let data = b"tree abc\n\nCaf\xc3\xa9\n".to_vec();
+        let kvlm = super::Kvlm::try_from(data.clone()).unwrap();
+        assert_eq!(Vec::<u8>::from(kvlm), data);
+    }
 }
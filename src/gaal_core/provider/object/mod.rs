@@ -1,7 +1,11 @@
 use thiserror::Error;
 
+pub mod commit;
 pub mod default;
+pub mod hash;
+pub mod header;
 pub mod kvlm;
+pub mod tag;
 pub mod tree;
 
 #[derive(Error, Debug)]
@@ -12,4 +16,14 @@ pub enum ObjectError {
     IoError(#[from] std::io::Error),
     #[error("Invalid data: {0}")]
     InvalidData(String),
+    #[error("Unknown object type: `{0}`")]
+    UnknownType(String),
+    #[error("Object length mismatch: header declares {expected} bytes, found {actual}")]
+    LengthMismatch { expected: usize, actual: usize },
+    #[error("Truncated object header: {0}")]
+    TruncatedHeader(String),
+    #[error("Corrupt zlib stream: {0}")]
+    CorruptZlib(String),
+    #[error("Corrupt object: expected hash {expected}, found {actual}")]
+    HashMismatch { expected: String, actual: String },
 }
@@ -0,0 +1,142 @@
+use super::{
+    commit::{is_object_id, Signature},
+    kvlm::Kvlm,
+    ObjectError,
+};
+
+/// An annotated tag: a named, signed pointer to another object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    /// Id of the tagged object.
+    pub object: String,
+    /// Type of the tagged object (`commit`, `tree`, `blob` or `tag`).
+    pub kind: String,
+    /// Name of the tag, without `refs/tags/`.
+    pub tag: String,
+    pub tagger: Option<Signature>,
+    /// Headers the model doesn't know about, kept so the tag round-trips.
+    pub extra: Vec<(String, String)>,
+    pub message: String,
+}
+
+impl Tag {
+    pub fn new(object: &str, kind: &str, tag: &str, tagger: Signature, message: &str) -> Self {
+        Self {
+            object: object.to_string(),
+            kind: kind.to_string(),
+            tag: tag.to_string(),
+            tagger: Some(tagger),
+            extra: Vec::new(),
+            message: message.to_string(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), ObjectError> {
+        if !is_object_id(&self.object) {
+            return Err(ObjectError::InvalidData(format!(
+                "Invalid object id `{}` in tag",
+                self.object
+            )));
+        }
+        if !["commit", "tree", "blob", "tag"].contains(&self.kind.as_str()) {
+            return Err(ObjectError::UnknownType(self.kind.clone()));
+        }
+        if self.tag.is_empty() {
+            return Err(ObjectError::InvalidData("Tag without name".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<Kvlm> for Tag {
+    type Error = ObjectError;
+
+    fn try_from(mut kvlm: Kvlm) -> Result<Self, Self::Error> {
+        let mut take = |key: &str| {
+            kvlm.remove(key)
+                .into_iter()
+                .next()
+                .ok_or_else(|| ObjectError::InvalidData(format!("Tag without `{}`", key)))
+        };
+
+        let object = take("object")?;
+        let kind = take("type")?;
+        let tag = take("tag")?;
+        let tagger = match take("tagger") {
+            Ok(tagger) => Some(Signature::parse(&tagger)?),
+            Err(_) => None,
+        };
+
+        let tag = Self {
+            object,
+            kind,
+            tag,
+            tagger,
+            extra: kvlm.fields,
+            message: kvlm.message,
+        };
+        tag.validate()?;
+        Ok(tag)
+    }
+}
+
+impl TryFrom<Tag> for Kvlm {
+    type Error = ObjectError;
+
+    fn try_from(tag: Tag) -> Result<Self, Self::Error> {
+        tag.validate()?;
+
+        let mut kvlm = Kvlm::default();
+        kvlm.push("object", &tag.object);
+        kvlm.push("type", &tag.kind);
+        kvlm.push("tag", &tag.tag);
+        if let Some(tagger) = &tag.tagger {
+            kvlm.push("tagger", &tagger.to_string());
+        }
+        kvlm.fields.extend(tag.extra);
+        kvlm.message = tag.message;
+        Ok(kvlm)
+    }
+}
+
+#[cfg(test)]
+mod test_tag {
+    use super::Tag;
+    use crate::gaal_core::provider::object::{commit::Signature, kvlm::Kvlm};
+
+    const OBJECT: &str = "206941306e8a8af65b66eaaaea388a7ae24d49a0";
+
+    #[test]
+    fn tag_round_trip() {
+        let data = format!(
+            "object {}\n\
+            type commit\n\
+            tag v1.0\n\
+            tagger Alice <alice@example.com> 1700000000 +0100\n\
+            \n\
+            First release\n",
+            OBJECT
+        );
+        let tag = Tag::try_from(Kvlm::from(data.clone())).unwrap();
+        assert_eq!(tag.object, OBJECT);
+        assert_eq!(tag.kind, "commit");
+        assert_eq!(tag.tag, "v1.0");
+        assert_eq!(
+            tag.tagger,
+            Some(Signature::new("Alice", "alice@example.com", 1700000000, 60))
+        );
+        assert_eq!(tag.message, "First release\n");
+
+        let kvlm = Kvlm::try_from(tag).unwrap();
+        assert_eq!(String::from(kvlm), data);
+    }
+
+    #[test]
+    fn tag_invalid() {
+        let missing_type = format!("object {}\ntag v1.0\n\nmsg", OBJECT);
+        assert!(Tag::try_from(Kvlm::from(missing_type)).is_err());
+
+        let bad_type = format!("object {}\ntype thing\ntag v1.0\n\nmsg", OBJECT);
+        assert!(Tag::try_from(Kvlm::from(bad_type)).is_err());
+    }
+}
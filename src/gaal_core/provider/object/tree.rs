@@ -2,123 +2,168 @@ use crate::gaal_core::provider::directory::GaalCoreDirectoryActions;
 
 use super::{
     default::{GaalObject, GaalObjectAction},
+    hash::GaalHashAlgorithm,
     ObjectError,
 };
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct TreeLeaf {
     pub mode: String,
     pub path: String,
     pub sha: String,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Tree {
     pub leafs: Vec<TreeLeaf>,
 }
 
-fn tree_parse_one(raw: String, start: usize) -> (TreeLeaf, usize) {
-    let mut mode = String::new();
-    let mut path = String::new();
+// A tree is a sequence of entries, each made of an octal mode, a path and the raw
+// digest of the object it points to:
+// ```
+// <mode> <path>\0<digest>
+// ```
+// Modes are stored without leading zeros (`40000` for subtrees) and padded back
+// to six digits when parsed. The digest size depends on the repository's hash
+// algorithm, so parsing needs to know it.
+
+impl TreeLeaf {
+    pub fn is_tree(&self) -> bool {
+        self.mode.trim_start_matches('0') == "40000"
+    }
 
-    let mut i = start;
-    while i < raw.len() && raw.as_bytes()[i] != b' ' {
-        mode.push(raw.as_bytes()[i] as char);
-        i += 1;
+    /// Mode as a number, e.g. `0o100644`.
+    pub fn mode_value(&self) -> Result<u32, ObjectError> {
+        u32::from_str_radix(&self.mode, 8).map_err(|_| {
+            ObjectError::InvalidData(format!("Invalid mode `{}` for `{}`", self.mode, self.path))
+        })
     }
-    if mode.len() == 5 {
-        mode = format!("0{}", mode);
+}
+
+/// Whether `name` can be stored in a tree: a single path component that is not
+/// `.`, `..` or the repository directory, so checking it out stays inside the
+/// working directory.
+pub fn is_valid_entry_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.eq_ignore_ascii_case(".gal")
+        && !name.contains(['/', '\0'])
+}
+
+fn tree_parse_one(
+    raw: &[u8],
+    start: usize,
+    digest_len: usize,
+) -> Result<(TreeLeaf, usize), ObjectError> {
+    let mode_end = raw[start..]
+        .iter()
+        .position(|b| *b == b' ')
+        .map(|i| start + i)
+        .ok_or_else(|| ObjectError::InvalidData("Tree entry without mode".to_string()))?;
+    let mode = &raw[start..mode_end];
+    if mode.is_empty() || !mode.iter().all(|b| (b'0'..=b'7').contains(b)) {
+        return Err(ObjectError::InvalidData(format!(
+            "Invalid tree entry mode `{}`",
+            String::from_utf8_lossy(mode)
+        )));
     }
-    i += 1; // skip space
+    let mode = format!("{:0>6}", String::from_utf8_lossy(mode));
 
-    while i < raw.len() && raw.as_bytes()[i] != 0x00 {
-        path.push(raw.as_bytes()[i] as char);
-        i += 1;
+    let path_end = raw[mode_end + 1..]
+        .iter()
+        .position(|b| *b == 0x00)
+        .map(|i| mode_end + 1 + i)
+        .ok_or_else(|| ObjectError::InvalidData("Tree entry without path".to_string()))?;
+    let path = String::from_utf8_lossy(&raw[mode_end + 1..path_end]).to_string();
+    if !is_valid_entry_name(&path) {
+        return Err(ObjectError::InvalidData(format!(
+            "Invalid tree entry path `{}`",
+            path
+        )));
     }
-    i += 1; // skip null terminator
 
-    let sha_bytes = &raw.to_string()[i..i + 20];
-    if sha_bytes.len() != 20 {
-        panic!("Invalid SHA length");
+    let sha_end = path_end + 1 + digest_len;
+    if sha_end > raw.len() {
+        return Err(ObjectError::InvalidData(format!(
+            "Truncated digest for tree entry `{}`",
+            path
+        )));
     }
-    let raw_sha = sha_bytes
-        .as_bytes()
-        .iter()
-        .fold(0u128, |acc, &b| acc << 8 | b as u128);
-    let sha = format!("{:040x}", raw_sha);
+    let sha = hex::encode(&raw[path_end + 1..sha_end]);
 
-    (TreeLeaf { mode, path, sha }, i)
+    Ok((TreeLeaf { mode, path, sha }, sha_end))
 }
 
-fn tree_parse(raw: String) -> Vec<TreeLeaf> {
+fn tree_parse(raw: &[u8], digest_len: usize) -> Result<Vec<TreeLeaf>, ObjectError> {
     let mut leafs = Vec::new();
     let mut i = 0;
     while i < raw.len() {
-        let (leaf, next_i) = tree_parse_one(raw.clone(), i);
+        let (leaf, next_i) = tree_parse_one(raw, i, digest_len)?;
         leafs.push(leaf);
         i = next_i;
     }
-    leafs
+    Ok(leafs)
 }
 
 fn tree_leaf_sort_key(leaf: &TreeLeaf) -> String {
-    if leaf.mode.starts_with("10") {
+    if !leaf.is_tree() {
         return leaf.path.clone();
     };
     format!("{}/", leaf.path)
 }
 
-fn tree_serialize(tree: &mut Tree) -> Result<String, ObjectError> {
+fn tree_serialize(tree: &mut Tree) -> Result<Vec<u8>, ObjectError> {
     tree.leafs.sort_by_key(tree_leaf_sort_key);
-    let mut ret = String::new();
+    let mut ret = Vec::new();
+    let mut digest_len = None;
 
     for item in &tree.leafs {
-        ret.push_str(&item.mode);
-        ret.push(' ');
+        if !is_valid_entry_name(&item.path) {
+            return Err(ObjectError::InvalidData(format!(
+                "Invalid tree entry path `{}`",
+                item.path
+            )));
+        }
 
-        ret.push_str(&item.path);
-        ret.push(0x00 as char);
+        ret.extend_from_slice(item.mode.trim_start_matches('0').as_bytes());
+        ret.push(b' ');
 
-        let sha_bytes_vec = hex::decode(&item.sha)
+        ret.extend_from_slice(item.path.as_bytes());
+        ret.push(0x00);
+
+        let sha_bytes = hex::decode(&item.sha)
             .map_err(|_| ObjectError::InvalidData("Invalid SHA".to_string()))?;
-        if sha_bytes_vec.len() != 20 {
-            return Err(ObjectError::InvalidData("SHA length is not 20".to_string()));
+        let supported = [GaalHashAlgorithm::Sha1, GaalHashAlgorithm::Sha256]
+            .iter()
+            .any(|format| format.digest_len() == sha_bytes.len());
+        if !supported || *digest_len.get_or_insert(sha_bytes.len()) != sha_bytes.len() {
+            return Err(ObjectError::InvalidData(format!(
+                "Unexpected SHA length {} for `{}`",
+                sha_bytes.len(),
+                item.path
+            )));
         }
-
-        let sha_bytes: String = sha_bytes_vec.iter().fold(String::new(), |acc, &b| {
-            let mut hex_str = String::new();
-            hex_str.push_str(&format!("{:02x}", b));
-            acc + &hex_str
-        });
-        ret.push_str(&sha_bytes);
+        ret.extend_from_slice(&sha_bytes);
     }
 
     Ok(ret)
 }
 
-impl From<String> for Tree {
-    fn from(data: String) -> Self {
-        let mut tree = Tree::default();
-
-        if data.is_empty() {
-            return tree;
-        }
-        let leafs = tree_parse(data);
-        tree.leafs = leafs;
-        tree
+impl Tree {
+    pub fn parse(data: &[u8], format: GaalHashAlgorithm) -> Result<Self, ObjectError> {
+        Ok(Tree {
+            leafs: tree_parse(data, format.digest_len())?,
+        })
     }
 }
 
-impl From<Tree> for String {
-    fn from(tree: Tree) -> Self {
-        let mut tree = tree.clone();
-        let result = tree_serialize(&mut tree);
-        match result {
-            Ok(data) => data,
-            Err(e) => {
-                panic!("Failed to serialize tree: {}", e);
-            }
-        }
+impl TryFrom<Tree> for Vec<u8> {
+    type Error = ObjectError;
+
+    fn try_from(tree: Tree) -> Result<Self, Self::Error> {
+        let mut tree = tree;
+        tree_serialize(&mut tree)
     }
 }
 
@@ -127,7 +172,7 @@ where
     GCDA: GaalCoreDirectoryActions,
 {
     type Data = Tree;
-    type Serialized = String;
+    type Serialized = Vec<u8>;
 
     fn new(fmt: String) -> Self {
         Self {
@@ -137,16 +182,20 @@ where
     }
 
     fn fmt(&self) -> Result<Self::Serialized, ObjectError> {
-        Ok(self.fmt.clone())
+        Ok(self.fmt.clone().into_bytes())
     }
 
-    fn from_serialized(fmt: &str, data: Self::Serialized) -> Self {
+    fn from_serialized(
+        fmt: &str,
+        data: Self::Serialized,
+        format: GaalHashAlgorithm,
+    ) -> Result<Self, ObjectError> {
         let deserialized =
-            <GaalObject<String> as GaalObjectAction<GCDA>>::deserialize_data(data).unwrap();
-        Self {
+            <GaalObject<Tree> as GaalObjectAction<GCDA>>::deserialize_data(data, format)?;
+        Ok(Self {
             fmt: fmt.to_string(),
-            data: deserialized.into(),
-        }
+            data: deserialized,
+        })
     }
 
     fn from_data(fmt: &str, data: Self::Data) -> Self {
@@ -157,24 +206,32 @@ where
     }
 
     fn serialize(&self) -> Result<Self::Serialized, ObjectError> {
-        Ok(self.data.clone().into())
+        self.data.clone().try_into()
     }
 
     fn serialize_data(data: Self::Data) -> Result<Self::Serialized, ObjectError> {
-        Ok(data.into())
+        data.try_into()
     }
 
     fn deserialize(&self) -> Result<Self::Data, ObjectError> {
         Ok(self.data.clone())
     }
 
-    fn deserialize_data(data: Self::Serialized) -> Result<Self::Data, ObjectError> {
-        Ok(data.into())
+    fn deserialize_data(
+        data: Self::Serialized,
+        format: GaalHashAlgorithm,
+    ) -> Result<Self::Data, ObjectError> {
+        Tree::parse(&data, format)
     }
 }
 
 #[cfg(test)]
 mod test_tree_from_string {
+    use proptest::prelude::*;
+
+    use crate::gaal_core::provider::object::hash::GaalHashAlgorithm;
+
+    const SHA: &str = "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
 
     #[test]
     fn tree_from_string() {
@@ -182,34 +239,103 @@ mod test_tree_from_string {
             leafs: vec![super::TreeLeaf {
                 path: "README.md".to_string(),
                 mode: "100644".to_string(),
-                sha: "1234567890abcdef1234567890abcdef12345678".to_string(),
+                sha: SHA.to_string(),
             }],
         };
 
-        let serialized: String = tree.into();
-        let result = super::Tree::from(serialized);
+        let serialized: Vec<u8> = tree.try_into().unwrap();
+        let result = super::Tree::parse(&serialized, GaalHashAlgorithm::Sha256).unwrap();
 
         assert_eq!(result.leafs[0].mode, "100644");
         assert_eq!(result.leafs[0].path, "README.md");
-        assert_eq!(
-            result.leafs[0].sha,
-            "1234567890abcdef1234567890abcdef12345678"
-        );
+        assert_eq!(result.leafs[0].sha, SHA);
     }
 
     #[test]
     fn tree_from_string_empty() {
-        let data = "";
-        let tree = super::Tree::from(data.to_string());
+        let data = Vec::new();
+        let tree = super::Tree::parse(&data, GaalHashAlgorithm::Sha256).unwrap();
         assert_eq!(tree.leafs.len(), 0);
     }
 
     #[test]
     fn tree_into_string() {
-        let serialized = "100644 README.md1234567890abcdef1234567890abcdef12345678";
-        let tree = super::Tree::from(serialized.to_string());
-        let serialized: String = tree.clone().into();
-        println!("Serialized: {}", serialized);
+        let mut serialized = b"100644 README.md\x00".to_vec();
+        serialized.extend_from_slice(&hex::decode(SHA).unwrap());
+        serialized.extend_from_slice(b"40000 docs\x00");
+        serialized.extend_from_slice(&hex::decode(SHA).unwrap());
+
+        let tree = super::Tree::parse(&serialized, GaalHashAlgorithm::Sha256).unwrap();
         assert_eq!(tree.leafs[0].mode, "100644");
+        assert_eq!(tree.leafs[1].mode, "040000");
+
+        let result: Vec<u8> = tree.try_into().unwrap();
+        assert_eq!(result, serialized);
+    }
+
+    #[test]
+    fn tree_invalid_entry_names() {
+        for name in ["", ".", "..", ".gal", ".GAL"] {
+            let mut serialized = format!("40000 {}\x00", name).into_bytes();
+            serialized.extend_from_slice(&hex::decode(SHA).unwrap());
+            assert!(super::Tree::parse(&serialized, GaalHashAlgorithm::Sha256).is_err());
+
+            let tree = super::Tree {
+                leafs: vec![super::TreeLeaf {
+                    path: name.to_string(),
+                    mode: "040000".to_string(),
+                    sha: SHA.to_string(),
+                }],
+            };
+            assert!(Vec::<u8>::try_from(tree).is_err());
+        }
+        assert!(super::is_valid_entry_name(".galignore"));
+    }
+
+    #[test]
+    fn tree_from_string_truncated() {
+        let mut serialized = b"100644 README.md\x00".to_vec();
+        serialized.extend_from_slice(&hex::decode(SHA).unwrap()[..20]);
+        assert!(super::Tree::parse(&serialized, GaalHashAlgorithm::Sha256).is_err());
+        assert!(super::Tree::parse(&serialized, GaalHashAlgorithm::Sha1).is_ok());
+    }
+
+    fn tree_leaf(format: GaalHashAlgorithm) -> impl Strategy<Value = super::TreeLeaf> {
+        (
+            prop::sample::select(vec!["100644", "100755", "120000", "160000", "040000"]),
+            "[^/\\x00]{1,16}"
+                .prop_filter("valid entry name", |path| super::is_valid_entry_name(path)),
+            prop::collection::vec(any::<u8>(), format.digest_len()),
+        )
+            .prop_map(|(mode, path, sha)| super::TreeLeaf {
+                mode: mode.to_string(),
+                path,
+                sha: hex::encode(sha),
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn tree_round_trip(
+            (format, leafs) in prop::sample::select(vec![
+                GaalHashAlgorithm::Sha1,
+                GaalHashAlgorithm::Sha256,
+            ])
+            .prop_flat_map(|format| {
+                (Just(format), prop::collection::vec(tree_leaf(format), 0..16))
+            })
+        ) {
+            let mut leafs = leafs;
+            leafs.sort_by_key(super::tree_leaf_sort_key);
+            leafs.dedup_by(|a, b| a.path == b.path);
+            let tree = super::Tree { leafs };
+
+            let serialized: Vec<u8> = tree.clone().try_into().unwrap();
+            let parsed = super::Tree::parse(&serialized, format).unwrap();
+            prop_assert_eq!(&parsed, &tree);
+
+            let reserialized: Vec<u8> = parsed.try_into().unwrap();
+            prop_assert_eq!(reserialized, serialized);
+        }
     }
 }
@@ -0,0 +1 @@
+pub mod gaal_core;
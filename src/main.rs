@@ -1,21 +1,45 @@
-mod gaal_core;
-
+use clap::{Args, Parser, Subcommand};
 use flate2::Compression;
 use flate2::{read::ZlibDecoder, write::ZlibEncoder};
-use gaal_core::provider::object::kvlm::Kvlm;
-use gaal_core::provider::object::tree::Tree;
+use gaal::gaal_core::actions::{
+    self,
+    cherry_pick::PickAction,
+    diff::{DiffFormat, PatchOptions},
+    log::LogFormat,
+    rebase::RebaseAction,
+    stash::StashAction,
+    Error,
+};
+use gaal::gaal_core::internals::date::parse_date_filter;
+use gaal::gaal_core::internals::repository::default::GaalRepository;
+use gaal::gaal_core::internals::repository::diff::DiffOptions;
+use gaal::gaal_core::internals::repository::merge::MergeOptions;
+use gaal::gaal_core::internals::repository::rebase::{clean_message, RebaseHooks, RebaseOptions};
+use gaal::gaal_core::internals::repository::sequencer::PickOptions;
+use gaal::gaal_core::internals::repository::walk::{WalkOptions, WalkOrder};
+use gaal::gaal_core::internals::repository::RepositoryError;
+use gaal::gaal_core::internals::text_diff::DiffAlgorithm;
+use gaal::gaal_core::internals::text_merge::ConflictStyle;
+use gaal::gaal_core::provider::object::hash::GaalHashAlgorithm;
+use gaal::gaal_core::provider::object::kvlm::Kvlm;
+use gaal::gaal_core::provider::object::tree::Tree;
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
-use std::path::Path;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
 
-use gaal_core::{
+use gaal::gaal_core::{
     core::{GaalCore, GaalCoreTrait},
     provider::{
-        directory::{GaalCoreDirectory, GaalCoreDirectoryActions, GaalCoreDirectoryActionsType},
+        directory::{
+            GaalCoreDirectory, GaalCoreDirectoryActions, GaalCoreDirectoryActionsType,
+            GaalEntryMetadata, GaalTimestamp,
+        },
         directory_config::{
             GaalDirectoryConfig, GaalDirectoryConfigSection, GaalDirectoryConfigSectionItem,
         },
         directory_object::{GaalCoreDirectoryObjectsActions, GaalCoreDirectoryObjectsActionsType},
+        index::{MODE_EXECUTABLE, MODE_FILE, MODE_SYMLINK, MODE_TREE},
         object::default::GaalObject,
     },
 };
@@ -23,21 +47,9 @@ use gaal_core::{
 #[derive(Clone, Debug)]
 pub struct GaalCoreDirectoryInit;
 
-#[derive(Clone, Default)]
-pub struct X {
-    pub x: i32,
-    pub y: i32,
-}
-
-impl X {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
-    }
-}
-
 impl GaalCoreDirectoryActionsType for GaalCoreDirectoryInit {
     type PathItem = String;
-    type Data = String;
+    type Data = Vec<u8>;
     type ConfigSection = GaalDirectoryConfigSection<GaalDirectoryConfigSectionItem<String>>;
     type Config = GaalDirectoryConfig<Self::ConfigSection>;
 
@@ -60,6 +72,15 @@ impl GaalCoreDirectoryActionsType for GaalCoreDirectoryInit {
         Path::new(&path.join("/")).is_file()
     }
 
+    fn list_path(path: Vec<Self::PathItem>) -> Result<Vec<Self::PathItem>, std::io::Error> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(Path::new(&path.join("/")))? {
+            entries.push(entry?.file_name().to_string_lossy().to_string());
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
     fn get_path() -> Vec<Self::PathItem> {
         todo!("Implement get_path")
     }
@@ -69,12 +90,14 @@ impl GaalCoreDirectoryActionsType for GaalCoreDirectoryInit {
         if uncompress {
             let b = BufReader::new(file);
             let mut decoder = ZlibDecoder::new(b);
-            let mut contents = String::new();
-            decoder.read_to_string(&mut contents)?;
+            let mut contents = Vec::new();
+            decoder
+                .read_to_end(&mut contents)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
             return Ok(contents);
         }
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
 
         Ok(contents)
     }
@@ -86,12 +109,68 @@ impl GaalCoreDirectoryActionsType for GaalCoreDirectoryInit {
         let mut file = File::create(Path::new(&path.join("/")))?;
         if compressed {
             let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
-            encoder.write_all(data.as_bytes())?;
+            encoder.write_all(&data)?;
             let compressed_data = encoder.finish()?;
             return file.write_all(&compressed_data);
         }
 
-        file.write_all(data.as_bytes())
+        file.write_all(&data)
+    }
+    fn create_data(path: Vec<Self::PathItem>, data: Self::Data) -> Result<(), std::io::Error> {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(Path::new(&path.join("/")))?;
+        file.write_all(&data)
+    }
+    fn rename_entry(
+        from: Vec<Self::PathItem>,
+        to: Vec<Self::PathItem>,
+    ) -> Result<(), std::io::Error> {
+        std::fs::rename(Path::new(&from.join("/")), Path::new(&to.join("/")))
+    }
+    fn remove_entry(path: Vec<Self::PathItem>) -> Result<(), std::io::Error> {
+        std::fs::remove_file(Path::new(&path.join("/")))
+    }
+    fn metadata(path: Vec<Self::PathItem>) -> Result<GaalEntryMetadata, std::io::Error> {
+        let metadata = std::fs::symlink_metadata(Path::new(&path.join("/")))?;
+        let mode = if metadata.file_type().is_symlink() {
+            MODE_SYMLINK
+        } else if metadata.is_dir() {
+            MODE_TREE
+        } else if metadata.mode() & 0o111 != 0 {
+            MODE_EXECUTABLE
+        } else {
+            MODE_FILE
+        };
+        Ok(GaalEntryMetadata {
+            mode,
+            size: metadata.len(),
+            mtime: GaalTimestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32),
+            ctime: GaalTimestamp::new(metadata.ctime(), metadata.ctime_nsec() as u32),
+        })
+    }
+    fn read_link(path: Vec<Self::PathItem>) -> Result<String, std::io::Error> {
+        Ok(std::fs::read_link(Path::new(&path.join("/")))?
+            .to_string_lossy()
+            .to_string())
+    }
+    fn make_link(path: Vec<Self::PathItem>, target: &str) -> Result<(), std::io::Error> {
+        std::os::unix::fs::symlink(target, Path::new(&path.join("/")))
+    }
+    fn set_executable(path: Vec<Self::PathItem>, executable: bool) -> Result<(), std::io::Error> {
+        let path = path.join("/");
+        let mut permissions = std::fs::metadata(Path::new(&path))?.permissions();
+        let mode = match executable {
+            // Only grant execution where reading is allowed, like git does.
+            true => permissions.mode() | ((permissions.mode() & 0o444) >> 2),
+            false => permissions.mode() & !0o111,
+        };
+        permissions.set_mode(mode);
+        std::fs::set_permissions(Path::new(&path), permissions)
+    }
+    fn remove_path(path: Vec<Self::PathItem>) -> Result<(), std::io::Error> {
+        std::fs::remove_dir(Path::new(&path.join("/")))
     }
     fn is_config(path: Vec<Self::PathItem>) -> bool {
         let path = path.join("/") + ".json";
@@ -126,9 +205,9 @@ impl GaalCoreDirectoryActions for GaalCoreDirectoryInit {}
 pub struct GaalCoreDirectoryObjectInit;
 
 impl GaalCoreDirectoryObjectsActionsType<GaalCoreDirectoryInit> for GaalCoreDirectoryObjectInit {
-    type GaalBlob = GaalObject<String>;
+    type GaalBlob = GaalObject<Vec<u8>>;
     type GaalCommit = GaalObject<Kvlm>;
-    type GaalTag = GaalObject<String>;
+    type GaalTag = GaalObject<Kvlm>;
     type GaalTree = GaalObject<Tree>;
 }
 
@@ -137,46 +216,792 @@ impl GaalCoreDirectoryObjectsActions<GaalCoreDirectoryInit> for GaalCoreDirector
 pub type GaalCoreDirectoryBuild =
     GaalCoreDirectory<GaalCoreDirectoryInit, GaalCoreDirectoryObjectInit>;
 
+pub type GaalRepositoryBuild<'a> =
+    GaalRepository<'a, GaalCoreDirectoryInit, GaalCoreDirectoryObjectInit>;
+
+#[derive(Parser)]
+#[command(name = "gaal", version, about = "A content tracker")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// How `diff` and `show` compare and print files.
+#[derive(Args)]
+struct DiffArgs {
+    /// Show a histogram of the changed lines of each file
+    #[arg(long, conflicts_with_all = ["numstat", "name_status"])]
+    stat: bool,
+    /// Show the numbers of added and deleted lines of each file
+    #[arg(long, conflicts_with = "name_status")]
+    numstat: bool,
+    /// Show the status and path of each changed file
+    #[arg(long)]
+    name_status: bool,
+    /// Show this many lines of context around changes
+    #[arg(short = 'U', long, value_name = "LINES", default_value_t = 3)]
+    unified: usize,
+    /// Line diff algorithm, `myers` or `patience`
+    #[arg(long, value_name = "ALGORITHM", default_value = "myers")]
+    diff_algorithm: DiffAlgorithm,
+    /// Detect renames, optionally with a minimum similarity in percent
+    #[arg(short = 'M', long, value_name = "PERCENT", num_args = 0..=1, default_missing_value = "50")]
+    find_renames: Option<u8>,
+    /// Detect copies as well as renames
+    #[arg(short = 'C', long)]
+    find_copies: bool,
+}
+
+impl DiffArgs {
+    fn options(&self, paths: Vec<String>) -> DiffOptions {
+        DiffOptions {
+            renames: self.find_renames.is_some() || self.find_copies,
+            copies: self.find_copies,
+            threshold: self
+                .find_renames
+                .unwrap_or(DiffOptions::default().threshold),
+            paths,
+        }
+    }
+
+    fn patch_options(&self) -> PatchOptions {
+        let format = match (self.stat, self.numstat, self.name_status) {
+            (true, _, _) => DiffFormat::Stat,
+            (_, true, _) => DiffFormat::NumStat,
+            (_, _, true) => DiffFormat::NameStatus,
+            _ => DiffFormat::Patch,
+        };
+        PatchOptions {
+            format,
+            context: self.unified,
+            algorithm: self.diff_algorithm,
+        }
+    }
+}
+
+/// How `cherry-pick` and `revert` apply a commit, or conclude the operation in
+/// progress.
+#[derive(Args)]
+struct PickArgs {
+    /// Parent number, from 1, the changes of a merge commit are taken against
+    #[arg(short, long, value_name = "PARENT")]
+    mainline: Option<usize>,
+    /// Conflict markers, `merge` or `diff3`, `merge.conflictstyle` by default
+    #[arg(long = "conflict", value_name = "STYLE")]
+    conflict_style: Option<ConflictStyle>,
+    /// Line diff algorithm, `myers` or `patience`
+    #[arg(long, value_name = "ALGORITHM", default_value = "myers")]
+    diff_algorithm: DiffAlgorithm,
+    /// Record the resolved conflicts of the operation in progress
+    #[arg(long = "continue", conflicts_with_all = ["abort", "commit"])]
+    continue_: bool,
+    /// Go back to the state before the operation in progress
+    #[arg(long, conflicts_with = "commit")]
+    abort: bool,
+    #[arg(required_unless_present_any = ["continue_", "abort"])]
+    commit: Option<String>,
+}
+
+impl PickArgs {
+    fn action(self) -> PickAction {
+        match (self.continue_, self.abort, self.commit) {
+            (true, _, _) => PickAction::Continue,
+            (_, true, _) => PickAction::Abort,
+            (_, _, commit) => PickAction::Start(
+                commit.unwrap_or_default(),
+                PickOptions {
+                    mainline: self.mainline,
+                    style: self.conflict_style,
+                    algorithm: self.diff_algorithm,
+                },
+            ),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum StashCommand {
+    /// Save the local changes and reset to `HEAD`
+    Push {
+        /// Describe the stash
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    /// Apply a stash, then drop it unless it conflicts
+    Pop { stash: Option<String> },
+    /// Apply a stash and keep it
+    Apply { stash: Option<String> },
+    /// List the stashes, the newest first
+    List,
+    /// Remove a stash
+    Drop { stash: Option<String> },
+    /// Show the changes recorded in a stash
+    Show {
+        /// Show a patch instead of a summary
+        #[arg(short, long)]
+        patch: bool,
+        stash: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create an empty repository
+    Init {
+        /// Hash algorithm used to name objects (sha1 or sha256)
+        #[arg(long, value_name = "FORMAT", default_value = "sha256")]
+        object_format: GaalHashAlgorithm,
+        /// Where to create the repository
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    /// Print the content of an object
+    CatFile {
+        /// Expected type (blob, commit, tag or tree)
+        #[arg(value_name = "TYPE")]
+        fmt: String,
+        object: String,
+    },
+    /// Compute an object id, optionally storing the object
+    HashObject {
+        #[arg(
+            short = 't',
+            long = "type",
+            value_name = "TYPE",
+            default_value = "blob"
+        )]
+        fmt: String,
+        /// Write the object into the repository
+        #[arg(short, long)]
+        write: bool,
+        path: String,
+    },
+    /// Verify the integrity of the object database
+    Fsck,
+    /// Show the history leading to a commit
+    Log {
+        /// Show each commit on a single line
+        #[arg(long)]
+        oneline: bool,
+        /// Draw the history as a graph next to the commits
+        #[arg(long)]
+        graph: bool,
+        /// Show children before parents, keeping lines of history together
+        #[arg(long)]
+        topo_order: bool,
+        /// Show the oldest commits first
+        #[arg(long)]
+        reverse: bool,
+        /// Only follow the first parent of merges
+        #[arg(long)]
+        first_parent: bool,
+        /// Show at most this many commits
+        #[arg(short = 'n', long)]
+        max_count: Option<usize>,
+        /// Only show commits made at or after this date, e.g. `2026-10-18`,
+        /// `2026-10-18 13:07 +0200`, `2 weeks ago` or Unix seconds
+        #[arg(long, value_parser = parse_date_arg)]
+        since: Option<i64>,
+        /// Only show commits made at or before this date
+        #[arg(long, value_parser = parse_date_arg)]
+        until: Option<i64>,
+        /// Only show commits whose author contains this text
+        #[arg(long)]
+        author: Option<String>,
+        /// Revisions and ranges such as `A..B` or `A...B`, HEAD by default
+        revisions: Vec<String>,
+        /// Only show commits changing these paths
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    /// List the content of a tree
+    LsTree {
+        /// Recurse into subtrees
+        #[arg(short, long)]
+        recursive: bool,
+        tree: String,
+    },
+    /// List, create, rename or delete branches
+    Branch {
+        /// Delete a branch merged into HEAD
+        #[arg(short, long, conflicts_with_all = ["rename", "set_upstream_to", "unset_upstream"])]
+        delete: bool,
+        /// Delete a branch even if it isn't merged
+        #[arg(short = 'D', conflicts_with_all = ["rename", "set_upstream_to", "unset_upstream"])]
+        force_delete: bool,
+        /// Rename a branch, the current one when only the new name is given
+        #[arg(short = 'm', long = "move", conflicts_with_all = ["set_upstream_to", "unset_upstream"])]
+        rename: bool,
+        /// Move an existing branch, or overwrite the target of a rename
+        #[arg(short, long)]
+        force: bool,
+        /// Make a branch, the current one by default, track UPSTREAM
+        #[arg(
+            short = 'u',
+            long,
+            value_name = "UPSTREAM",
+            conflicts_with = "unset_upstream"
+        )]
+        set_upstream_to: Option<String>,
+        /// Stop a branch, the current one by default, from tracking anything
+        #[arg(long)]
+        unset_upstream: bool,
+        /// Show the commit and upstream of each branch
+        #[arg(short, long)]
+        verbose: bool,
+        name: Option<String>,
+        /// Commit of a new branch, or new name of a renamed one
+        start_point: Option<String>,
+    },
+    /// Show changes between the working directory, the index and trees
+    Diff {
+        /// Compare with the index instead of the working directory
+        #[arg(long, alias = "staged")]
+        cached: bool,
+        #[command(flatten)]
+        format: DiffArgs,
+        /// Trees to compare, or a range `A..B`
+        revisions: Vec<String>,
+        /// Only show changes to these paths
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    /// Show a commit with its changes, a tag, a tree or a blob
+    Show {
+        #[command(flatten)]
+        format: DiffArgs,
+        #[arg(default_value = "HEAD")]
+        object: String,
+        /// Only show changes to these paths
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    /// Join the history of a commit into the current branch
+    Merge {
+        /// Record a merge commit even when the branch could fast-forward
+        #[arg(long, conflicts_with = "ff_only")]
+        no_ff: bool,
+        /// Fail unless the branch can fast-forward
+        #[arg(long)]
+        ff_only: bool,
+        /// Message of the merge commit
+        #[arg(short, long)]
+        message: Option<String>,
+        /// Conflict markers, `merge` or `diff3`, `merge.conflictstyle` by default
+        #[arg(long = "conflict", value_name = "STYLE")]
+        conflict_style: Option<ConflictStyle>,
+        /// Line diff algorithm, `myers` or `patience`
+        #[arg(long, value_name = "ALGORITHM", default_value = "myers")]
+        diff_algorithm: DiffAlgorithm,
+        revision: String,
+    },
+    /// Apply the changes of a commit on top of the current branch
+    CherryPick {
+        #[command(flatten)]
+        args: PickArgs,
+    },
+    /// Record a commit undoing the changes of another one
+    Revert {
+        #[command(flatten)]
+        args: PickArgs,
+    },
+    /// Replay the commits of the current branch onto another base
+    #[command(group(
+        clap::ArgGroup::new("step")
+            .args(["continue_", "skip", "abort", "edit_todo"])
+            .conflicts_with_all(["interactive", "onto", "upstream"])
+    ))]
+    Rebase {
+        /// Edit the list of commits to replay before starting
+        #[arg(short, long)]
+        interactive: bool,
+        /// Replay the commits onto this commit instead of the upstream
+        #[arg(long, value_name = "NEWBASE")]
+        onto: Option<String>,
+        /// Resume after resolving conflicts or amending a commit
+        #[arg(long = "continue")]
+        continue_: bool,
+        /// Drop the commit the rebase stopped at and resume
+        #[arg(long)]
+        skip: bool,
+        /// Go back to the branch as it was before the rebase
+        #[arg(long)]
+        abort: bool,
+        /// Edit the lines left in the todo list
+        #[arg(long)]
+        edit_todo: bool,
+        /// Commits reachable from this revision aren't replayed, the upstream of
+        /// the branch by default
+        upstream: Option<String>,
+    },
+    /// Set local changes aside and bring them back later
+    Stash {
+        #[command(subcommand)]
+        command: Option<StashCommand>,
+    },
+    /// Switch the working directory to a branch, commit or tree
+    Checkout {
+        /// Discard local changes and overwrite untracked files
+        #[arg(short, long)]
+        force: bool,
+        revision: String,
+    },
+    /// List references
+    ShowRef,
+    /// Point a reference at an object, or delete it
+    UpdateRef {
+        /// Delete the reference, the second value is then the expected old value
+        #[arg(short = 'd')]
+        delete: bool,
+        name: String,
+        #[arg(required_unless_present = "delete")]
+        new_value: Option<String>,
+        /// Only update when the reference currently holds this value
+        old_value: Option<String>,
+    },
+    /// List tags or create a new one
+    Tag {
+        /// Create an annotated tag object, its message written in the editor
+        /// unless given with -m
+        #[arg(short = 'a', requires = "name")]
+        annotate: bool,
+        /// Message of the annotated tag, implies -a
+        #[arg(short, long)]
+        message: Option<String>,
+        name: Option<String>,
+        #[arg(default_value = "HEAD")]
+        object: String,
+    },
+    /// Resolve a name to an object id
+    RevParse {
+        /// Peel the object until it has this type
+        #[arg(long = "gaal-type", value_name = "TYPE")]
+        fmt: Option<String>,
+        name: String,
+    },
+    /// List files in the staging area
+    LsFiles {
+        /// Show the mode, object id and stage of each entry
+        #[arg(short, long)]
+        stage: bool,
+    },
+    /// Show the working tree status
+    Status {
+        /// One `XY path` line per changed path
+        #[arg(short, long, visible_alias = "porcelain")]
+        short: bool,
+    },
+    /// Add files to the staging area
+    Add {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Remove files from the staging area and the working tree
+    Rm {
+        /// Only remove the files from the staging area
+        #[arg(long)]
+        cached: bool,
+        /// Remove files even when they have local changes
+        #[arg(short, long)]
+        force: bool,
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Write the staging area as a tree
+    WriteTree {
+        /// Write this directory of the working tree instead of the staging area
+        #[arg(long, value_name = "PATH")]
+        work_dir: Option<String>,
+    },
+    /// Record staged changes
+    Commit {
+        /// Commit message, the one prepared by a conflicted merge by default
+        #[arg(short, long)]
+        message: Option<String>,
+        /// Record a commit even if its tree is the same as its parent's
+        #[arg(long)]
+        allow_empty: bool,
+    },
+}
+
+/// Turns a filesystem path into provider path items, e.g. `/home/gaal` becomes
+/// `["", "home", "gaal"]`.
+fn path_items(path: &Path) -> Result<Vec<String>, Error> {
+    let path = std::path::absolute(path)?;
+    let mut items = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::RootDir => items.push(String::new()),
+            Component::Normal(item) => items.push(item.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                items.pop();
+            }
+            Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    Ok(items)
+}
+
+/// Turns paths given on the command line into paths relative to the working directory.
+fn work_paths(
+    repository: &GaalRepositoryBuild<'_>,
+    paths: &[String],
+) -> Result<Vec<String>, Error> {
+    paths
+        .iter()
+        .map(|path| {
+            let items = path_items(Path::new(path))?;
+            match items.strip_prefix(repository.work_dir.as_slice()) {
+                Some(relative) => Ok(relative.join("/")),
+                None => Err(Error::InvalidArgument(format!(
+                    "`{}` is outside the repository",
+                    path
+                ))),
+            }
+        })
+        .collect()
+}
+
+fn repository(core: &GaalCore<GaalCoreDirectoryBuild>) -> Result<GaalRepositoryBuild<'_>, Error> {
+    let cwd = path_items(&std::env::current_dir()?)?;
+    Ok(core.derive_from_path(cwd)?)
+}
+
+/// Parses the date of `--since` and `--until`, relative dates counting back
+/// from now.
+fn parse_date_arg(date: &str) -> Result<i64, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default();
+    parse_date_filter(date, now).ok_or_else(|| format!("Invalid date `{}`", date))
+}
+
+/// Lets the user change `text` in the editor named by the first of `variables`
+/// that is set, `vi` otherwise.
+fn edit_text(text: &str, variables: &[&str]) -> Result<String, RepositoryError> {
+    let editor = variables
+        .iter()
+        .find_map(|variable| std::env::var(variable).ok())
+        .unwrap_or_else(|| "vi".to_string());
+    let path = std::env::temp_dir().join(format!("gaal-edit-{}", std::process::id()));
+    std::fs::write(&path, text)?;
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(&path)
+        .status();
+    let edited = std::fs::read_to_string(&path);
+    std::fs::remove_file(&path)?;
+    if !status?.success() {
+        return Err(std::io::Error::other(format!("Editor `{}` failed", editor)).into());
+    }
+    Ok(edited?)
+}
+
+fn run(command: Command, out: &mut impl Write) -> Result<(), Error> {
+    let core = GaalCore::new(GaalCoreDirectoryBuild::default());
+
+    match command {
+        Command::Init {
+            object_format,
+            path,
+        } => actions::init::init(&core, path_items(Path::new(&path))?, object_format, out),
+        Command::CatFile { fmt, object } => {
+            actions::cat_file::cat_file(&repository(&core)?, &fmt, &object, out)
+        }
+        Command::HashObject { fmt, write, path } => {
+            let data = std::fs::read(&path)?;
+            let repository = match repository(&core) {
+                Ok(repository) => Some(repository),
+                Err(e) if write => return Err(e),
+                Err(_) => None,
+            };
+            actions::hash_object::hash_object(repository.as_ref(), write, &fmt, data, out)
+        }
+        Command::Fsck => actions::fsck::fsck(&repository(&core)?, out),
+        Command::Log {
+            oneline,
+            graph,
+            topo_order,
+            reverse,
+            first_parent,
+            max_count,
+            since,
+            until,
+            author,
+            revisions,
+            paths,
+        } => {
+            let repository = repository(&core)?;
+            let options = WalkOptions {
+                // Like git, drawing a graph implies keeping lines together.
+                order: match topo_order || graph {
+                    true => WalkOrder::Topological,
+                    false => WalkOrder::Date,
+                },
+                reverse,
+                first_parent,
+                max_count,
+                since,
+                until,
+                author,
+                paths: work_paths(&repository, &paths)?,
+            };
+            let format = match oneline {
+                true => LogFormat::Oneline,
+                false => LogFormat::Full,
+            };
+            let revisions = revisions.iter().map(String::as_str).collect::<Vec<&str>>();
+            actions::log::log(&repository, &revisions, options, format, graph, out)
+        }
+        Command::LsTree { recursive, tree } => {
+            actions::ls_tree::ls_tree(&repository(&core)?, &tree, recursive, out)
+        }
+        Command::Branch {
+            delete,
+            force_delete,
+            rename,
+            force,
+            set_upstream_to,
+            unset_upstream,
+            verbose,
+            name,
+            start_point,
+        } => {
+            let mut repository = repository(&core)?;
+            match (name, start_point) {
+                (name, None) if set_upstream_to.is_some() || unset_upstream => {
+                    actions::branch::branch_upstream(
+                        &mut repository,
+                        name.as_deref(),
+                        set_upstream_to.as_deref(),
+                        out,
+                    )
+                }
+                (Some(name), None) if delete || force_delete => actions::branch::branch_delete(
+                    &mut repository,
+                    &name,
+                    force || force_delete,
+                    out,
+                ),
+                (Some(new), None) if rename => {
+                    actions::branch::branch_rename(&mut repository, None, &new, force)
+                }
+                (Some(old), Some(new)) if rename => {
+                    actions::branch::branch_rename(&mut repository, Some(&old), &new, force)
+                }
+                (Some(name), start) if !delete && !force_delete && !rename => {
+                    actions::branch::branch_create(
+                        &repository,
+                        &name,
+                        start.as_deref().unwrap_or("HEAD"),
+                        force,
+                    )
+                }
+                (None, None) if !delete && !force_delete && !rename => {
+                    actions::branch::branch_list(&repository, verbose, out)
+                }
+                _ => Err(Error::InvalidArgument(
+                    "Unexpected combination of branch arguments".to_string(),
+                )),
+            }
+        }
+        Command::Diff {
+            cached,
+            format,
+            revisions,
+            paths,
+        } => {
+            let repository = repository(&core)?;
+            let options = format.options(work_paths(&repository, &paths)?);
+            let revisions = revisions.iter().map(String::as_str).collect::<Vec<&str>>();
+            let entries = actions::diff::diff_entries(&repository, &revisions, cached, &options)?;
+            actions::diff::diff_write(&repository, &entries, &format.patch_options(), out)
+        }
+        Command::Show {
+            format,
+            object,
+            paths,
+        } => {
+            let repository = repository(&core)?;
+            let options = format.options(work_paths(&repository, &paths)?);
+            actions::show::show(&repository, &object, &options, &format.patch_options(), out)
+        }
+        Command::Merge {
+            no_ff,
+            ff_only,
+            message,
+            conflict_style,
+            diff_algorithm,
+            revision,
+        } => {
+            let options = MergeOptions {
+                no_ff,
+                ff_only,
+                style: conflict_style,
+                algorithm: diff_algorithm,
+                message,
+            };
+            actions::merge::merge(&repository(&core)?, &revision, &options, out)
+        }
+        Command::CherryPick { args } => {
+            actions::cherry_pick::cherry_pick(&repository(&core)?, &args.action(), out)
+        }
+        Command::Revert { args } => {
+            actions::revert::revert(&repository(&core)?, &args.action(), out)
+        }
+        Command::Rebase {
+            interactive,
+            onto,
+            continue_,
+            skip,
+            abort,
+            edit_todo,
+            upstream,
+        } => {
+            let action = match (continue_, skip, abort, edit_todo) {
+                (true, _, _, _) => RebaseAction::Continue,
+                (_, true, _, _) => RebaseAction::Skip,
+                (_, _, true, _) => RebaseAction::Abort,
+                (_, _, _, true) => RebaseAction::EditTodo,
+                _ => RebaseAction::Start(upstream, RebaseOptions { onto, interactive }),
+            };
+            let repository = repository(&core)?;
+            let work_dir = PathBuf::from(repository.work_dir.join("/"));
+            let hooks = RebaseHooks {
+                edit_todo: Box::new(|text| {
+                    edit_text(
+                        text,
+                        &["GAAL_SEQUENCE_EDITOR", "GAAL_EDITOR", "VISUAL", "EDITOR"],
+                    )
+                }),
+                edit_message: Box::new(|text| {
+                    edit_text(text, &["GAAL_EDITOR", "VISUAL", "EDITOR"])
+                }),
+                exec: Box::new(|command| {
+                    Ok(std::process::Command::new("sh")
+                        .arg("-c")
+                        .arg(command)
+                        .current_dir(&work_dir)
+                        .status()?
+                        .success())
+                }),
+            };
+            actions::rebase::rebase(&repository, &action, hooks, out)
+        }
+        Command::Stash { command } => {
+            let action = match command.unwrap_or(StashCommand::Push { message: None }) {
+                StashCommand::Push { message } => StashAction::Push(message),
+                StashCommand::Pop { stash } => StashAction::Pop(stash),
+                StashCommand::Apply { stash } => StashAction::Apply(stash),
+                StashCommand::List => StashAction::List,
+                StashCommand::Drop { stash } => StashAction::Drop(stash),
+                StashCommand::Show { patch, stash } => {
+                    let format = match patch {
+                        true => DiffFormat::Patch,
+                        false => DiffFormat::Stat,
+                    };
+                    let options = PatchOptions {
+                        format,
+                        ..Default::default()
+                    };
+                    StashAction::Show(stash, options)
+                }
+            };
+            actions::stash::stash(&repository(&core)?, &action, out)
+        }
+        Command::Checkout { force, revision } => {
+            actions::checkout::checkout(&repository(&core)?, &revision, force, out)
+        }
+        Command::ShowRef => actions::show_ref::show_ref(&repository(&core)?, out),
+        Command::UpdateRef {
+            delete,
+            name,
+            new_value,
+            old_value,
+        } => {
+            let (new_value, old_value) = match delete {
+                true => (None, new_value),
+                false => (new_value, old_value),
+            };
+            actions::update_ref::update_ref(
+                &repository(&core)?,
+                &name,
+                new_value.as_deref(),
+                old_value.as_deref(),
+            )
+        }
+        Command::Tag {
+            annotate,
+            message,
+            name,
+            object,
+        } => {
+            let message = match (annotate, message, &name) {
+                (true, None, Some(name)) => {
+                    let template = format!(
+                        "\n#\n# Write a message for tag:\n#   {}\n# Lines starting with '#' will be ignored.\n",
+                        name
+                    );
+                    let message =
+                        clean_message(&edit_text(&template, &["GAAL_EDITOR", "VISUAL", "EDITOR"])?);
+                    if message.is_empty() {
+                        return Err(RepositoryError::EmptyMessage.into());
+                    }
+                    Some(message)
+                }
+                (_, message, _) => message,
+            };
+            actions::tag::tag(
+                &repository(&core)?,
+                name.as_deref(),
+                &object,
+                message.as_deref(),
+                out,
+            )
+        }
+        Command::RevParse { fmt, name } => {
+            actions::rev_parse::rev_parse(&repository(&core)?, &name, fmt.as_deref(), out)
+        }
+        Command::LsFiles { stage } => actions::ls_files::ls_files(&repository(&core)?, stage, out),
+        Command::Add { paths } => {
+            let repository = repository(&core)?;
+            actions::add::add(&repository, &work_paths(&repository, &paths)?)
+        }
+        Command::Rm {
+            cached,
+            force,
+            paths,
+        } => {
+            let repository = repository(&core)?;
+            let paths = work_paths(&repository, &paths)?;
+            actions::rm::rm(&repository, &paths, cached, force, out)
+        }
+        Command::Status { short } => actions::status::status(&repository(&core)?, short, out),
+        Command::WriteTree { work_dir } => {
+            let repository = repository(&core)?;
+            let work_dir = match work_dir {
+                Some(path) => Some(work_paths(&repository, &[path])?.remove(0)),
+                None => None,
+            };
+            actions::write_tree::write_tree(&repository, work_dir.as_deref(), out)
+        }
+        Command::Commit {
+            message,
+            allow_empty,
+        } => actions::commit::commit(&repository(&core)?, message.as_deref(), allow_empty, out),
+    }
+}
+
 fn main() {
-    /*
-    Using main for tests at the moment.
-    */
-    let gal_core = GaalCore::new(GaalCoreDirectoryBuild::default());
-    let repository =
-        gal_core.derive_from_path(["", "home", "aliyss"].map(|x| x.to_string()).to_vec());
-    let usable_repo = match repository {
-        Ok(repo) => repo,
-        Err(e) => {
-            println!("{:?}", e.to_string());
-            return;
-        }
-    };
-
-    let data = "Subject Hello\n\
-            From Alice\n\
-            To Bob\n\
-            MultilineField This is a\n \
-             multiline\n \
-             message\n\
-            \n\
-            This is the message \n\
-            body";
-    let gaal_commit = GaalCoreDirectoryObjectInit::new_object("tree".to_string(), data.to_string());
-
-    let x = usable_repo.object_write(gaal_commit).unwrap();
-    let y = usable_repo.object_read(x);
-    match y {
-        Ok(data) => println!("{:?}", data),
-        Err(e) => {
-            println!("{:?}", e.to_string());
-        }
-    }
-
-    // let repository = gal_core.init(vec!["/home/aliyss".to_string()]);
-    // match repository {
-    //     Ok(_) => println!("Repository created"),
-    //     Err(e) => {
-    //         println!("{:?}", e.to_string())
-    //     }
-    // }
+    let cli = Cli::parse();
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+
+    if let Err(e) = run(cli.command, &mut out) {
+        eprintln!("gaal: {}", e);
+        std::process::exit(1);
+    }
 }